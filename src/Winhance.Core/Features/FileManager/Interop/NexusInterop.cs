@@ -106,6 +106,12 @@ namespace Winhance.Core.Features.FileManager.Interop
         public ulong Size;
         [MarshalAs(UnmanagedType.I1)]
         public bool IsDir;
+        /// <summary>
+        /// True when NUL characters in the path or name were replaced with '_',
+        /// so the path no longer names the file.
+        /// </summary>
+        [MarshalAs(UnmanagedType.I1)]
+        public bool PathModified;
 
         public string GetPath() => Marshal.PtrToStringAnsi(Path) ?? string.Empty;
 
@@ -1,6 +1,9 @@
 //! Benchmarks for the indexer module
 
 use criterion::{criterion_group, criterion_main, Criterion};
+use nexus_core::indexer::{ContentHasher, EntryFilter};
+use nexus_core::{FileEntry, IndexConfig};
+use std::io::Write;
 
 fn indexer_benchmark(c: &mut Criterion) {
     c.bench_function("placeholder", |b| {
@@ -11,5 +14,158 @@ fn indexer_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, indexer_benchmark);
+/// The per-entry filter loop `FastIndexer` used before `EntryFilter`
+fn naive_should_include(config: &IndexConfig, entry: &FileEntry) -> bool {
+    if !config.include_hidden && entry.is_hidden {
+        return false;
+    }
+    if !config.include_system && entry.is_system {
+        return false;
+    }
+    if config.exclude_dirs.iter().any(|e| entry.path.contains(e)) {
+        return false;
+    }
+    if !config.extensions.is_empty() && !entry.is_dir {
+        match &entry.extension {
+            Some(ext)
+                if config
+                    .extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(ext)) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Filtering 100k entries, naive loop vs compiled `EntryFilter`
+///
+/// `default` uses the stock config (10 excludes, no extension list);
+/// `heavy` adds developer-style excludes and an extension allow-list,
+/// where the automaton and sorted extension list pay off.
+fn filter_benchmark(c: &mut Criterion) {
+    let entries: Vec<FileEntry> = (0..100_000)
+        .map(|i| FileEntry {
+            path: format!(
+                "C:\\Users\\user\\Documents\\project_{}\\file_{}.txt",
+                i % 100,
+                i
+            ),
+            extension: Some("txt".to_string()),
+            ..Default::default()
+        })
+        .collect();
+
+    let default = IndexConfig::default();
+    let mut heavy = IndexConfig::default();
+    heavy.exclude_dirs.extend(
+        [
+            "node_modules",
+            ".git",
+            "target",
+            "bin\\Debug",
+            "obj",
+            "__pycache__",
+            ".vs",
+            ".idea",
+            "AppData\\Local\\Temp",
+            "Temporary Internet Files",
+            ".cache",
+            "dist",
+        ]
+        .map(String::from),
+    );
+    heavy.extensions = [
+        "txt", "md", "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "csv", "json", "xml",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    for (name, config) in [("default", &default), ("heavy", &heavy)] {
+        let filter = EntryFilter::new(config);
+        let mut group = c.benchmark_group(format!("should_include_100k/{}", name));
+        group.bench_function("naive", |b| {
+            b.iter(|| {
+                entries
+                    .iter()
+                    .filter(|e| naive_should_include(config, e))
+                    .count()
+            })
+        });
+        group.bench_function("entry_filter", |b| {
+            b.iter(|| entries.iter().filter(|e| filter.matches(e)).count())
+        });
+        group.finish();
+    }
+}
+
+/// Buffered vs memory-mapped SHA-256 on one large file
+///
+/// Set `NEXUS_BENCH_HASH_MB` to benchmark a multi-GB file (default 256MB).
+/// The page cache is warm after the first iteration, so this measures the
+/// syscall/copy overhead rather than disk throughput.
+fn hashing_benchmark(c: &mut Criterion) {
+    let size_mb: usize = std::env::var("NEXUS_BENCH_HASH_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(256);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.bin");
+    let mut file = std::fs::File::create(&path).unwrap();
+    let chunk: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    for _ in 0..size_mb {
+        file.write_all(&chunk).unwrap();
+    }
+    drop(file);
+
+    let max_size = u64::MAX;
+    let buffered = ContentHasher::new(max_size);
+    let mapped = ContentHasher::new(max_size).with_mmap(true);
+
+    let mut group = c.benchmark_group("sha256_large_file");
+    group.sample_size(10);
+    group.bench_function("buffered", |b| {
+        b.iter(|| std::hint::black_box(buffered.sha256_hash(&path)))
+    });
+    group.bench_function("mmap", |b| {
+        b.iter(|| std::hint::black_box(mapped.sha256_hash(&path)))
+    });
+    group.finish();
+}
+
+/// `files_equal` on two identical files, hashed vs compared byte-for-byte
+///
+/// Identical content is the worst case for both paths: hashing can't stop
+/// at the quick hash and comparison reads to the end.
+fn direct_compare_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let hashed = ContentHasher::default().with_direct_compare_threshold(0);
+    let direct = ContentHasher::default().with_direct_compare_threshold(u64::MAX);
+
+    let mut group = c.benchmark_group("files_equal");
+    for size in [256usize, 1024, 4 * 1024, 16 * 1024, 64 * 1024] {
+        let content: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        let left = dir.path().join(format!("{}_left.bin", size));
+        let right = dir.path().join(format!("{}_right.bin", size));
+        std::fs::write(&left, &content).unwrap();
+        std::fs::write(&right, &content).unwrap();
+
+        group.bench_function(format!("hashed/{}", size), |b| {
+            b.iter(|| std::hint::black_box(hashed.files_equal(&left, &right)))
+        });
+        group.bench_function(format!("direct/{}", size), |b| {
+            b.iter(|| std::hint::black_box(direct.files_equal(&left, &right)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    indexer_benchmark,
+    filter_benchmark,
+    hashing_benchmark,
+    direct_compare_benchmark
+);
 criterion_main!(benches);
@@ -0,0 +1,26 @@
+//! Bakes the git commit into `nexus_version` when building from a checkout
+
+use std::process::Command;
+
+fn main() {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .map(|s| s.trim().to_string())
+    };
+
+    // Rebuild when HEAD moves
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/index", git_dir);
+    }
+
+    let suffix = git(&["rev-parse", "--short", "HEAD"])
+        .map(|hash| format!("+{}", hash))
+        .unwrap_or_default();
+    println!("cargo:rustc-env=NEXUS_GIT_SUFFIX={}", suffix);
+}
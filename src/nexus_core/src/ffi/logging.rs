@@ -0,0 +1,100 @@
+//! Forwarding of `tracing` events to an FFI log callback
+//!
+//! A .NET host has no `tracing` subscriber, so without this every warning
+//! the indexer logs is lost. The subscriber is installed once; the callback
+//! and level live in statics so the host can swap or silence them later.
+
+use once_cell::sync::Lazy;
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Log callback: `level` as in [`level_number`], `message` valid only for
+/// the duration of the call
+pub(super) type LogCallback = extern "C" fn(level: i32, message: *const c_char);
+
+pub(super) static LOG_CALLBACK: Lazy<Mutex<Option<LogCallback>>> = Lazy::new(|| Mutex::new(None));
+
+/// Most verbose level forwarded; info by default
+pub(super) static LOG_LEVEL: AtomicI32 = AtomicI32::new(3);
+
+static INSTALLED: Lazy<bool> = Lazy::new(|| {
+    tracing_subscriber::registry()
+        .with(CallbackLayer)
+        .try_init()
+        .is_ok()
+});
+
+/// Install the forwarding subscriber; false if another global subscriber
+/// was set first
+pub(super) fn install() -> bool {
+    *INSTALLED
+}
+
+/// 1 = error, 2 = warn, 3 = info, 4 = debug, 5 = trace
+pub(super) fn level_number(level: &Level) -> i32 {
+    match *level {
+        Level::ERROR => 1,
+        Level::WARN => 2,
+        Level::INFO => 3,
+        Level::DEBUG => 4,
+        Level::TRACE => 5,
+    }
+}
+
+struct CallbackLayer;
+
+impl<S: Subscriber> Layer<S> for CallbackLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // A field's Debug impl can panic; the code that logged must not
+        // unwind because of it, least of all across an FFI call
+        let _ = catch_unwind(AssertUnwindSafe(|| forward(event)));
+    }
+}
+
+fn forward(event: &Event<'_>) {
+    let level = level_number(event.metadata().level());
+    if level > LOG_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(callback) = LOG_CALLBACK.lock().ok().and_then(|cb| *cb) else {
+        return;
+    };
+
+    let mut visitor = MessageVisitor::default();
+    event.record(&mut visitor);
+    let text = format!(
+        "{}: {}{}",
+        event.metadata().target(),
+        visitor.message,
+        visitor.fields
+    );
+    let text = CString::new(text.replace('\0', " ")).unwrap_or_default();
+
+    // `text` is freed when this returns; the host must copy it to keep it
+    callback(level, text.as_ptr());
+}
+
+/// Formats the `message` field followed by `key=value` for other fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
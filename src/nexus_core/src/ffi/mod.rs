@@ -2,18 +2,36 @@
 //!
 //! Provides C-compatible functions for calling from C# via P/Invoke.
 //! Pure performance - zero-copy where possible, minimal allocations.
+//!
+//! Path arguments come in two forms: `*const c_char` UTF-8 for portable
+//! callers, and `_w` variants taking NUL-terminated UTF-16. Windows hosts
+//! should prefer the `_w` variants: a .NET string marshals to them without
+//! conversion, and paths with unpaired surrogates, which have no UTF-8
+//! form, can still be opened. Paths reported back (entries, results) are
+//! UTF-8 either way.
 
 // FFI functions intentionally take raw pointers and handle safety internally
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-use crate::indexer::ContentHasher;
-use crate::{FileEntry, IndexConfig};
+mod logging;
+
+use crate::indexer::{
+    ContentHasher, HashFormat, JournalPosition, ProgressInterval, ProgressThrottle,
+    ScanProgressCallback, UsnJournal,
+};
+use crate::search::{
+    most_recent, CsvColumn, FileTypeAliases, MultiTermMatcher, NameCollator, PathScope, RecentBy,
+};
+use crate::{ExtensionMatch, FileEntry, IndexConfig, ScanMode};
 use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 // Global state for FFI - thread-safe cached results
 static LAST_ERROR: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
@@ -24,6 +42,129 @@ static SEARCH_RESULTS: Lazy<Mutex<Vec<FileEntry>>> = Lazy::new(|| Mutex::new(Vec
 static PROGRESS_CURRENT: AtomicU64 = AtomicU64::new(0);
 static PROGRESS_TOTAL: AtomicU64 = AtomicU64::new(0);
 static INDEXING_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Set by `nexus_cancel_indexing`, cleared when the next index starts
+static CANCEL_INDEXING: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
+static PROGRESS_STATE: Lazy<Mutex<ProgressState>> =
+    Lazy::new(|| Mutex::new(ProgressState::default()));
+static PROGRESS_THROTTLE: Lazy<ProgressThrottle> = Lazy::new(ProgressThrottle::default);
+
+/// Throughput is re-sampled at most this often
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Weight of the newest sample in the smoothed rate; lower is steadier
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// Timing and phase details behind `nexus_get_progress_json`
+#[derive(Debug, Default)]
+struct ProgressState {
+    phase: &'static str,
+    /// Progress as of the last report that reached the callback
+    current: u64,
+    total: u64,
+    drive: Option<char>,
+    started: Option<Instant>,
+    /// Time and count of the last throughput sample
+    last_sample: Option<(Instant, u64)>,
+    /// Exponential moving average of the sampled rate
+    files_per_sec: f64,
+}
+
+/// Progress snapshot serialized by `nexus_get_progress_json`
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressSnapshot {
+    phase: &'static str,
+    current: u64,
+    total: u64,
+    drive: Option<char>,
+    files_per_sec: f64,
+    elapsed_ms: u64,
+    eta_ms: u64,
+}
+
+/// Runtime configuration for the FFI layer, set via `nexus_init_with_config`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct FfiConfig {
+    /// Hard cap on results materialized by a single search call
+    max_search_results: u32,
+    /// BCP-47 locale for name sorting, e.g. `"de"`; unset sorts naturally
+    locale: Option<String>,
+    /// Format of hash strings returned by the hashing functions
+    hash_format: HashFormat,
+    /// Columns written by `nexus_export_results_csv`, e.g.
+    /// `["path", "size", "isDir"]`
+    csv_columns: Vec<CsvColumn>,
+    /// Shorter quick-search queries are skipped without scanning; 0 searches
+    /// on every keystroke
+    min_query_length: u32,
+    /// Leave entries whose path or name contains a NUL out of search
+    /// results instead of returning them with `path_modified` set
+    skip_nul_paths: bool,
+    /// Seed for every quick (xxHash3) hash. Quick hashes taken with one
+    /// seed never match those taken with another, so changing it
+    /// invalidates any the host has persisted
+    quick_hash_seed: u64,
+    /// How often the progress callback fires during an operation, e.g.
+    /// `{"entries": 10000, "millis": 100}`; the final report always does
+    progress_interval: ProgressInterval,
+    /// File type categories for `nexus_search_types`, added to the
+    /// built-in ones, e.g. `{"photos": ["jpg", "heic"]}`; a built-in name
+    /// replaces that category
+    file_type_aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for FfiConfig {
+    fn default() -> Self {
+        Self {
+            max_search_results: 100_000,
+            locale: None,
+            hash_format: HashFormat::HexLower,
+            csv_columns: CsvColumn::DEFAULT.to_vec(),
+            min_query_length: 2,
+            skip_nul_paths: false,
+            quick_hash_seed: 0,
+            progress_interval: ProgressInterval::default(),
+            file_type_aliases: BTreeMap::new(),
+        }
+    }
+}
+
+static FFI_CONFIG: Lazy<Mutex<FfiConfig>> = Lazy::new(|| Mutex::new(FfiConfig::default()));
+
+fn ffi_config() -> FfiConfig {
+    FFI_CONFIG
+        .lock()
+        .map(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// Built-in file type categories plus the configured ones
+fn ffi_file_types() -> FileTypeAliases {
+    ffi_config()
+        .file_type_aliases
+        .iter()
+        .fold(FileTypeAliases::default(), |aliases, (name, extensions)| {
+            aliases.with_alias(name, extensions)
+        })
+}
+
+/// Unlimited-size hasher with the configured seed and hash format
+fn ffi_hasher() -> ContentHasher {
+    let config = ffi_config();
+    ContentHasher::with_seed(u64::MAX, config.quick_hash_seed).with_hash_format(config.hash_format)
+}
+
+/// When the cached entries were last fully indexed, and the USN journal
+/// position of each drive at the start of that scan
+#[derive(Debug, Default)]
+struct IndexFreshness {
+    completed: Option<Instant>,
+    watermarks: std::collections::HashMap<char, JournalPosition>,
+}
+
+static INDEX_FRESHNESS: Lazy<Mutex<IndexFreshness>> =
+    Lazy::new(|| Mutex::new(IndexFreshness::default()));
 
 // Progress callback type
 type ProgressCallback = extern "C" fn(current: u64, total: u64, phase: *const c_char);
@@ -35,6 +176,58 @@ fn set_error(msg: String) {
     }
 }
 
+/// Read a NUL-terminated UTF-16 path argument, recording why it's
+/// unusable in the last error
+///
+/// On Windows the units are taken as-is, unpaired surrogates included;
+/// elsewhere they must be valid UTF-16.
+fn wide_arg(ptr: *const u16, what: &str) -> Option<PathBuf> {
+    if ptr.is_null() {
+        set_error(format!("{} is null", what));
+        return None;
+    }
+    let units = unsafe {
+        let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+        std::slice::from_raw_parts(ptr, len)
+    };
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_wide(units)))
+    }
+    #[cfg(not(windows))]
+    {
+        match String::from_utf16(units) {
+            Ok(s) => Some(PathBuf::from(s)),
+            Err(_) => {
+                set_error(format!("{} is not valid UTF-16", what));
+                None
+            }
+        }
+    }
+}
+
+/// Borrow a C string argument, recording why it's unusable in the last
+/// error; `what` names the argument in the message
+fn str_arg<'a>(ptr: *const c_char, what: &str) -> Option<&'a str> {
+    if ptr.is_null() {
+        set_error(format!("{} is null", what));
+        return None;
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            set_error(format!(
+                "{} is not valid UTF-8 (invalid byte at offset {})",
+                what,
+                e.valid_up_to()
+            ));
+            None
+        }
+    }
+}
+
 // Static phase strings to avoid lifetime issues in FFI callbacks
 // Using static byte arrays ensures the pointers remain valid
 #[allow(dead_code)]
@@ -45,24 +238,112 @@ static PHASE_SEARCHING: &[u8] = b"searching\0";
 static PHASE_COMPLETE: &[u8] = b"complete\0";
 #[allow(dead_code)]
 static PHASE_HASHING: &[u8] = b"hashing\0";
+static PHASE_CANCELLED: &[u8] = b"cancelled\0";
 #[allow(dead_code)]
 static PHASE_UNKNOWN: &[u8] = b"unknown\0";
 
-#[allow(dead_code)]
 fn get_phase_ptr(phase: &str) -> *const c_char {
     match phase {
         "indexing" => PHASE_INDEXING.as_ptr() as *const c_char,
         "searching" => PHASE_SEARCHING.as_ptr() as *const c_char,
         "complete" => PHASE_COMPLETE.as_ptr() as *const c_char,
         "hashing" => PHASE_HASHING.as_ptr() as *const c_char,
+        "cancelled" => PHASE_CANCELLED.as_ptr() as *const c_char,
         _ => PHASE_UNKNOWN.as_ptr() as *const c_char,
     }
 }
 
-#[allow(dead_code)]
+/// Reset progress and start the clock for a new operation
+fn begin_progress(phase: &'static str) {
+    PROGRESS_CURRENT.store(0, Ordering::SeqCst);
+    PROGRESS_TOTAL.store(0, Ordering::SeqCst);
+    PROGRESS_THROTTLE.reset();
+    if let Ok(mut state) = PROGRESS_STATE.lock() {
+        let now = Instant::now();
+        *state = ProgressState {
+            phase,
+            current: 0,
+            total: 0,
+            drive: None,
+            started: Some(now),
+            last_sample: Some((now, 0)),
+            files_per_sec: 0.0,
+        };
+    }
+}
+
+/// Record which drive the current progress refers to
+fn set_progress_drive(drive: Option<char>) {
+    if let Ok(mut state) = PROGRESS_STATE.lock() {
+        state.drive = drive;
+    }
+}
+
+/// Update the phase and the throughput sample
+fn update_progress_state(current: u64, total: u64, phase: &str) {
+    let Ok(mut state) = PROGRESS_STATE.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    state.phase = phase_name(phase);
+    state.current = current;
+    state.total = total;
+
+    match state.last_sample {
+        Some((at, count)) => {
+            let elapsed = now.duration_since(at);
+            if elapsed >= RATE_SAMPLE_INTERVAL {
+                let rate = current.saturating_sub(count) as f64 / elapsed.as_secs_f64();
+                state.files_per_sec = if state.files_per_sec > 0.0 {
+                    RATE_SMOOTHING * rate + (1.0 - RATE_SMOOTHING) * state.files_per_sec
+                } else {
+                    rate
+                };
+                state.last_sample = Some((now, current));
+            }
+        }
+        None => {
+            state.started.get_or_insert(now);
+            state.last_sample = Some((now, current));
+        }
+    }
+}
+
+/// Map a phase to its static name, as used by the callback phase strings
+fn phase_name(phase: &str) -> &'static str {
+    match phase {
+        "indexing" => "indexing",
+        "searching" => "searching",
+        "complete" => "complete",
+        "hashing" => "hashing",
+        "cancelled" => "cancelled",
+        _ => "unknown",
+    }
+}
+
+/// Record progress, and call the callback at most as often as the
+/// configured `progress_interval` allows; "complete" and "cancelled"
+/// always get through
 fn report_progress(current: u64, total: u64, phase: &str) {
+    let is_final = phase == "complete" || phase == "cancelled";
+    if !is_final && !PROGRESS_THROTTLE.is_due(current) {
+        record_progress(current, total, phase);
+        return;
+    }
+    publish_progress(current, total, phase);
+}
+
+/// Record progress for the getters without calling the callback
+fn record_progress(current: u64, total: u64, phase: &str) {
     PROGRESS_CURRENT.store(current, Ordering::SeqCst);
     PROGRESS_TOTAL.store(total, Ordering::SeqCst);
+    update_progress_state(current, total, phase);
+}
+
+/// Record progress and call the callback unconditionally, for reports
+/// the indexer has already paced
+fn publish_progress(current: u64, total: u64, phase: &str) {
+    record_progress(current, total, phase);
 
     if let Ok(cb) = PROGRESS_CALLBACK.lock() {
         if let Some(callback) = *cb {
@@ -73,21 +354,116 @@ fn report_progress(current: u64, total: u64, phase: &str) {
     }
 }
 
+/// Crate version, plus the git commit when built from a checkout
+static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), env!("NEXUS_GIT_SUFFIX"), "\0");
+
+/// Get the nexus_core version, e.g. `"0.1.0+1a2b3c4"`
+///
+/// Returns a static string: do NOT free it with `nexus_free_string`.
+/// Check it before calling other functions to confirm the loaded library
+/// matches the host's bindings.
+#[no_mangle]
+pub extern "C" fn nexus_version() -> *const c_char {
+    VERSION.as_ptr() as *const c_char
+}
+
 /// Initialize the indexer with default configuration
 #[no_mangle]
 pub extern "C" fn nexus_init() -> bool {
     true
 }
 
+/// Initialize with a JSON configuration, e.g. `{"max_search_results": 50000}`
+///
+/// Missing fields keep their defaults. Returns false on invalid JSON.
+#[no_mangle]
+pub extern "C" fn nexus_init_with_config(config_json: *const c_char) -> bool {
+    let Some(json) = str_arg(config_json, "config JSON") else {
+        return false;
+    };
+
+    match serde_json::from_str::<FfiConfig>(json) {
+        Ok(config) => {
+            PROGRESS_THROTTLE.set_interval(config.progress_interval);
+            if let Ok(mut current) = FFI_CONFIG.lock() {
+                *current = config;
+            }
+            true
+        }
+        Err(e) => {
+            set_error(format!("Invalid config: {}", e));
+            false
+        }
+    }
+}
+
+/// Returned by `nexus_index_all*` when the scan was stopped by
+/// `nexus_cancel_indexing`; the previous index is kept
+pub const INDEX_CANCELLED: i64 = -3;
+
 /// Index all configured drives and return count
+///
+/// Returns [`INDEX_CANCELLED`] (-3) if cancelled.
 #[no_mangle]
 pub extern "C" fn nexus_index_all() -> i64 {
-    match crate::indexer::FastIndexer::new(IndexConfig::default()).index_all() {
+    index_all_with(IndexConfig::default())
+}
+
+/// Index all configured drives with a scan mode and return count
+///
+/// `mode` 0 = quick (names, sizes, timestamps), 1 = deep (also content
+/// hashes). Returns -1 for an unknown mode and [`INDEX_CANCELLED`] if
+/// cancelled.
+#[no_mangle]
+pub extern "C" fn nexus_index_all_mode(mode: i32) -> i64 {
+    let mode = match mode {
+        0 => ScanMode::Quick,
+        1 => ScanMode::Deep,
+        _ => {
+            set_error(format!("Unknown scan mode: {}", mode));
+            return -1;
+        }
+    };
+    index_all_with(IndexConfig::default().with_scan_mode(mode))
+}
+
+fn index_all_with(mut config: IndexConfig) -> i64 {
+    let ffi = ffi_config();
+    config.progress_interval = ffi.progress_interval;
+    // Hashes are looked up in the configured format later
+    config.hash_format = ffi.hash_format;
+    // The previous index is the best guess at how many entries to expect
+    config.expected_entries = CACHED_ENTRIES
+        .lock()
+        .ok()
+        .map(|cache| cache.len() as u64)
+        .filter(|&len| len > 0);
+    let _active = begin_indexing(&mut config);
+
+    // Taken before the scan, so changes made during it count as pending
+    let watermarks: std::collections::HashMap<char, JournalPosition> = config
+        .drives
+        .iter()
+        .filter_map(|&drive| Some((drive, UsnJournal::new(drive).position().ok()?)))
+        .collect();
+
+    match crate::indexer::FastIndexer::new(config).index_all_cancellable(CANCEL_INDEXING.clone()) {
+        Ok((entries, stats)) if stats.cancelled => {
+            // A partial scan would pass for a complete, fresh index
+            let count = entries.len() as u64;
+            report_progress(count, count, "cancelled");
+            INDEX_CANCELLED
+        }
         Ok((entries, _stats)) => {
             let count = entries.len() as i64;
+            report_progress(count as u64, count as u64, "complete");
             if let Ok(mut cache) = CACHED_ENTRIES.lock() {
                 *cache = entries;
             }
+            if let Ok(mut freshness) = INDEX_FRESHNESS.lock() {
+                freshness.completed = Some(Instant::now());
+                freshness.watermarks = watermarks;
+            }
             count
         }
         Err(e) => {
@@ -97,6 +473,262 @@ pub extern "C" fn nexus_index_all() -> i64 {
     }
 }
 
+/// Ask a running `nexus_index_all` to stop
+///
+/// The scan stops soon after, even while paused on battery, and returns
+/// [`INDEX_CANCELLED`]; the entries it found are discarded and the previous
+/// index stays cached. Does nothing when no index is running.
+#[no_mangle]
+pub extern "C" fn nexus_cancel_indexing() {
+    CANCEL_INDEXING.store(true, Ordering::SeqCst);
+}
+
+/// Write the current search results to a CSV file
+///
+/// Columns come from the `csv_columns` config setting (path, name, size,
+/// modified, extension by default). The file is overwritten. Returns the
+/// number of rows written, or -1 on error.
+#[no_mangle]
+pub extern "C" fn nexus_export_results_csv(path: *const c_char) -> i64 {
+    let Some(path) = str_arg(path, "input path") else {
+        return -1;
+    };
+    export_results_csv_to(Path::new(path))
+}
+
+/// UTF-16 variant of `nexus_export_results_csv`
+#[no_mangle]
+pub extern "C" fn nexus_export_results_csv_w(path: *const u16) -> i64 {
+    let Some(path) = wide_arg(path, "input path") else {
+        return -1;
+    };
+    export_results_csv_to(&path)
+}
+
+fn export_results_csv_to(path: &Path) -> i64 {
+    let results = match SEARCH_RESULTS.lock() {
+        Ok(results) => results,
+        Err(_) => return -1,
+    };
+    let written = std::fs::File::create(path)
+        .map_err(crate::NexusError::from)
+        .and_then(|file| {
+            crate::search::export_entries_csv(
+                results.iter(),
+                &ffi_config().csv_columns,
+                std::io::BufWriter::new(file),
+            )
+        });
+
+    match written {
+        Ok(()) => results.len() as i64,
+        Err(e) => {
+            set_error(format!("Failed to export {}: {}", path.display(), e));
+            -1
+        }
+    }
+}
+
+/// Describe the fields of the search index at `index_path` as JSON
+///
+/// Returns an array of `{"name", "kind", "searchable", "sortable",
+/// "stored"}` objects, so a query UI can build its filters without
+/// hard-coding field names. An index created by an older version lacks
+/// some fields; pass null for the fields of a new index instead. Returns
+/// null on failure. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_schema_json(index_path: *const c_char) -> *mut c_char {
+    if index_path.is_null() {
+        return to_json_string(&crate::search::SearchEngine::default_schema_info());
+    }
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return ptr::null_mut();
+    };
+
+    match crate::search::SearchEngine::open_reader_only(index_path) {
+        Ok(engine) => to_json_string(&engine.schema_info()),
+        Err(e) => {
+            set_error(format!("Failed to read schema: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Merge the search index at `index_path` into one segment, reclaiming
+/// the space of deleted documents
+///
+/// Rewrites the whole index: slow and disk-heavy on large indexes, so call
+/// it when idle. Fails (returning false) if another process is writing to
+/// the index.
+#[no_mangle]
+pub extern "C" fn nexus_optimize_index(index_path: *const c_char) -> bool {
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return false;
+    };
+    if !Path::new(index_path).exists() {
+        set_error(format!("No index at {}", index_path));
+        return false;
+    }
+
+    match crate::search::SearchEngine::new(index_path).and_then(|engine| engine.optimize()) {
+        Ok(()) => true,
+        Err(e) => {
+            set_error(format!("Failed to optimize index: {}", e));
+            false
+        }
+    }
+}
+
+/// Size in bytes of the search index at `index_path` on disk
+///
+/// Returns 0 on failure, including when there is no index at the path.
+#[no_mangle]
+pub extern "C" fn nexus_index_size(index_path: *const c_char) -> u64 {
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return 0;
+    };
+
+    match crate::search::SearchEngine::open_reader_only(index_path)
+        .and_then(|engine| engine.index_size_bytes())
+    {
+        Ok(size) => size,
+        Err(e) => {
+            set_error(format!("Failed to measure index: {}", e));
+            0
+        }
+    }
+}
+
+/// Replace the tags of `path` in the search index at `index_path`
+///
+/// `tags_json` is a JSON array of strings; `[]` removes all tags. Tags are
+/// kept in a store next to the index, so they are reapplied when `path` is
+/// indexed again. The cached entry for `path`, if any, is updated too.
+/// Fails (returning false) if another process is writing to the index.
+#[no_mangle]
+pub extern "C" fn nexus_set_tags(
+    index_path: *const c_char,
+    path: *const c_char,
+    tags_json: *const c_char,
+) -> bool {
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return false;
+    };
+    let Some(path) = str_arg(path, "path") else {
+        return false;
+    };
+    let Some(tags_json) = str_arg(tags_json, "tags_json") else {
+        return false;
+    };
+    let tags: Vec<String> = match serde_json::from_str(tags_json) {
+        Ok(tags) => tags,
+        Err(e) => {
+            set_error(format!("Invalid tag list: {}", e));
+            return false;
+        }
+    };
+
+    let stored =
+        match crate::search::SearchEngine::new(index_path).and_then(|e| e.set_tags(path, &tags)) {
+            Ok(stored) => stored,
+            Err(e) => {
+                set_error(format!("Failed to set tags: {}", e));
+                return false;
+            }
+        };
+
+    if let Ok(mut cache) = CACHED_ENTRIES.lock() {
+        if let Some(entry) = cache.iter_mut().find(|e| e.path == path) {
+            entry.tags = stored;
+        }
+    }
+    true
+}
+
+/// Tags of `path` in the search index at `index_path`, as a JSON array
+///
+/// Returns null on failure. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_tags(index_path: *const c_char, path: *const c_char) -> *mut c_char {
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return ptr::null_mut();
+    };
+    let Some(path) = str_arg(path, "path") else {
+        return ptr::null_mut();
+    };
+
+    match crate::search::SearchEngine::open_reader_only(index_path) {
+        Ok(engine) => to_json_string(&engine.tags(path)),
+        Err(e) => {
+            set_error(format!("Failed to read tags: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Every tag in the search index at `index_path` with how many files carry
+/// it, as a JSON object (`{"invoice": 12}`)
+///
+/// Returns null on failure. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_list_tags(index_path: *const c_char) -> *mut c_char {
+    let Some(index_path) = str_arg(index_path, "index_path") else {
+        return ptr::null_mut();
+    };
+
+    match crate::search::SearchEngine::open_reader_only(index_path) {
+        Ok(engine) => to_json_string(&engine.all_tags()),
+        Err(e) => {
+            set_error(format!("Failed to read tags: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Milliseconds since the last completed `nexus_index_all*` call
+///
+/// Returns `u64::MAX` if no full index has completed yet.
+#[no_mangle]
+pub extern "C" fn nexus_index_age_ms() -> u64 {
+    INDEX_FRESHNESS
+        .lock()
+        .ok()
+        .and_then(|f| f.completed)
+        .map(|completed| completed.elapsed().as_millis() as u64)
+        .unwrap_or(u64::MAX)
+}
+
+/// Count file system changes on a drive since it was last fully indexed
+///
+/// `drive` is the drive letter as a UTF-16 code unit, e.g. `'C'`. Reads the
+/// drive's USN journal from where it stood when that index started.
+/// Returns -1 if the drive wasn't in the last full index, has no USN
+/// journal, or the journal has been reset or wrapped since, in which case
+/// the index should be rebuilt; see `nexus_get_last_error`.
+#[no_mangle]
+pub extern "C" fn nexus_count_pending_changes(drive: u16) -> i64 {
+    let Some(drive) = char::from_u32(drive as u32).map(|d| d.to_ascii_uppercase()) else {
+        set_error(format!("Invalid drive letter: {}", drive));
+        return -1;
+    };
+    let watermark = INDEX_FRESHNESS
+        .lock()
+        .ok()
+        .and_then(|f| f.watermarks.get(&drive).copied());
+    let Some(watermark) = watermark else {
+        set_error(format!("No USN position recorded for drive {}", drive));
+        return -1;
+    };
+
+    match UsnJournal::new(drive).count_changes_since(&watermark) {
+        Ok(count) => count as i64,
+        Err(e) => {
+            set_error(e.to_string());
+            -1
+        }
+    }
+}
+
 /// Free a string allocated by Rust
 #[no_mangle]
 pub extern "C" fn nexus_free_string(s: *mut c_char) {
@@ -123,65 +755,129 @@ pub extern "C" fn nexus_get_last_error() -> *mut c_char {
 /// Index a specific directory
 #[no_mangle]
 pub extern "C" fn nexus_index_directory(path: *const c_char) -> i64 {
-    if path.is_null() {
+    let Some(path_str) = str_arg(path, "input path") else {
         return -1;
-    }
+    };
+    index_directory_at(Path::new(path_str), crate::IndexDirectoryOptions::default())
+}
 
-    let path_str = unsafe {
-        match CStr::from_ptr(path).to_str() {
-            Ok(s) => s,
-            Err(_) => return -1,
-        }
+/// UTF-16 variant of `nexus_index_directory`
+#[no_mangle]
+pub extern "C" fn nexus_index_directory_w(path: *const u16) -> i64 {
+    let Some(path) = wide_arg(path, "input path") else {
+        return -1;
+    };
+    index_directory_at(&path, crate::IndexDirectoryOptions::default())
+}
+
+/// Index only the direct children of a directory (non-recursive)
+#[no_mangle]
+pub extern "C" fn nexus_index_directory_shallow(path: *const c_char) -> i64 {
+    let Some(path_str) = str_arg(path, "input path") else {
+        return -1;
     };
+    index_directory_at(Path::new(path_str), SHALLOW)
+}
+
+/// UTF-16 variant of `nexus_index_directory_shallow`
+#[no_mangle]
+pub extern "C" fn nexus_index_directory_shallow_w(path: *const u16) -> i64 {
+    let Some(path) = wide_arg(path, "input path") else {
+        return -1;
+    };
+    index_directory_at(&path, SHALLOW)
+}
 
-    match crate::indexer::FastIndexer::new(IndexConfig::default()).index_directory(path_str) {
-        Ok(entries) => entries.len() as i64,
+const SHALLOW: crate::IndexDirectoryOptions = crate::IndexDirectoryOptions {
+    recursive: false,
+    max_depth: None,
+};
+
+fn index_directory_at(path: &Path, options: crate::IndexDirectoryOptions) -> i64 {
+    let ffi = ffi_config();
+    let mut config = IndexConfig {
+        progress_interval: ffi.progress_interval,
+        hash_format: ffi.hash_format,
+        ..Default::default()
+    };
+    let _active = begin_indexing(&mut config);
+
+    match crate::indexer::FastIndexer::new(config).index_directory_with(path, options) {
+        Ok(entries) => {
+            let count = entries.len() as u64;
+            report_progress(count, count, "complete");
+            count as i64
+        }
         Err(_) => -1,
     }
 }
 
+/// Clears `INDEXING_ACTIVE` when an index call returns
+struct IndexingActive;
+
+impl Drop for IndexingActive {
+    fn drop(&mut self) {
+        INDEXING_ACTIVE.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Mark indexing as active, clear any earlier cancel, reset progress, and
+/// route the indexer's progress reports to the progress callback
+fn begin_indexing(config: &mut IndexConfig) -> IndexingActive {
+    INDEXING_ACTIVE.store(true, Ordering::SeqCst);
+    // Cleared as the scan is claimed, so a cancel from here on stops it
+    CANCEL_INDEXING.store(false, Ordering::SeqCst);
+    begin_progress("indexing");
+    config.progress_callback = Some(ScanProgressCallback::new(|current, total, drive| {
+        set_progress_drive(drive);
+        publish_progress(current, total, "indexing")
+    }));
+    IndexingActive
+}
+
 /// Search result structure for FFI
+///
+/// C strings can't hold NUL, so any NUL in the path or name is replaced
+/// with `_` and `path_modified` is set: such a path no longer names the
+/// file. Set `skip_nul_paths` in the config to drop these entries from
+/// results instead.
 #[repr(C)]
 pub struct FfiSearchResult {
     pub path: *mut c_char,
     pub name: *mut c_char,
     pub size: u64,
     pub is_dir: bool,
+    /// `path` or `name` differs from the indexed string
+    pub path_modified: bool,
 }
 
 impl FfiSearchResult {
     fn from_entry(entry: &FileEntry) -> Self {
-        // Safely convert strings, replacing null bytes if present to avoid silent corruption
-        let safe_path = entry.path.replace('\0', "_");
-        let safe_name = entry.name.replace('\0', "_");
-
         Self {
-            path: match CString::new(safe_path) {
-                Ok(s) => s.into_raw(),
-                Err(e) => {
-                    set_error(format!(
-                        "Invalid path string contains null byte at position {}",
-                        e.nul_position()
-                    ));
-                    ptr::null_mut()
-                }
-            },
-            name: match CString::new(safe_name) {
-                Ok(s) => s.into_raw(),
-                Err(e) => {
-                    set_error(format!(
-                        "Invalid name string contains null byte at position {}",
-                        e.nul_position()
-                    ));
-                    ptr::null_mut()
-                }
-            },
+            path: nul_free_c_string(&entry.path),
+            name: nul_free_c_string(&entry.name),
             size: entry.size,
             is_dir: entry.is_dir,
+            path_modified: has_nul(entry),
         }
     }
 }
 
+fn nul_free_c_string(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', "_"))
+        .unwrap_or_default()
+        .into_raw()
+}
+
+fn has_nul(entry: &FileEntry) -> bool {
+    entry.path.contains('\0') || entry.name.contains('\0')
+}
+
+/// Whether `entry` may appear in search results under `skip_nul_paths`
+fn representable(entry: &FileEntry, skip_nul_paths: bool) -> bool {
+    !(skip_nul_paths && has_nul(entry))
+}
+
 /// Free a search result
 #[no_mangle]
 pub extern "C" fn nexus_free_result(result: *mut FfiSearchResult) {
@@ -218,6 +914,54 @@ pub extern "C" fn nexus_clear_progress_callback() {
     }
 }
 
+/// Set how often the progress callback fires: every `entries` entries or
+/// every `millis` milliseconds, whichever comes first
+///
+/// 0 turns a limit off; both 0 reports every entry. Same as the
+/// `progress_interval` config setting. Defaults to 10000 entries / 100ms.
+#[no_mangle]
+pub extern "C" fn nexus_set_progress_interval(entries: u64, millis: u64) {
+    let interval = ProgressInterval { entries, millis };
+    PROGRESS_THROTTLE.set_interval(interval);
+    if let Ok(mut config) = FFI_CONFIG.lock() {
+        config.progress_interval = interval;
+    }
+}
+
+/// Forward the crate's log output to `callback`
+///
+/// `callback(level, message)` receives 1 = error, 2 = warn, 3 = info,
+/// 4 = debug, 5 = trace, and a NUL-terminated UTF-8 message that is only
+/// valid during the call: copy it, don't keep the pointer. It may be called
+/// from any thread. Returns false if a different log subscriber is already
+/// installed in the process.
+#[no_mangle]
+pub extern "C" fn nexus_set_log_callback(callback: logging::LogCallback) -> bool {
+    if let Ok(mut cb) = logging::LOG_CALLBACK.lock() {
+        *cb = Some(callback);
+    }
+    if !logging::install() {
+        set_error("Another log subscriber is already installed".to_string());
+        return false;
+    }
+    true
+}
+
+/// Stop forwarding log output
+#[no_mangle]
+pub extern "C" fn nexus_clear_log_callback() {
+    if let Ok(mut cb) = logging::LOG_CALLBACK.lock() {
+        *cb = None;
+    }
+}
+
+/// Set the most verbose level forwarded to the log callback (1 = error
+/// ... 5 = trace, 0 = off); defaults to 3 (info)
+#[no_mangle]
+pub extern "C" fn nexus_set_log_level(level: i32) {
+    logging::LOG_LEVEL.store(level.clamp(0, 5), Ordering::Relaxed);
+}
+
 /// Get current progress (returns current count)
 #[no_mangle]
 pub extern "C" fn nexus_get_progress_current() -> u64 {
@@ -236,30 +980,236 @@ pub extern "C" fn nexus_is_indexing() -> bool {
     INDEXING_ACTIVE.load(Ordering::SeqCst)
 }
 
+/// Whether the machine is running on battery power
+///
+/// False when plugged in, without a battery, or if the state is unknown.
+/// Lets the host hold off starting a full index on laptops.
+#[no_mangle]
+pub extern "C" fn nexus_is_on_battery() -> bool {
+    crate::indexer::is_on_battery()
+}
+
+/// Estimated milliseconds until the current operation completes
+///
+/// Based on the smoothed rate and the remaining `total - current`. Returns 0
+/// while the total or rate is not yet known, and once done.
+#[no_mangle]
+pub extern "C" fn nexus_get_progress_eta_ms() -> u64 {
+    let rate = PROGRESS_STATE
+        .lock()
+        .map(|state| state.files_per_sec)
+        .unwrap_or(0.0);
+    eta_ms(
+        PROGRESS_CURRENT.load(Ordering::SeqCst),
+        PROGRESS_TOTAL.load(Ordering::SeqCst),
+        rate,
+    )
+}
+
+fn eta_ms(current: u64, total: u64, files_per_sec: f64) -> u64 {
+    if total == 0 || current >= total || files_per_sec <= 0.0 {
+        return 0;
+    }
+    ((total - current) as f64 / files_per_sec * 1000.0) as u64
+}
+
+/// Get a consistent progress snapshot as JSON
+///
+/// Returns `{"phase", "current", "total", "drive", "filesPerSec", "elapsedMs",
+/// "etaMs"}`, read under one lock instead of racing the scalar getters.
+/// Updated on every report, including those `progress_interval` keeps
+/// from the callback. `filesPerSec` is a moving average of ~500ms samples;
+/// `drive` is null when not drive-specific; `etaMs` is as for
+/// `nexus_get_progress_eta_ms`. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_progress_json() -> *mut c_char {
+    let snapshot = match PROGRESS_STATE.lock() {
+        Ok(state) => ProgressSnapshot {
+            phase: if state.phase.is_empty() {
+                "unknown"
+            } else {
+                state.phase
+            },
+            current: state.current,
+            total: state.total,
+            drive: state.drive,
+            files_per_sec: state.files_per_sec,
+            elapsed_ms: state
+                .started
+                .map(|t| t.elapsed().as_millis() as u64)
+                .unwrap_or(0),
+            eta_ms: eta_ms(state.current, state.total, state.files_per_sec),
+        },
+        Err(_) => return ptr::null_mut(),
+    };
+
+    serde_json::to_string(&snapshot)
+        .ok()
+        .and_then(|json| CString::new(json).ok())
+        .map(|s| s.into_raw())
+        .unwrap_or(ptr::null_mut())
+}
+
 // ============================================================================
 // SEARCH FFI
 // ============================================================================
 
-/// Search cached entries by name pattern (case-insensitive)
+/// Clamp a requested result count to the configured maximum,
+/// recording a last-error note when the request was reduced
+fn clamp_max_results(requested: u32) -> usize {
+    let cap = ffi_config().max_search_results;
+    if requested > cap {
+        set_error(format!(
+            "max_results {} exceeds limit {}; results clamped (use nexus_search_page for large result sets)",
+            requested, cap
+        ));
+        cap as usize
+    } else {
+        requested as usize
+    }
+}
+
+/// Returned by the quick search when the query is below the configured
+/// minimum length, as distinct from a search that found nothing
+pub const QUERY_TOO_SHORT: i64 = -2;
+
+/// Search cached entries by name (case-insensitive)
 /// Returns number of results found
+///
+/// The query is split on whitespace and every term must match; quote a
+/// phrase (`"q3 final"`) to match it as one term. Terms containing `\` or
+/// `/` are matched against the full path instead of the name.
+/// Prefix a term with `-` to exclude entries containing it (`report -draft`);
+/// quote it (`"-v2"`) to search for a literal leading dash.
+///
+/// `max_results` is clamped to the configured `max_search_results`.
+/// Prefer `nexus_search_page` when a query may match many entries.
+///
+/// Returns [`QUERY_TOO_SHORT`] (-2) without searching when the trimmed
+/// query is shorter than the configured `min_query_length`.
 #[no_mangle]
 pub extern "C" fn nexus_search(query: *const c_char, max_results: u32) -> i64 {
-    if query.is_null() {
+    nexus_search_page(query, 0, max_results)
+}
+
+/// Search cached entries, returning one page of results
+/// Returns number of results in the page
+///
+/// Skips the first `offset` matches and keeps at most `limit` (clamped to
+/// the configured `max_search_results`). Results are read with
+/// `nexus_get_search_result` as usual. Queries below `min_query_length`
+/// return [`QUERY_TOO_SHORT`] and clear the previous results.
+#[no_mangle]
+pub extern "C" fn nexus_search_page(query: *const c_char, offset: u32, limit: u32) -> i64 {
+    let Some(query_str) = str_arg(query, "query") else {
         return -1;
-    }
+    };
 
-    let query_str = unsafe {
-        match CStr::from_ptr(query).to_str() {
-            Ok(s) => s.to_lowercase(),
-            Err(_) => return -1,
+    if query_str.trim().chars().count() < ffi_config().min_query_length as usize {
+        if let Ok(mut search_results) = SEARCH_RESULTS.lock() {
+            search_results.clear();
         }
+        return QUERY_TOO_SHORT;
+    }
+
+    quick_search(query_str, None, None, offset, limit)
+}
+
+/// Search the cached entries under a folder
+/// Returns number of results found
+///
+/// Same query syntax as `nexus_search`, restricted to entries inside
+/// `parent_path`: its direct children, or its whole subtree when
+/// `recursive`. A trailing separator on `parent_path` is ignored and on
+/// Windows it matches case-insensitively. The scope already bounds the
+/// results, so `min_query_length` doesn't apply and an empty query lists
+/// the folder.
+#[no_mangle]
+pub extern "C" fn nexus_search_in(
+    query: *const c_char,
+    parent_path: *const c_char,
+    recursive: bool,
+    max_results: u32,
+) -> i64 {
+    let Some(query_str) = str_arg(query, "query") else {
+        return -1;
+    };
+    let Some(parent) = str_arg(parent_path, "parent_path") else {
+        return -1;
+    };
+
+    let scope = PathScope::new(parent, recursive);
+    quick_search(query_str, Some(&scope), None, 0, max_results)
+}
+
+/// Search the cached entries of some file types
+/// Returns number of results found
+///
+/// Same query syntax as `nexus_search`. `file_types` is a comma-separated
+/// list of extensions and category names, e.g. `"video,pdf"`; see
+/// `nexus_get_file_type_categories` for the categories. Directories never
+/// match. The types already bound the results, so `min_query_length`
+/// doesn't apply and an empty query lists every file of those types.
+#[no_mangle]
+pub extern "C" fn nexus_search_types(
+    query: *const c_char,
+    file_types: *const c_char,
+    max_results: u32,
+) -> i64 {
+    let Some(query_str) = str_arg(query, "query") else {
+        return -1;
+    };
+    let Some(file_types) = str_arg(file_types, "file_types") else {
+        return -1;
     };
 
+    let types: Vec<String> = file_types
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect();
+    let types = ffi_file_types().expand(&types);
+    quick_search(query_str, None, Some(&types), 0, max_results)
+}
+
+/// File type categories accepted by `nexus_search_types`, as a JSON object
+/// of category name to extensions (`{"audio": ["mp3", ...], ...}`)
+///
+/// Includes any `file_type_aliases` from the config. Free with
+/// `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_file_type_categories() -> *mut c_char {
+    to_json_string(ffi_file_types().categories())
+}
+
+/// Replace the search results with one page of matching cached entries
+fn quick_search(
+    query: &str,
+    scope: Option<&PathScope>,
+    types: Option<&[String]>,
+    offset: u32,
+    limit: u32,
+) -> i64 {
+    let limit = clamp_max_results(limit);
+    let matcher = MultiTermMatcher::new(query);
+    let skip_nul_paths = ffi_config().skip_nul_paths;
+
     if let Ok(entries) = CACHED_ENTRIES.lock() {
         let results: Vec<FileEntry> = entries
             .iter()
-            .filter(|e| e.name.to_lowercase().contains(&query_str))
-            .take(max_results as usize)
+            .filter(|e| scope.is_none_or(|s| s.contains(&e.path)))
+            .filter(|e| {
+                types.is_none_or(|types| {
+                    !e.is_dir
+                        && types
+                            .iter()
+                            .any(|t| e.has_extension(t, ExtensionMatch::Equals))
+                })
+            })
+            .filter(|e| matcher.matches(e) && representable(e, skip_nul_paths))
+            .skip(offset as usize)
+            .take(limit)
             .cloned()
             .collect();
 
@@ -275,6 +1225,77 @@ pub extern "C" fn nexus_search(query: *const c_char, max_results: u32) -> i64 {
     }
 }
 
+/// Find cached entries with the given content hash
+/// Returns number of results
+///
+/// `hash` must be in the configured `hash_format`. Only entries indexed
+/// with hashes (deep scan, or `nexus_get_cached_hash`) can match. Results
+/// replace the current search results and are read with
+/// `nexus_get_search_result`.
+#[no_mangle]
+pub extern "C" fn nexus_find_by_hash(hash: *const c_char) -> i64 {
+    let Some(hash) = str_arg(hash, "hash") else {
+        return -1;
+    };
+
+    let skip_nul_paths = ffi_config().skip_nul_paths;
+
+    if let Ok(entries) = CACHED_ENTRIES.lock() {
+        let results: Vec<FileEntry> = entries
+            .iter()
+            .filter(|e| e.content_hash.as_deref() == Some(hash))
+            .filter(|e| representable(e, skip_nul_paths))
+            .cloned()
+            .collect();
+        let count = results.len() as i64;
+
+        if let Ok(mut search_results) = SEARCH_RESULTS.lock() {
+            *search_results = results;
+        }
+        count
+    } else {
+        -1
+    }
+}
+
+/// Replace the search results with the `count` most recent cached files
+/// Returns number of results
+///
+/// `by` picks the timestamp: 0 = created, 1 = modified. Newest first;
+/// directories and files without that timestamp are left out, so created
+/// times need an index scanned with timestamps. Unlike a date-range
+/// search this ignores any query. `count` is clamped to the configured
+/// `max_search_results`.
+#[no_mangle]
+pub extern "C" fn nexus_get_recent_files(count: u32, by: u32) -> i64 {
+    let by = match by {
+        0 => RecentBy::Created,
+        1 => RecentBy::Modified,
+        other => {
+            set_error(format!("Invalid recent-files timestamp: {}", other));
+            return -1;
+        }
+    };
+    let count = clamp_max_results(count);
+    let skip_nul_paths = ffi_config().skip_nul_paths;
+
+    if let Ok(entries) = CACHED_ENTRIES.lock() {
+        let candidates = entries.iter().filter(|e| representable(e, skip_nul_paths));
+        let results: Vec<FileEntry> = most_recent(candidates, count, by)
+            .into_iter()
+            .cloned()
+            .collect();
+        let count = results.len() as i64;
+
+        if let Ok(mut search_results) = SEARCH_RESULTS.lock() {
+            *search_results = results;
+        }
+        count
+    } else {
+        -1
+    }
+}
+
 /// Get search result at index
 #[no_mangle]
 pub extern "C" fn nexus_get_search_result(index: u32) -> *mut FfiSearchResult {
@@ -287,6 +1308,52 @@ pub extern "C" fn nexus_get_search_result(index: u32) -> *mut FfiSearchResult {
     ptr::null_mut()
 }
 
+/// Total size in bytes of the selected search results
+///
+/// `indices` points to `count` indices into the current search results.
+/// Out-of-range and repeated indices are ignored, as are directories.
+#[no_mangle]
+pub extern "C" fn nexus_sum_selected_size(indices: *const u32, count: u32) -> u64 {
+    if indices.is_null() || count == 0 {
+        return 0;
+    }
+
+    let indices = unsafe { std::slice::from_raw_parts(indices, count as usize) };
+    let mut seen = std::collections::HashSet::new();
+
+    if let Ok(results) = SEARCH_RESULTS.lock() {
+        indices
+            .iter()
+            .filter(|&&i| seen.insert(i))
+            .filter_map(|&i| results.get(i as usize))
+            .filter(|e| !e.is_dir)
+            .map(|e| e.size)
+            .sum()
+    } else {
+        0
+    }
+}
+
+/// Sort the current search results by name
+///
+/// Uses the locale from `nexus_init_with_config` (`{"locale": "de"}`) when
+/// set, otherwise natural order (`file2` before `file10`). Indices passed to
+/// `nexus_get_search_result` refer to the new order afterwards.
+#[no_mangle]
+pub extern "C" fn nexus_sort_results_by_name(descending: bool) -> bool {
+    let collator = NameCollator::new(ffi_config().locale.as_deref());
+
+    if let Ok(mut results) = SEARCH_RESULTS.lock() {
+        results.sort_by(|a, b| collator.compare(&a.name, &b.name));
+        if descending {
+            results.reverse();
+        }
+        true
+    } else {
+        false
+    }
+}
+
 /// Clear search results to free memory
 #[no_mangle]
 pub extern "C" fn nexus_clear_search_results() {
@@ -312,39 +1379,61 @@ pub struct FfiHashResult {
 /// Compute quick hash (xxHash3) for a file - fast for dedup pre-screening
 #[no_mangle]
 pub extern "C" fn nexus_hash_file_quick(path: *const c_char) -> u64 {
-    if path.is_null() {
+    let Some(path_str) = str_arg(path, "input path") else {
         return 0;
-    }
+    };
+    ffi_hasher().quick_hash(Path::new(path_str)).unwrap_or(0)
+}
 
-    let path_str = unsafe {
-        match CStr::from_ptr(path).to_str() {
-            Ok(s) => s,
-            Err(_) => return 0,
-        }
+/// UTF-16 variant of `nexus_hash_file_quick`
+#[no_mangle]
+pub extern "C" fn nexus_hash_file_quick_w(path: *const u16) -> u64 {
+    let Some(path) = wide_arg(path, "input path") else {
+        return 0;
+    };
+    ffi_hasher().quick_hash(&path).unwrap_or(0)
+}
+
+/// Quick hash (xxHash3) as a string in the configured `hash_format`
+///
+/// Returns null on failure. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_hash_file_quick_string(path: *const c_char) -> *mut c_char {
+    let Some(path_str) = str_arg(path, "input path") else {
+        return ptr::null_mut();
     };
 
-    let hasher = ContentHasher::new(u64::MAX);
+    let hasher = ffi_hasher();
     hasher
-        .quick_hash(std::path::Path::new(path_str))
-        .unwrap_or(0)
+        .quick_hash_string(std::path::Path::new(path_str))
+        .and_then(|hash| CString::new(hash).ok())
+        .map(|s| s.into_raw())
+        .unwrap_or(ptr::null_mut())
 }
 
 /// Compute full hash (SHA-256) for a file - for verification
+///
+/// Formatted per the configured `hash_format` (`"hexLower"` by default).
 #[no_mangle]
 pub extern "C" fn nexus_hash_file_full(path: *const c_char) -> *mut c_char {
-    if path.is_null() {
+    let Some(path_str) = str_arg(path, "input path") else {
         return ptr::null_mut();
-    }
+    };
+    full_hash_at(Path::new(path_str))
+}
 
-    let path_str = unsafe {
-        match CStr::from_ptr(path).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        }
+/// UTF-16 variant of `nexus_hash_file_full`
+#[no_mangle]
+pub extern "C" fn nexus_hash_file_full_w(path: *const u16) -> *mut c_char {
+    let Some(path) = wide_arg(path, "input path") else {
+        return ptr::null_mut();
     };
+    full_hash_at(&path)
+}
 
-    let hasher = ContentHasher::new(u64::MAX);
-    match hasher.full_hash(std::path::Path::new(path_str)) {
+fn full_hash_at(path: &Path) -> *mut c_char {
+    let hasher = ffi_hasher();
+    match hasher.full_hash(path) {
         Some((_size, hash)) => CString::new(hash)
             .map(|s| s.into_raw())
             .unwrap_or(ptr::null_mut()),
@@ -352,31 +1441,285 @@ pub extern "C" fn nexus_hash_file_full(path: *const c_char) -> *mut c_char {
     }
 }
 
-/// Find duplicates in cached entries by size+hash
-/// Returns count of duplicate groups found
+/// Get the content hash of an indexed file, hashing only if needed
+///
+/// Returns the SHA-256 `content_hash` recorded for `path` when the index was
+/// built with hashing (deep scan). Otherwise the file is hashed now, in the
+/// configured `hash_format`, and the result is stored in the cached entry
+/// for next time. Returns null if `path` is not in the index or can't be
+/// read. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_cached_hash(path: *const c_char) -> *mut c_char {
+    let Some(path_str) = str_arg(path, "input path") else {
+        return ptr::null_mut();
+    };
+
+    let cached = match CACHED_ENTRIES.lock() {
+        Ok(entries) => match entries.iter().find(|e| e.path == path_str) {
+            Some(entry) if !entry.is_dir => entry.content_hash.clone(),
+            _ => return ptr::null_mut(),
+        },
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let hash = match cached {
+        Some(hash) => hash,
+        None => {
+            // Hash without holding the lock
+            let hasher = ffi_hasher();
+            let Some(hash) = hasher.sha256_hash(std::path::Path::new(path_str)) else {
+                return ptr::null_mut();
+            };
+            if let Ok(mut entries) = CACHED_ENTRIES.lock() {
+                if let Some(entry) = entries.iter_mut().find(|e| e.path == path_str) {
+                    entry.content_hash = Some(hash.clone());
+                }
+            }
+            hash
+        }
+    };
+
+    CString::new(hash)
+        .map(|s| s.into_raw())
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Get a size treemap of the cached entries as JSON
+///
+/// Returns nested `{"path", "size", "isDir", "children"}` nodes, children
+/// largest first, with directory sizes summed recursively. Nodes more than
+/// `max_depth` levels below the root are left out (their sizes still count)
+/// to keep the payload small. Several drives are joined under a root with
+/// an empty path. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_treemap_json(max_depth: u32) -> *mut c_char {
+    let mut tree = match CACHED_ENTRIES.lock() {
+        Ok(entries) => crate::indexer::treemap::build_treemap(&entries),
+        Err(_) => return ptr::null_mut(),
+    };
+    tree.truncate(max_depth as usize);
+    to_json_string(&tree)
+}
+
+/// Hashing threads used unless `nexus_set_hash_parallelism` says otherwise;
+/// hashing is bound by disk I/O, so more threads mostly add seek contention
+const DEFAULT_HASH_PARALLELISM: usize = 4;
+
+/// Dedicated pool for duplicate hashing, kept apart from the global pool
+/// indexing runs on
+static HASH_POOL: Lazy<Mutex<Option<Arc<rayon::ThreadPool>>>> = Lazy::new(|| Mutex::new(None));
+
+fn hash_pool() -> Option<Arc<rayon::ThreadPool>> {
+    let mut pool = HASH_POOL.lock().ok()?;
+    if pool.is_none() {
+        *pool = build_hash_pool(DEFAULT_HASH_PARALLELISM);
+    }
+    pool.clone()
+}
+
+fn build_hash_pool(threads: usize) -> Option<Arc<rayon::ThreadPool>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("nexus-hash-{}", i))
+        .build()
+        .map(Arc::new)
+        .map_err(|e| set_error(format!("Failed to create hashing pool: {}", e)))
+        .ok()
+}
+
+/// Set how many files duplicate detection hashes at once
+///
+/// Defaults to 4. Lower values keep the system responsive during large
+/// scans; higher values help on fast SSDs. `0` restores the default.
+/// Hashing already in progress finishes on the old pool. Returns false if
+/// the pool couldn't be created.
+#[no_mangle]
+pub extern "C" fn nexus_set_hash_parallelism(threads: u32) -> bool {
+    let threads = match threads {
+        0 => DEFAULT_HASH_PARALLELISM,
+        n => n as usize,
+    };
+    let Some(new_pool) = build_hash_pool(threads) else {
+        return false;
+    };
+    match HASH_POOL.lock() {
+        Ok(mut pool) => {
+            *pool = Some(new_pool);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Candidate size groups from the last `nexus_find_duplicates`, resolved to
+/// content groups on first access
+static DUPLICATE_CANDIDATES: Lazy<Mutex<Vec<SizeGroup>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Same-size files that may be duplicates
+struct SizeGroup {
+    entries: Vec<FileEntry>,
+    /// Content groups, once hashed
+    resolved: Option<Vec<crate::indexer::dedup::DuplicateGroup>>,
+}
+
+/// Find duplicate candidates in cached entries
+/// Returns count of candidate groups found
+///
+/// Only groups files by size, so it returns at once; no file is read.
+/// Hashing is deferred to `nexus_get_duplicate_group`, so a candidate group
+/// can turn out to hold no duplicates, or several sets of them. Groups are
+/// ordered by potential savings, largest first.
 #[no_mangle]
 pub extern "C" fn nexus_find_duplicates(min_size: u64) -> i64 {
     use std::collections::HashMap;
 
     if let Ok(entries) = CACHED_ENTRIES.lock() {
         // Group by size first (fast pre-filter)
-        let mut size_groups: HashMap<u64, Vec<&FileEntry>> = HashMap::new();
+        let mut size_groups: HashMap<u64, Vec<FileEntry>> = HashMap::new();
 
         for entry in entries.iter() {
-            if !entry.is_dir && entry.size >= min_size {
-                size_groups.entry(entry.size).or_default().push(entry);
+            if !entry.is_dir && !entry.is_offline && entry.size > 0 && entry.size >= min_size {
+                size_groups
+                    .entry(entry.size)
+                    .or_default()
+                    .push(entry.clone());
             }
         }
 
-        // Count groups with potential duplicates (same size, 2+ files)
-        let dup_groups: i64 = size_groups.values().filter(|g| g.len() > 1).count() as i64;
+        // Groups with potential duplicates (same size, 2+ files)
+        let mut groups: Vec<SizeGroup> = size_groups
+            .into_values()
+            .filter(|g| g.len() > 1)
+            .map(|entries| SizeGroup {
+                entries,
+                resolved: None,
+            })
+            .collect();
+        groups.sort_by_key(|g| {
+            let size = g.entries[0].size;
+            (std::cmp::Reverse(size * (g.entries.len() as u64 - 1)), size)
+        });
 
-        dup_groups
+        let count = groups.len() as i64;
+        if let Ok(mut candidates) = DUPLICATE_CANDIDATES.lock() {
+            *candidates = groups;
+        }
+        count
     } else {
         -1
     }
 }
 
+/// Get the duplicates in a candidate group from `nexus_find_duplicates`
+///
+/// Returns a JSON array of `{"size", "hash", "paths", "isHardlinked",
+/// "reclaimableBytes"}` groups, empty if the candidates turn out to differ,
+/// or null for an invalid index. The first call for a group hashes its
+/// files and may take a while for large files; later calls are served from
+/// cache. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_get_duplicate_group(index: u32) -> *mut c_char {
+    use crate::indexer::dedup::{find_duplicates, DedupOptions};
+
+    let entries = match DUPLICATE_CANDIDATES.lock() {
+        Ok(candidates) => match candidates.get(index as usize) {
+            Some(SizeGroup {
+                resolved: Some(groups),
+                ..
+            }) => return to_json_string(groups),
+            Some(group) => group.entries.clone(),
+            None => return ptr::null_mut(),
+        },
+        Err(_) => return ptr::null_mut(),
+    };
+
+    // Hash without holding the lock so other groups stay accessible
+    let hasher = ffi_hasher();
+    let options = DedupOptions::default();
+    let groups = match hash_pool() {
+        Some(pool) => pool.install(|| find_duplicates(&entries, &hasher, &options)),
+        None => find_duplicates(&entries, &hasher, &options),
+    };
+    let json = to_json_string(&groups);
+
+    if let Ok(mut candidates) = DUPLICATE_CANDIDATES.lock() {
+        // Skip if a new search replaced the candidates meanwhile
+        if let Some(group) = candidates.get_mut(index as usize) {
+            if group.entries.len() == entries.len()
+                && group
+                    .entries
+                    .iter()
+                    .zip(&entries)
+                    .all(|(a, b)| a.path == b.path)
+            {
+                group.resolved = Some(groups);
+            }
+        }
+    }
+    json
+}
+
+fn to_json_string<T: serde::Serialize + ?Sized>(value: &T) -> *mut c_char {
+    serde_json::to_string(value)
+        .ok()
+        .and_then(|json| CString::new(json).ok())
+        .map(|s| s.into_raw())
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Move files to the Recycle Bin
+///
+/// `paths_json` is a JSON array of paths. Returns a JSON array with one
+/// `{"path", "error"}` object per input path, `error` null on success, or
+/// null if `paths_json` is invalid. Recycled paths are dropped from the
+/// cached entries. Free with `nexus_free_string`.
+#[no_mangle]
+pub extern "C" fn nexus_recycle_files(paths_json: *const c_char) -> *mut c_char {
+    #[derive(serde::Serialize)]
+    struct RecycleResult {
+        path: String,
+        error: Option<String>,
+    }
+
+    let Some(json) = str_arg(paths_json, "path list") else {
+        return ptr::null_mut();
+    };
+    let paths: Vec<String> = match serde_json::from_str(json) {
+        Ok(paths) => paths,
+        Err(e) => {
+            set_error(format!("Invalid path list: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    let path_refs: Vec<&std::path::Path> = paths.iter().map(std::path::Path::new).collect();
+    let results: Vec<RecycleResult> = crate::indexer::ops::recycle(&path_refs)
+        .into_iter()
+        .zip(&paths)
+        .map(|(result, path)| RecycleResult {
+            path: path.clone(),
+            error: result.err().map(|e| e.to_string()),
+        })
+        .collect();
+
+    let recycled: std::collections::HashSet<&str> = results
+        .iter()
+        .filter(|r| r.error.is_none())
+        .map(|r| r.path.as_str())
+        .collect();
+    if !recycled.is_empty() {
+        if let Ok(mut cache) = CACHED_ENTRIES.lock() {
+            cache.retain(|e| !recycled.contains(e.path.as_str()));
+        }
+    }
+
+    serde_json::to_string(&results)
+        .ok()
+        .and_then(|json| CString::new(json).ok())
+        .map(|s| s.into_raw())
+        .unwrap_or(ptr::null_mut())
+}
+
 // ============================================================================
 // STATISTICS FFI
 // ============================================================================
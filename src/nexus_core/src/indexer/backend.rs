@@ -0,0 +1,181 @@
+//! Scanning backends
+//!
+//! A backend only enumerates the raw entries under a root. Filtering,
+//! hashing, counting and the `max_entries` cap stay in
+//! [`FastIndexer`](super::FastIndexer), which tries its backends in order
+//! and falls back to the next one when a backend can't scan a root.
+
+use super::{drive_letter, MetadataExtractor, MftReader, MftScanOptions};
+use crate::{FileEntry, NexusError, Result};
+use rayon::prelude::*;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Receives each scanned entry; returning false asks the backend to stop
+pub type EmitFn<'a> = dyn Fn(FileEntry) -> bool + Sync + 'a;
+
+/// A way of enumerating the entries under a drive or directory
+pub trait IndexBackend: Send + Sync {
+    /// Short name for logs, e.g. `"mft"`
+    fn name(&self) -> &'static str;
+
+    /// Stream the entries under `root` (a drive root like `C:\`, or a
+    /// directory) to `emit`, possibly from several threads at once
+    ///
+    /// Return an error only when `root` can't be scanned at all, before
+    /// anything is emitted, so the indexer can fall back to another backend
+    /// without seeing entries twice. Unreadable entries are skipped.
+    fn scan_each(&self, root: &Path, emit: &EmitFn) -> Result<()>;
+
+    /// Whether entries carry created, modified and accessed times
+    fn has_timestamps(&self) -> bool {
+        true
+    }
+
+    /// Rough number of entries under `root`, when known without scanning;
+    /// used as the progress total of a first scan
+    fn estimate_entries(&self, _root: &Path) -> Option<u64> {
+        None
+    }
+
+    /// Collect the entries under `root`, in no particular order
+    fn scan(&self, root: &Path) -> Result<Vec<FileEntry>> {
+        let entries = parking_lot::Mutex::new(Vec::new());
+        self.scan_each(root, &|entry| {
+            entries.lock().push(entry);
+            true
+        })?;
+        Ok(entries.into_inner())
+    }
+}
+
+/// Reads the NTFS Master File Table of a whole volume
+///
+/// Only drive roots can be scanned; anything else is an error, as is a
+/// volume that isn't NTFS or can't be opened (which needs elevation).
+/// Entries have no size or times unless `resolve_sizes` is set.
+#[derive(Debug, Clone, Default)]
+pub struct MftBackend {
+    options: MftScanOptions,
+}
+
+impl MftBackend {
+    pub fn new(options: MftScanOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl IndexBackend for MftBackend {
+    fn name(&self) -> &'static str {
+        "mft"
+    }
+
+    fn scan_each(&self, root: &Path, emit: &EmitFn) -> Result<()> {
+        let drive = drive_of_root(root).ok_or_else(|| {
+            NexusError::InvalidPath(format!("not a drive root: {}", root.display()))
+        })?;
+
+        MftReader::enumerate_while(drive, &self.options, emit)?;
+        Ok(())
+    }
+
+    fn has_timestamps(&self) -> bool {
+        self.options.resolve_sizes
+    }
+
+    /// The MFT record count, an overestimate
+    fn estimate_entries(&self, root: &Path) -> Option<u64> {
+        MftReader::record_count(drive_of_root(root)?).ok()
+    }
+}
+
+/// Letter of a bare drive root (`C:`, `C:\`, `\\?\C:\`)
+fn drive_of_root(root: &Path) -> Option<char> {
+    let root = root.to_str()?.trim_end_matches(['\\', '/']);
+    let letter = drive_letter(root)?;
+    root.ends_with(':').then_some(letter)
+}
+
+/// Walks the directory tree in parallel, reading each entry's metadata
+///
+/// Works on any file system and for any directory.
+#[derive(Default)]
+pub struct WalkdirBackend {
+    max_depth: Option<usize>,
+    metadata_extractor: MetadataExtractor,
+}
+
+impl WalkdirBackend {
+    /// Walk at most `max_depth` levels below the root (None = no limit)
+    pub fn new(max_depth: Option<usize>) -> Self {
+        Self {
+            max_depth,
+            metadata_extractor: MetadataExtractor::new(),
+        }
+    }
+}
+
+impl IndexBackend for WalkdirBackend {
+    fn name(&self) -> &'static str {
+        "walkdir"
+    }
+
+    fn scan_each(&self, root: &Path, emit: &EmitFn) -> Result<()> {
+        let mut walker = WalkDir::new(root).follow_links(false);
+        if let Some(depth) = self.max_depth {
+            walker = walker.max_depth(depth);
+        }
+
+        // Short-circuits once `emit` asks to stop
+        let _ = walker
+            .into_iter()
+            .par_bridge()
+            .filter_map(|e| e.ok())
+            .try_for_each(|entry| {
+                let Some(file_entry) = self.metadata_extractor.extract(entry.path()) else {
+                    return Ok(());
+                };
+                if emit(file_entry) {
+                    Ok(())
+                } else {
+                    Err(())
+                }
+            });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_walkdir_scan() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/a.txt"), b"a").unwrap();
+
+        let names: Vec<String> = WalkdirBackend::default()
+            .scan(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert!(names.contains(&"sub".to_string()));
+        assert!(names.contains(&"a.txt".to_string()));
+
+        let shallow = WalkdirBackend::new(Some(1)).scan(dir.path()).unwrap();
+        assert!(shallow.iter().all(|e| e.name != "a.txt"));
+    }
+
+    #[test]
+    fn test_mft_needs_drive_root() {
+        assert_eq!(drive_of_root(Path::new("c:\\")), Some('C'));
+        assert_eq!(drive_of_root(Path::new(r"\\?\D:")), Some('D'));
+        assert_eq!(drive_of_root(Path::new("C:\\Users")), None);
+
+        let err = MftBackend::default().scan(Path::new("C:\\Users"));
+        assert!(matches!(err, Err(NexusError::InvalidPath(_))));
+    }
+}
@@ -3,11 +3,62 @@
 //! Provides fast content hashing using xxHash for quick comparison
 //! and SHA-256 for verification.
 
+use super::metadata_extractor::is_offline_path;
+use memmap2::Mmap;
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
-use xxhash_rust::xxh3::xxh3_64;
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+/// String format for hashes handed to consumers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HashFormat {
+    /// `9f86d08...`
+    #[default]
+    HexLower,
+    /// `9F86D08...`
+    HexUpper,
+    /// Standard base64 with padding
+    Base64,
+    /// Algorithm-prefixed lowercase hex: `sha256:9f86d08...`, `xxh3:...`
+    Prefixed,
+}
+
+impl HashFormat {
+    /// Format digest bytes produced by `algorithm` (e.g. `"sha256"`)
+    pub fn format(&self, algorithm: &str, digest: &[u8]) -> String {
+        use base64::Engine;
+        use std::fmt::Write;
+
+        let hex = |upper: bool| {
+            digest
+                .iter()
+                .fold(String::with_capacity(digest.len() * 2), |mut out, b| {
+                    let _ = if upper {
+                        write!(out, "{:02X}", b)
+                    } else {
+                        write!(out, "{:02x}", b)
+                    };
+                    out
+                })
+        };
+
+        match self {
+            HashFormat::HexLower => hex(false),
+            HashFormat::HexUpper => hex(true),
+            HashFormat::Base64 => base64::engine::general_purpose::STANDARD.encode(digest),
+            HashFormat::Prefixed => format!("{}:{}", algorithm, hex(false)),
+        }
+    }
+
+    /// Format a quick (xxHash3) hash; the value is rendered big-endian, so
+    /// hex output matches `{:016x}`
+    pub fn format_quick(&self, hash: u64) -> String {
+        self.format("xxh3", &hash.to_be_bytes())
+    }
+}
 
 /// Content hasher for file deduplication
 pub struct ContentHasher {
@@ -15,6 +66,18 @@ pub struct ContentHasher {
     max_size: u64,
     /// Buffer size for reading files
     buffer_size: usize,
+    /// Hash files at or above `mmap_threshold` through a memory map
+    use_mmap: bool,
+    /// Minimum file size for the mmap path (in bytes)
+    mmap_threshold: u64,
+    /// Output format for string hashes
+    hash_format: HashFormat,
+    /// Files smaller than this are compared byte-for-byte, not hashed
+    direct_compare_threshold: u64,
+    /// Seed for quick (xxHash3) hashes
+    seed: u64,
+    /// Also drop trailing whitespace in `text_normalized_hash`
+    trim_trailing_whitespace: bool,
 }
 
 impl ContentHasher {
@@ -23,11 +86,134 @@ impl ContentHasher {
         Self {
             max_size,
             buffer_size: 64 * 1024, // 64KB buffer
+            use_mmap: false,
+            mmap_threshold: 16 * 1024 * 1024, // 16MB
+            hash_format: HashFormat::default(),
+            direct_compare_threshold: 4 * 1024, // 4KB
+            seed: 0,
+            trim_trailing_whitespace: false,
+        }
+    }
+
+    /// Create a content hasher whose quick hashes use `seed`
+    ///
+    /// Seed 0 gives the same hashes as [`ContentHasher::new`]. Quick hashes
+    /// are only comparable between hashers with the same seed, so changing
+    /// it invalidates any persisted quick hashes. SHA-256 is unaffected.
+    pub fn with_seed(max_size: u64, seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::new(max_size)
+        }
+    }
+
+    /// The configured quick-hash seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Set the string format of SHA-256 and quick-hash strings
+    pub fn with_hash_format(mut self, format: HashFormat) -> Self {
+        self.hash_format = format;
+        self
+    }
+
+    /// The configured string format
+    pub fn hash_format(&self) -> HashFormat {
+        self.hash_format
+    }
+
+    /// Enable or disable memory-mapped hashing for large files
+    ///
+    /// Mapping avoids a read syscall per 64KB chunk and lets the OS manage
+    /// paging, which pays off for multi-GB local files. It is
+    /// counterproductive for small files (mapping setup costs more than a
+    /// few reads) and unsafe for network files, where a dropped connection
+    /// surfaces as an in-page fault instead of an I/O error, so UNC paths
+    /// always use buffered reads. If mapping fails, hashing falls back to
+    /// buffered reads.
+    pub fn with_mmap(mut self, use_mmap: bool) -> Self {
+        self.use_mmap = use_mmap;
+        self
+    }
+
+    /// Set the minimum file size hashed through a memory map
+    pub fn with_mmap_threshold(mut self, threshold: u64) -> Self {
+        self.mmap_threshold = threshold;
+        self
+    }
+
+    /// Set the size below which files are compared byte-for-byte
+    ///
+    /// For small files, reading both and comparing is cheaper than two
+    /// quick hashes plus two SHA-256 passes. Applies to `files_equal` and
+    /// the dedup funnel. `0` always hashes.
+    pub fn with_direct_compare_threshold(mut self, threshold: u64) -> Self {
+        self.direct_compare_threshold = threshold;
+        self
+    }
+
+    /// Make `text_normalized_hash` ignore trailing whitespace as well as
+    /// line endings
+    pub fn with_trim_trailing_whitespace(mut self, trim: bool) -> Self {
+        self.trim_trailing_whitespace = trim;
+        self
+    }
+
+    /// The configured direct-compare threshold
+    pub fn direct_compare_threshold(&self) -> u64 {
+        self.direct_compare_threshold
+    }
+
+    /// Read a whole file for direct comparison
+    ///
+    /// Returns `None` for offline placeholders and files that aren't below
+    /// the direct-compare threshold.
+    pub(crate) fn read_small(&self, path: &Path) -> Option<Vec<u8>> {
+        if is_offline_path(path) {
+            return None;
         }
+        let mut file = File::open(path).ok()?;
+        let len = file.metadata().ok()?.len();
+        if len >= self.direct_compare_threshold || len > self.max_size {
+            return None;
+        }
+        let mut content = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut content).ok()?;
+        Some(content)
+    }
+
+    /// SHA-256 of in-memory content, formatted like `sha256_hash`
+    pub(crate) fn sha256_of(&self, content: &[u8]) -> String {
+        self.hash_format.format("sha256", &Sha256::digest(content))
+    }
+
+    /// Map the file if the mmap path applies to it
+    fn try_map(&self, file: &File, path: &Path, len: u64) -> Option<Mmap> {
+        if !self.use_mmap || len < self.mmap_threshold || len == 0 {
+            return None;
+        }
+        let path_str = path.to_string_lossy();
+        let is_network = path_str.starts_with(r"\\?\UNC\")
+            || (path_str.starts_with(r"\\") && !path_str.starts_with(r"\\?\"));
+        if is_network {
+            return None;
+        }
+        // SAFETY: the map is read-only and dropped before returning. A file
+        // truncated concurrently by another process can still fault; that is
+        // the documented trade-off of opting into mmap.
+        unsafe { Mmap::map(file) }.ok()
     }
 
     /// Compute a fast hash (xxHash3) for quick comparison
+    ///
+    /// Returns `None` for offline / cloud placeholder files, since reading
+    /// them would trigger a download.
     pub fn quick_hash(&self, path: &Path) -> Option<u64> {
+        if is_offline_path(path) {
+            return None;
+        }
+
         let file = File::open(path).ok()?;
         let metadata = file.metadata().ok()?;
 
@@ -35,15 +221,31 @@ impl ContentHasher {
             return None;
         }
 
+        if let Some(map) = self.try_map(&file, path, metadata.len()) {
+            return Some(xxh3_64_with_seed(&map, self.seed));
+        }
+
         let mut reader = BufReader::with_capacity(self.buffer_size, file);
         let mut buffer = Vec::new();
         reader.read_to_end(&mut buffer).ok()?;
 
-        Some(xxh3_64(&buffer))
+        Some(xxh3_64_with_seed(&buffer, self.seed))
     }
 
     /// Compute a SHA-256 hash for verification
+    ///
+    /// Returns `None` for offline / cloud placeholder files.
     pub fn sha256_hash(&self, path: &Path) -> Option<String> {
+        self.sha256_digest(path)
+            .map(|digest| self.hash_format.format("sha256", &digest))
+    }
+
+    /// Raw SHA-256 digest of a file's content
+    fn sha256_digest(&self, path: &Path) -> Option<[u8; 32]> {
+        if is_offline_path(path) {
+            return None;
+        }
+
         let file = File::open(path).ok()?;
         let metadata = file.metadata().ok()?;
 
@@ -51,6 +253,10 @@ impl ContentHasher {
             return None;
         }
 
+        if let Some(map) = self.try_map(&file, path, metadata.len()) {
+            return Some(Sha256::digest(&map[..]).into());
+        }
+
         let mut reader = BufReader::with_capacity(self.buffer_size, file);
         let mut hasher = Sha256::new();
         let mut buffer = vec![0u8; self.buffer_size];
@@ -63,8 +269,219 @@ impl ContentHasher {
             hasher.update(&buffer[..bytes_read]);
         }
 
-        let result = hasher.finalize();
-        Some(format!("{:x}", result))
+        Some(hasher.finalize().into())
+    }
+
+    /// SHA-256 of a file's content with text normalized, so copies that
+    /// differ only in line endings hash the same
+    ///
+    /// Files whose first 8KB contain a NUL byte are taken to be binary and
+    /// hashed as is, giving the same digest as `sha256_hash`. For anything
+    /// else, each CRLF pair and each lone CR becomes LF. With
+    /// `with_trim_trailing_whitespace`, spaces and tabs before each line
+    /// break and at the end of the file are dropped too, as are line breaks
+    /// at the end of the file, so `"a \r\n"` and `"a"` match. Nothing else
+    /// changes: a byte order mark, the encoding, blank lines within the text
+    /// and leading whitespace all still count. UTF-16 text contains NULs and
+    /// so is hashed as binary.
+    ///
+    /// Returns `None` for offline placeholders, unreadable files and files
+    /// above `max_size`.
+    pub fn text_normalized_hash(&self, path: &Path) -> Option<String> {
+        if is_offline_path(path) {
+            return None;
+        }
+
+        let file = File::open(path).ok()?;
+        if file.metadata().ok()?.len() > self.max_size {
+            return None;
+        }
+
+        let mut reader = BufReader::with_capacity(self.buffer_size, file);
+        let mut buffer = vec![0u8; self.buffer_size.max(TEXT_SNIFF_LEN)];
+
+        // Reads may come up short, so fill the sniff window before deciding
+        let mut filled = 0;
+        while filled < TEXT_SNIFF_LEN {
+            match reader.read(&mut buffer[filled..]).ok()? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        let is_binary = buffer[..filled.min(TEXT_SNIFF_LEN)].contains(&0);
+        let mut normalizer =
+            (!is_binary).then(|| TextNormalizer::new(self.trim_trailing_whitespace));
+
+        let mut hasher = Sha256::new();
+        while filled > 0 {
+            match normalizer.as_mut() {
+                Some(normalizer) => normalizer.feed(&buffer[..filled], &mut hasher),
+                None => hasher.update(&buffer[..filled]),
+            }
+            filled = reader.read(&mut buffer).ok()?;
+        }
+        // Whatever the normalizer still holds back is trailing, and trimmed
+
+        Some(self.hash_format.format("sha256", &hasher.finalize()))
+    }
+
+    /// Compute a single hash for a whole directory tree
+    ///
+    /// Covers structure as well as content: every file, subdirectory and
+    /// symlink contributes a leaf built from its path relative to `path`
+    /// (with `/` separators, so copies on Windows and Unix compare equal)
+    /// and, for files, the SHA-256 of its content. Renaming, moving or adding
+    /// an empty directory therefore changes the hash; timestamps and
+    /// attributes do not. Leaves are sorted by relative path and folded
+    /// pairwise into a Merkle root, so the result is deterministic.
+    ///
+    /// Files are hashed in parallel. Returns `None` if `path` is not a
+    /// directory or any file can't be hashed (unreadable, offline, or above
+    /// `max_size`), since a partial hash can't verify the tree.
+    pub fn hash_directory(&self, path: &Path) -> Option<String> {
+        use rayon::prelude::*;
+        use walkdir::WalkDir;
+
+        if !path.is_dir() {
+            return None;
+        }
+
+        let entries: Vec<walkdir::DirEntry> = WalkDir::new(path)
+            .follow_links(false)
+            .min_depth(1)
+            .into_iter()
+            .collect::<std::result::Result<_, _>>()
+            .ok()?;
+
+        let mut leaves: Vec<(String, [u8; 32])> = entries
+            .par_iter()
+            .map(|entry| {
+                let relative = entry
+                    .path()
+                    .strip_prefix(path)
+                    .ok()?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                let mut leaf = Sha256::new();
+                let file_type = entry.file_type();
+                if file_type.is_symlink() {
+                    let target = std::fs::read_link(entry.path()).ok()?;
+                    leaf.update(b"L");
+                    leaf.update(relative.as_bytes());
+                    leaf.update([0]);
+                    leaf.update(target.to_string_lossy().as_bytes());
+                } else if file_type.is_dir() {
+                    leaf.update(b"D");
+                    leaf.update(relative.as_bytes());
+                } else {
+                    leaf.update(b"F");
+                    leaf.update(relative.as_bytes());
+                    leaf.update([0]);
+                    leaf.update(self.sha256_digest(entry.path())?);
+                }
+                Some((relative, leaf.finalize().into()))
+            })
+            .collect::<Option<_>>()?;
+
+        leaves.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut level: Vec<[u8; 32]> = leaves.into_iter().map(|(_, hash)| hash).collect();
+
+        // An empty directory still gets a well-defined hash
+        if level.is_empty() {
+            level.push(Sha256::digest(b"").into());
+        }
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => {
+                        let mut node = Sha256::new();
+                        node.update(left);
+                        node.update(right);
+                        node.finalize().into()
+                    }
+                    // Odd node out is promoted unchanged
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        Some(self.hash_format.format("sha256", &level[0]))
+    }
+
+    /// Quick hash formatted with the configured `HashFormat`
+    pub fn quick_hash_string(&self, path: &Path) -> Option<String> {
+        self.quick_hash(path)
+            .map(|hash| self.hash_format.format_quick(hash))
+    }
+
+    /// Warm the OS file cache for a batch of files about to be hashed
+    ///
+    /// Walks the files in path order (neighbouring files tend to be
+    /// neighbours on disk) and asks the OS to read them ahead, so the hashing
+    /// pass that follows reads from cache instead of seeking. On Unix this is
+    /// an asynchronous `posix_fadvise(WILLNEED)` hint; on Windows each file is
+    /// read through once with `FILE_FLAG_SEQUENTIAL_SCAN`.
+    ///
+    /// This is an HDD optimization. On SSDs random reads are already cheap
+    /// and prefetching just costs an extra pass, so skip it there. The HDD
+    /// speedup hasn't been measured: it needs a cold cache on a spinning
+    /// disk, which neither the tests nor the benches can set up, so time it
+    /// on the target machine before turning it on by default. Files
+    /// larger than the cache can hold evict each other; prefetch in batches
+    /// no larger than free memory. Offline placeholders and files above
+    /// `max_size` are skipped. Returns the number of files prefetched.
+    pub fn prefetch(&self, paths: &[PathBuf]) -> usize {
+        let mut ordered: Vec<&PathBuf> = paths.iter().collect();
+        ordered.sort();
+        ordered.dedup();
+
+        ordered
+            .into_iter()
+            .filter(|path| !is_offline_path(path))
+            .filter(|path| self.prefetch_file(path).is_some())
+            .count()
+    }
+
+    #[cfg(unix)]
+    fn prefetch_file(&self, path: &Path) -> Option<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = File::open(path).ok()?;
+        let len = file.metadata().ok()?.len();
+        if len > self.max_size {
+            return None;
+        }
+        // SAFETY: the descriptor is valid for the lifetime of `file`
+        let rc = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_WILLNEED) };
+        (rc == 0).then_some(())
+    }
+
+    #[cfg(windows)]
+    fn prefetch_file(&self, path: &Path) -> Option<()> {
+        use std::os::windows::fs::OpenOptionsExt;
+        use windows::Win32::Storage::FileSystem::FILE_FLAG_SEQUENTIAL_SCAN;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(FILE_FLAG_SEQUENTIAL_SCAN.0)
+            .open(path)
+            .ok()?;
+        if file.metadata().ok()?.len() > self.max_size {
+            return None;
+        }
+        let mut reader = BufReader::with_capacity(1024 * 1024, file);
+        std::io::copy(&mut reader, &mut std::io::sink()).ok()?;
+        Some(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn prefetch_file(&self, _path: &Path) -> Option<()> {
+        None
     }
 
     /// Compute both quick and secure hash
@@ -84,6 +501,11 @@ impl ContentHasher {
             return Some(false);
         }
 
+        // Small files: comparing the bytes beats hashing them
+        if meta1.len() < self.direct_compare_threshold {
+            return Some(self.read_small(path1)? == self.read_small(path2)?);
+        }
+
         // Then compare quick hashes
         let hash1 = self.quick_hash(path1)?;
         let hash2 = self.quick_hash(path2)?;
@@ -100,6 +522,58 @@ impl ContentHasher {
     }
 }
 
+/// Bytes inspected to tell text from binary, as git does
+const TEXT_SNIFF_LEN: usize = 8 * 1024;
+
+/// Streams text into a hasher with line endings normalized; see
+/// [`ContentHasher::text_normalized_hash`]
+struct TextNormalizer {
+    trim: bool,
+    /// Last byte was a CR, so a following LF belongs to the same break
+    after_cr: bool,
+    /// Spaces and tabs held back in case the line ends after them
+    whitespace: Vec<u8>,
+    /// Line breaks held back in case the file ends after them
+    breaks: usize,
+}
+
+impl TextNormalizer {
+    fn new(trim: bool) -> Self {
+        Self {
+            trim,
+            after_cr: false,
+            whitespace: Vec::new(),
+            breaks: 0,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8], hasher: &mut Sha256) {
+        let mut out = Vec::with_capacity(chunk.len());
+        for &byte in chunk {
+            let after_cr = std::mem::replace(&mut self.after_cr, byte == b'\r');
+            match byte {
+                b'\n' if after_cr => {}
+                b'\r' | b'\n' => {
+                    if self.trim {
+                        self.whitespace.clear();
+                        self.breaks += 1;
+                    } else {
+                        out.push(b'\n');
+                    }
+                }
+                b' ' | b'\t' if self.trim => self.whitespace.push(byte),
+                _ => {
+                    out.extend(std::iter::repeat_n(b'\n', self.breaks));
+                    self.breaks = 0;
+                    out.append(&mut self.whitespace);
+                    out.push(byte);
+                }
+            }
+        }
+        hasher.update(&out);
+    }
+}
+
 impl Default for ContentHasher {
     fn default() -> Self {
         Self::new(100 * 1024 * 1024) // 100MB default
@@ -123,6 +597,23 @@ mod tests {
         assert!(hash.is_some());
     }
 
+    #[test]
+    fn test_quick_hash_seed() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        std::fs::write(&file_path, b"Hello, World!").unwrap();
+
+        let unseeded = ContentHasher::default().quick_hash(&file_path);
+        let zero = ContentHasher::with_seed(u64::MAX, 0).quick_hash(&file_path);
+        let seeded = ContentHasher::with_seed(u64::MAX, 42).quick_hash(&file_path);
+        assert_eq!(zero, unseeded);
+        assert_ne!(seeded, unseeded);
+        assert_eq!(
+            seeded,
+            ContentHasher::with_seed(u64::MAX, 42).quick_hash(&file_path)
+        );
+    }
+
     #[test]
     fn test_sha256_hash() {
         let dir = tempdir().unwrap();
@@ -153,4 +644,174 @@ mod tests {
         assert_eq!(hasher.files_equal(&file1, &file2), Some(true));
         assert_eq!(hasher.files_equal(&file1, &file3), Some(false));
     }
+
+    #[test]
+    fn test_direct_compare_matches_hashing() {
+        let dir = tempdir().unwrap();
+        let file1 = dir.path().join("file1.bin");
+        let file2 = dir.path().join("file2.bin");
+        let file3 = dir.path().join("file3.bin");
+        std::fs::write(&file1, b"abcdef").unwrap();
+        std::fs::write(&file2, b"abcdef").unwrap();
+        std::fs::write(&file3, b"abcdeF").unwrap();
+
+        let direct = ContentHasher::default();
+        let hashed = ContentHasher::default().with_direct_compare_threshold(0);
+        assert!(direct.read_small(&file1).is_some());
+        assert!(hashed.read_small(&file1).is_none());
+
+        for hasher in [&direct, &hashed] {
+            assert_eq!(hasher.files_equal(&file1, &file2), Some(true));
+            assert_eq!(hasher.files_equal(&file1, &file3), Some(false));
+        }
+        assert_eq!(
+            Some(direct.sha256_of(b"abcdef")),
+            hashed.sha256_hash(&file1)
+        );
+    }
+
+    #[test]
+    fn test_text_normalized_hash() {
+        let dir = tempdir().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        let lf = write("lf.txt", b"one\ntwo\n");
+        let crlf = write("crlf.txt", b"one\r\ntwo\r\n");
+        let cr = write("cr.txt", b"one\rtwo\r");
+        let trailing = write("trailing.txt", b"one  \r\ntwo\t\n\n");
+        let binary = write("binary.bin", b"one\r\n\0two");
+
+        let hasher = ContentHasher::default();
+        let normalized = hasher.text_normalized_hash(&lf).unwrap();
+        assert_eq!(hasher.sha256_hash(&lf), Some(normalized.clone()));
+        assert_eq!(hasher.text_normalized_hash(&crlf), Some(normalized.clone()));
+        assert_eq!(hasher.text_normalized_hash(&cr), Some(normalized.clone()));
+        assert_ne!(hasher.text_normalized_hash(&trailing), Some(normalized));
+        // Binary content is hashed byte for byte
+        assert_eq!(
+            hasher.text_normalized_hash(&binary),
+            hasher.sha256_hash(&binary)
+        );
+
+        // Trimming also drops trailing spaces and line breaks
+        let trimming = ContentHasher::default().with_trim_trailing_whitespace(true);
+        let trimmed = trimming.text_normalized_hash(&lf).unwrap();
+        assert_eq!(
+            trimming.text_normalized_hash(&trailing),
+            Some(trimmed.clone())
+        );
+        assert_eq!(trimming.text_normalized_hash(&crlf), Some(trimmed.clone()));
+        let inner = write("inner.txt", b"one\n\ntwo");
+        assert_ne!(trimming.text_normalized_hash(&inner), Some(trimmed));
+
+        // A CRLF split across reads is still one line break
+        let digest = |chunks: &[&[u8]]| {
+            let mut hasher = Sha256::new();
+            let mut normalizer = TextNormalizer::new(false);
+            for chunk in chunks {
+                normalizer.feed(chunk, &mut hasher);
+            }
+            hasher.finalize()
+        };
+        assert_eq!(digest(&[b"one\r", b"\ntwo"]), digest(&[b"one\ntwo"]));
+    }
+
+    #[test]
+    fn test_hash_formats() {
+        let digest = [0xABu8, 0x01, 0xFF];
+        assert_eq!(HashFormat::HexLower.format("sha256", &digest), "ab01ff");
+        assert_eq!(HashFormat::HexUpper.format("sha256", &digest), "AB01FF");
+        assert_eq!(HashFormat::Base64.format("sha256", &digest), "qwH/");
+        assert_eq!(
+            HashFormat::Prefixed.format("sha256", &digest),
+            "sha256:ab01ff"
+        );
+        assert_eq!(
+            HashFormat::HexLower.format_quick(0x1234),
+            format!("{:016x}", 0x1234)
+        );
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        std::fs::write(&file_path, b"Hello, World!").unwrap();
+        let lower = ContentHasher::default().sha256_hash(&file_path).unwrap();
+        let prefixed = ContentHasher::default()
+            .with_hash_format(HashFormat::Prefixed)
+            .sha256_hash(&file_path)
+            .unwrap();
+        assert_eq!(prefixed, format!("sha256:{}", lower));
+    }
+
+    #[test]
+    fn test_hash_directory() {
+        let make_tree = |root: &Path| {
+            std::fs::create_dir_all(root.join("sub/empty")).unwrap();
+            std::fs::write(root.join("a.txt"), b"alpha").unwrap();
+            std::fs::write(root.join("sub/b.txt"), b"bravo").unwrap();
+        };
+        let dir = tempdir().unwrap();
+        let (left, right) = (dir.path().join("left"), dir.path().join("right"));
+        make_tree(&left);
+        make_tree(&right);
+
+        let hasher = ContentHasher::default();
+        let original = hasher.hash_directory(&left).unwrap();
+        assert_eq!(Some(original.clone()), hasher.hash_directory(&right));
+
+        // Same content under a different name changes the hash
+        std::fs::rename(right.join("a.txt"), right.join("c.txt")).unwrap();
+        assert_ne!(Some(original.clone()), hasher.hash_directory(&right));
+        std::fs::rename(right.join("c.txt"), right.join("a.txt")).unwrap();
+
+        // So does an extra empty directory
+        std::fs::create_dir(right.join("new")).unwrap();
+        assert_ne!(Some(original), hasher.hash_directory(&right));
+
+        assert_eq!(hasher.hash_directory(&left.join("a.txt")), None);
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let dir = tempdir().unwrap();
+        let small = dir.path().join("small.txt");
+        let large = dir.path().join("large.txt");
+        std::fs::write(&small, b"abc").unwrap();
+        std::fs::write(&large, vec![0u8; 64]).unwrap();
+
+        let hasher = ContentHasher::new(16);
+        let paths = vec![
+            large.clone(),
+            small.clone(),
+            small.clone(),
+            dir.path().join("missing.txt"),
+        ];
+        // Duplicates, oversized and missing files are not counted
+        assert_eq!(hasher.prefetch(&paths), 1);
+        assert!(hasher.quick_hash(&small).is_some());
+    }
+
+    #[test]
+    fn test_mmap_matches_buffered() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("large.bin");
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&file_path, &content).unwrap();
+
+        let buffered = ContentHasher::default();
+        let mapped = ContentHasher::default()
+            .with_mmap(true)
+            .with_mmap_threshold(1024);
+
+        assert_eq!(
+            buffered.quick_hash(&file_path),
+            mapped.quick_hash(&file_path)
+        );
+        assert_eq!(
+            buffered.sha256_hash(&file_path),
+            mapped.sha256_hash(&file_path)
+        );
+    }
 }
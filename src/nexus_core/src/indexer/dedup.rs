@@ -0,0 +1,1208 @@
+//! Duplicate detection
+//!
+//! Provides the content dedup funnel (size -> quick hash -> SHA-256),
+//! detection of duplicate directory trees, and heuristics that catch
+//! logical duplicates hashing cannot, such as edited copies of the same
+//! document.
+
+use super::volume::is_network_path;
+use super::ContentHasher;
+use crate::{ExtensionMatch, FileEntry};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Physical identity of a file: (volume serial, file index)
+type FileIdentity = (u64, u64);
+
+/// Paths sharing a file size
+type SizeGroup<'a> = (u64, Vec<&'a str>);
+
+/// Extensions treated as text by [`DedupStrategy::TextNormalized`]
+pub const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "log", "csv", "tsv", "ini", "cfg", "conf", "json", "xml",
+    "yaml", "yml", "toml", "html", "htm", "css", "js", "ts", "py", "rs", "c", "h", "cpp", "hpp",
+    "cs", "java", "go", "sh", "bat", "cmd", "ps1", "sql", "srt", "tex",
+];
+
+/// What counts as identical content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DedupStrategy {
+    /// Byte-for-byte identical
+    #[default]
+    Exact,
+    /// Files with a [`TEXT_EXTENSIONS`] extension are compared by
+    /// [`ContentHasher::text_normalized_hash`], so copies differing only in
+    /// CRLF vs LF line endings (and, if the hasher trims, trailing
+    /// whitespace) match; other files are compared exactly
+    ///
+    /// Normalized copies can differ in size, so text files skip the size
+    /// stage and every one is hashed. A text group's `size` is that of its
+    /// smallest file, making `reclaimable_bytes` a lower bound. Text files
+    /// are only matched with each other, not with an identical file under
+    /// another extension.
+    TextNormalized,
+}
+
+/// Options for the content dedup funnel
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupOptions {
+    /// Report NTFS hardlinks (several paths, one physical file) as their own
+    /// `is_hardlinked` groups instead of as deletable duplicates.
+    /// Deleting a hardlink frees no space. Network paths resolving to the
+    /// same file, such as two DFS namespace paths to one share, count as
+    /// hardlinks too.
+    pub collapse_hardlinks: bool,
+    /// Hash candidates one at a time in on-disk order (by starting cluster)
+    /// instead of in parallel, turning an HDD's random seeks into a mostly
+    /// sequential sweep. Harmless but slower on SSDs, where parallel reads
+    /// win. Files whose location is unknown (resident in the MFT, non-NTFS,
+    /// non-Windows) are read last, in path order.
+    pub optimize_read_order: bool,
+    /// What counts as identical content
+    #[serde(default)]
+    pub strategy: DedupStrategy,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            collapse_hardlinks: true,
+            optimize_read_order: false,
+            strategy: DedupStrategy::default(),
+        }
+    }
+}
+
+/// A set of paths with identical content
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// Size of each file in bytes
+    pub size: u64,
+    /// SHA-256 of the shared content
+    pub hash: String,
+    /// Paths in the group
+    pub paths: Vec<String>,
+    /// All paths are hardlinks to, or network paths resolving to, the same
+    /// physical file; deleting any of them reclaims nothing
+    pub is_hardlinked: bool,
+    /// Bytes freed by keeping one copy and deleting the rest
+    pub reclaimable_bytes: u64,
+}
+
+/// Find files with identical content
+///
+/// Funnel: group by size, then by quick hash (xxHash3), then confirm with
+/// SHA-256. Size groups below the hasher's direct-compare threshold skip
+/// the hashing and are split by comparing content directly. Directories,
+/// empty files and offline placeholders are skipped. Hashing runs in
+/// parallel per size group.
+///
+/// With [`DedupStrategy::TextNormalized`], text files are instead grouped
+/// by their normalized hash.
+///
+/// With `collapse_hardlinks`, paths that resolve to the same physical file
+/// (same volume and file index) are reported once in their content group,
+/// and each set of hardlinked paths gets a separate group flagged
+/// `is_hardlinked`. Network paths are matched by their
+/// `FileEntry::resolved_path`, or their own path when unresolved,
+/// case-insensitively.
+pub fn find_duplicates(
+    entries: &[FileEntry],
+    hasher: &ContentHasher,
+    options: &DedupOptions,
+) -> Vec<DuplicateGroup> {
+    let is_text = |entry: &FileEntry| {
+        options.strategy == DedupStrategy::TextNormalized
+            && TEXT_EXTENSIONS
+                .iter()
+                .any(|ext| entry.has_extension(ext, ExtensionMatch::Equals))
+    };
+    let resolved: HashMap<&str, &str> = entries
+        .iter()
+        .filter_map(|e| Some((e.path.as_str(), e.resolved_path.as_deref()?)))
+        .collect();
+    let mut by_size: HashMap<u64, Vec<&str>> = HashMap::new();
+    let mut text: Vec<(u64, &str)> = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|e| !e.is_dir && !e.is_offline && e.size > 0)
+    {
+        if is_text(entry) {
+            text.push((entry.size, entry.path.as_str()));
+            continue;
+        }
+        by_size
+            .entry(entry.size)
+            .or_default()
+            .push(entry.path.as_str());
+    }
+
+    let (small, candidates): (Vec<SizeGroup>, Vec<SizeGroup>) = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .partition(|(size, _)| *size < hasher.direct_compare_threshold());
+
+    let mut content_groups: Vec<(u64, String, Vec<String>)> = if options.optimize_read_order {
+        hash_in_read_order(&candidates, hasher)
+    } else {
+        candidates
+            .into_par_iter()
+            .flat_map_iter(|(size, paths)| {
+                hash_size_group(&paths, hasher)
+                    .into_iter()
+                    .map(move |(hash, paths)| (size, hash, paths))
+            })
+            .collect()
+    };
+    content_groups.par_extend(small.into_par_iter().flat_map_iter(|(size, paths)| {
+        compare_size_group(&paths, hasher)
+            .into_iter()
+            .map(move |(hash, paths)| (size, hash, paths))
+    }));
+    if text.len() > 1 {
+        content_groups.extend(hash_text_files(&text, hasher));
+    }
+
+    let mut groups: Vec<DuplicateGroup> = content_groups
+        .into_par_iter()
+        .flat_map_iter(|(size, hash, paths)| {
+            content_group_to_duplicates(size, hash, paths, &resolved, options)
+        })
+        .collect();
+
+    groups.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then(a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+/// Split a same-size group into groups of identical SHA-256
+fn hash_size_group(paths: &[&str], hasher: &ContentHasher) -> Vec<(String, Vec<String>)> {
+    let mut by_quick: HashMap<u64, Vec<&str>> = HashMap::new();
+    for &path in paths {
+        if let Some(quick) = hasher.quick_hash(Path::new(path)) {
+            by_quick.entry(quick).or_default().push(path);
+        }
+    }
+
+    let mut by_sha: HashMap<String, Vec<String>> = HashMap::new();
+    for candidates in by_quick.into_values().filter(|c| c.len() > 1) {
+        for path in candidates {
+            if let Some(sha) = hasher.sha256_hash(Path::new(path)) {
+                by_sha.entry(sha).or_default().push(path.to_string());
+            }
+        }
+    }
+
+    by_sha.into_iter().filter(|(_, p)| p.len() > 1).collect()
+}
+
+/// Group text files by normalized hash, each group sized by its smallest
+/// file
+fn hash_text_files(
+    files: &[(u64, &str)],
+    hasher: &ContentHasher,
+) -> Vec<(u64, String, Vec<String>)> {
+    let hashed: Vec<(String, u64, &str)> = files
+        .par_iter()
+        .filter_map(|&(size, path)| {
+            Some((hasher.text_normalized_hash(Path::new(path))?, size, path))
+        })
+        .collect();
+
+    let mut by_hash: HashMap<String, (u64, Vec<String>)> = HashMap::new();
+    for (hash, size, path) in hashed {
+        let group = by_hash.entry(hash).or_insert((size, Vec::new()));
+        group.0 = group.0.min(size);
+        group.1.push(path.to_string());
+    }
+
+    by_hash
+        .into_iter()
+        .filter(|(_, (_, p))| p.len() > 1)
+        .map(|(hash, (size, p))| (size, hash, p))
+        .collect()
+}
+
+/// Split a same-size group of small files by comparing their content
+///
+/// Each file is read once; the group's SHA-256 is computed from memory.
+fn compare_size_group(paths: &[&str], hasher: &ContentHasher) -> Vec<(String, Vec<String>)> {
+    let mut by_content: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+    for &path in paths {
+        if let Some(content) = hasher.read_small(Path::new(path)) {
+            by_content
+                .entry(content)
+                .or_default()
+                .push(path.to_string());
+        }
+    }
+
+    by_content
+        .into_iter()
+        .filter(|(_, p)| p.len() > 1)
+        .map(|(content, p)| (hasher.sha256_of(&content), p))
+        .collect()
+}
+
+/// Run the hash funnel over all size groups sequentially in on-disk order
+///
+/// Both hashing stages read files sorted by starting cluster, so each stage
+/// is one sweep across the disk.
+fn hash_in_read_order(
+    groups: &[(u64, Vec<&str>)],
+    hasher: &ContentHasher,
+) -> Vec<(u64, String, Vec<String>)> {
+    let mut order: Vec<(Option<u64>, &str)> = groups
+        .par_iter()
+        .flat_map_iter(|(_, paths)| paths.iter())
+        .map(|&path| (starting_lcn(Path::new(path)), path))
+        .collect();
+    order.sort_by_key(|&(lcn, path)| (lcn.is_none(), lcn, path));
+    let rank: HashMap<&str, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, path))| (path, i))
+        .collect();
+
+    let quick: HashMap<&str, u64> = order
+        .iter()
+        .filter_map(|&(_, path)| Some((path, hasher.quick_hash(Path::new(path))?)))
+        .collect();
+
+    // Only paths sharing a quick hash within their size group need SHA-256
+    let mut survivors: Vec<&str> = Vec::new();
+    for (_, paths) in groups {
+        let mut by_quick: HashMap<u64, Vec<&str>> = HashMap::new();
+        for &path in paths {
+            if let Some(&hash) = quick.get(path) {
+                by_quick.entry(hash).or_default().push(path);
+            }
+        }
+        survivors.extend(by_quick.into_values().filter(|c| c.len() > 1).flatten());
+    }
+    survivors.sort_by_key(|path| rank[path]);
+
+    let sha: HashMap<&str, String> = survivors
+        .iter()
+        .filter_map(|&path| Some((path, hasher.sha256_hash(Path::new(path))?)))
+        .collect();
+
+    let mut content_groups = Vec::new();
+    for (size, paths) in groups {
+        let mut by_sha: HashMap<&str, Vec<String>> = HashMap::new();
+        for &path in paths {
+            if let Some(hash) = sha.get(path) {
+                by_sha.entry(hash).or_default().push(path.to_string());
+            }
+        }
+        content_groups.extend(
+            by_sha
+                .into_iter()
+                .filter(|(_, p)| p.len() > 1)
+                .map(|(hash, p)| (*size, hash.to_string(), p)),
+        );
+    }
+    content_groups
+}
+
+/// Logical cluster number where the file's data starts
+///
+/// `None` for files with no clusters of their own (small files resident in
+/// the MFT record) or when the volume can't report it.
+#[cfg(windows)]
+fn starting_lcn(path: &Path) -> Option<u64> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Ioctl::{
+        FSCTL_GET_RETRIEVAL_POINTERS, RETRIEVAL_POINTERS_BUFFER, STARTING_VCN_INPUT_BUFFER,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    const FILE_READ_ATTRIBUTES: u32 = 0x80;
+    let file = std::fs::OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .open(path)
+        .ok()?;
+
+    let input = STARTING_VCN_INPUT_BUFFER { StartingVcn: 0 };
+    let mut output = RETRIEVAL_POINTERS_BUFFER::default();
+    let mut bytes_returned = 0u32;
+
+    // Room for a single extent: a fragmented file fails with ERROR_MORE_DATA
+    // but the first extent, the only one needed, is still filled in
+    let _ = unsafe {
+        DeviceIoControl(
+            HANDLE(file.as_raw_handle()),
+            FSCTL_GET_RETRIEVAL_POINTERS,
+            Some(&input as *const _ as *const _),
+            std::mem::size_of::<STARTING_VCN_INPUT_BUFFER>() as u32,
+            Some(&mut output as *mut _ as *mut _),
+            std::mem::size_of::<RETRIEVAL_POINTERS_BUFFER>() as u32,
+            Some(&mut bytes_returned),
+            None,
+        )
+    };
+
+    if output.ExtentCount == 0 {
+        return None;
+    }
+    u64::try_from(output.Extents[0].Lcn).ok()
+}
+
+#[cfg(not(windows))]
+fn starting_lcn(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Turn one verified content group into duplicate groups,
+/// separating hardlinks when requested
+///
+/// `resolved` maps network paths to their `FileEntry::resolved_path`.
+fn content_group_to_duplicates(
+    size: u64,
+    hash: String,
+    mut paths: Vec<String>,
+    resolved: &HashMap<&str, &str>,
+    options: &DedupOptions,
+) -> Vec<DuplicateGroup> {
+    paths.sort();
+
+    if !options.collapse_hardlinks {
+        return vec![duplicate_group(size, hash, paths, false)];
+    }
+
+    // Partition by physical file: same identity, or the same network target
+    // (shares are case-insensitive). Unknown identities stay distinct.
+    let mut by_file: Vec<(Option<FileIdentity>, Option<String>, Vec<String>)> = Vec::new();
+    for path in paths {
+        let identity = file_identity(Path::new(&path));
+        let target = resolved
+            .get(path.as_str())
+            .copied()
+            .or_else(|| is_network_path(&path).then_some(path.as_str()))
+            .map(str::to_lowercase);
+        match by_file.iter_mut().find(|(id, t, _)| {
+            (identity.is_some() && *id == identity) || (target.is_some() && *t == target)
+        }) {
+            Some((_, _, group)) => group.push(path),
+            None => by_file.push((identity, target, vec![path])),
+        }
+    }
+
+    let mut groups = Vec::new();
+
+    let representatives: Vec<String> = by_file.iter().map(|(_, _, p)| p[0].clone()).collect();
+    if representatives.len() > 1 {
+        groups.push(duplicate_group(size, hash.clone(), representatives, false));
+    }
+
+    for (_, _, linked) in by_file.into_iter().filter(|(_, _, p)| p.len() > 1) {
+        groups.push(duplicate_group(size, hash.clone(), linked, true));
+    }
+
+    groups
+}
+
+fn duplicate_group(
+    size: u64,
+    hash: String,
+    paths: Vec<String>,
+    is_hardlinked: bool,
+) -> DuplicateGroup {
+    let reclaimable_bytes = if is_hardlinked {
+        0
+    } else {
+        size * (paths.len() as u64 - 1)
+    };
+
+    DuplicateGroup {
+        size,
+        hash,
+        paths,
+        is_hardlinked,
+        reclaimable_bytes,
+    }
+}
+
+/// Physical identity of a file as (volume serial, file index)
+///
+/// Hardlinks share an identity. Returns `None` when it cannot be read.
+#[cfg(windows)]
+fn file_identity(path: &Path) -> Option<FileIdentity> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    // No data access requested: querying attributes must not read content
+    let file = std::fs::OpenOptions::new().access_mode(0).open(path).ok()?;
+
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }.ok()?;
+
+    let index = ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64;
+    Some((info.dwVolumeSerialNumber as u64, index))
+}
+
+#[cfg(unix)]
+fn file_identity(path: &Path) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(any(windows, unix)))]
+fn file_identity(_path: &Path) -> Option<FileIdentity> {
+    None
+}
+
+/// A set of directories whose trees are identical
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DuplicateDirGroup {
+    /// Tree hash shared by the directories, see [`ContentHasher::hash_directory`]
+    pub hash: String,
+    /// Total size of the files in each tree in bytes
+    pub size: u64,
+    /// Number of files in each tree
+    pub file_count: u64,
+    /// Directories in the group, sorted
+    pub paths: Vec<String>,
+    /// Bytes freed by keeping one tree and deleting the rest
+    pub reclaimable_bytes: u64,
+}
+
+/// Tree totals of a directory as recorded in the index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct TreeTotals {
+    size: u64,
+    files: u64,
+    dirs: u64,
+}
+
+/// Find directories with identical structure and content
+///
+/// Directories are first grouped by the total size, file count and
+/// subdirectory count of their tree in `entries`, so only plausible copies
+/// are read. Each candidate is then confirmed with
+/// [`ContentHasher::hash_directory`], which requires the same relative
+/// paths and the same file content. Trees without files are skipped, as
+/// are trees that can't be hashed completely.
+///
+/// Only the topmost copies are reported: a group whose directories are
+/// exactly the children of another group's directories is implied by it
+/// and left out. Groups are sorted by reclaimable bytes, largest first.
+pub fn find_duplicate_dirs(
+    entries: &[FileEntry],
+    hasher: &ContentHasher,
+) -> Vec<DuplicateDirGroup> {
+    let parents: HashMap<&str, &str> = entries
+        .iter()
+        .filter(|e| e.is_dir)
+        .map(|e| (e.path.as_str(), e.parent.as_str()))
+        .collect();
+    let mut totals: HashMap<&str, TreeTotals> = parents
+        .keys()
+        .map(|&dir| (dir, TreeTotals::default()))
+        .collect();
+
+    // Add every entry to each indexed ancestor
+    for entry in entries {
+        let mut dir = entry.parent.as_str();
+        while let Some(tree) = totals.get_mut(dir) {
+            if entry.is_dir {
+                tree.dirs += 1;
+            } else {
+                tree.size += entry.size;
+                tree.files += 1;
+            }
+            match parents.get(dir) {
+                Some(&parent) if parent != dir => dir = parent,
+                _ => break,
+            }
+        }
+    }
+
+    let mut by_totals: HashMap<TreeTotals, Vec<&str>> = HashMap::new();
+    for (dir, tree) in totals.into_iter().filter(|(_, t)| t.files > 0) {
+        by_totals.entry(tree).or_default().push(dir);
+    }
+
+    let mut groups: Vec<DuplicateDirGroup> = by_totals
+        .into_par_iter()
+        .filter(|(_, dirs)| dirs.len() > 1)
+        .flat_map_iter(|(tree, dirs)| {
+            let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+            for dir in dirs {
+                if let Some(hash) = hasher.hash_directory(Path::new(dir)) {
+                    by_hash.entry(hash).or_default().push(dir.to_string());
+                }
+            }
+            by_hash
+                .into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(move |(hash, mut paths)| {
+                    paths.sort();
+                    DuplicateDirGroup {
+                        hash,
+                        size: tree.size,
+                        file_count: tree.files,
+                        reclaimable_bytes: tree.size * (paths.len() as u64 - 1),
+                        paths,
+                    }
+                })
+        })
+        .collect();
+
+    // Drop groups whose parents are all the copies of one other group
+    let group_of: HashMap<&str, usize> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(i, g)| g.paths.iter().map(move |p| (p.as_str(), i)))
+        .collect();
+    let implied: Vec<bool> = groups
+        .iter()
+        .map(|group| {
+            let Some(mut group_parents) = group
+                .paths
+                .iter()
+                .map(|p| parents.get(p.as_str()).copied())
+                .collect::<Option<Vec<&str>>>()
+            else {
+                return false;
+            };
+            group_parents.sort_unstable();
+            group_parents
+                .first()
+                .and_then(|p| group_of.get(p))
+                .is_some_and(|&outer| groups[outer].paths == group_parents)
+        })
+        .collect();
+    let mut implied = implied.into_iter();
+    groups.retain(|_| !implied.next().unwrap_or(false));
+
+    groups.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then(a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+/// Result of a duplicate scan, the input to [`plan_deletions`]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+}
+
+impl DuplicateReport {
+    /// Run [`find_duplicates`] and wrap the groups
+    pub fn scan(entries: &[FileEntry], hasher: &ContentHasher, options: &DedupOptions) -> Self {
+        find_duplicates(entries, hasher, options).into()
+    }
+
+    /// Bytes freed by keeping one copy of every group
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.groups.iter().map(|g| g.reclaimable_bytes).sum()
+    }
+}
+
+impl From<Vec<DuplicateGroup>> for DuplicateReport {
+    fn from(groups: Vec<DuplicateGroup>) -> Self {
+        Self { groups }
+    }
+}
+
+/// A copy offered to a [`KeepRule`]
+#[derive(Debug, Clone)]
+pub struct KeepCandidate {
+    pub path: String,
+    /// Last modification time; `None` if the file can't be read
+    pub modified: Option<std::time::SystemTime>,
+}
+
+/// Chooses which copy of a duplicate group survives
+pub trait KeepRule {
+    /// Index into `candidates` of the copy to keep; `candidates` holds at
+    /// least two entries, sorted by path
+    fn keep(&self, candidates: &[KeepCandidate]) -> usize;
+}
+
+/// Built-in keep rules; ties and unreadable times fall back to path order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeepPolicy {
+    /// Keep the least recently modified copy, usually the original
+    Oldest,
+    /// Keep the most recently modified copy
+    Newest,
+    /// Keep the copy with the shortest path, usually the least nested
+    ShortestPath,
+}
+
+impl KeepRule for KeepPolicy {
+    fn keep(&self, candidates: &[KeepCandidate]) -> usize {
+        let indices = 0..candidates.len();
+        let chosen = match self {
+            // Unknown times sort after known ones in both directions
+            KeepPolicy::Oldest => indices.min_by_key(|&i| {
+                let modified = candidates[i].modified;
+                (modified.is_none(), modified, i)
+            }),
+            KeepPolicy::Newest => indices.min_by_key(|&i| {
+                let modified = candidates[i].modified;
+                (modified.is_none(), std::cmp::Reverse(modified), i)
+            }),
+            KeepPolicy::ShortestPath => indices.min_by_key(|&i| (candidates[i].path.len(), i)),
+        };
+        chosen.unwrap_or(0)
+    }
+}
+
+/// What a dedup run would do, for review before anything is deleted
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DeletionPlan {
+    pub to_delete: Vec<String>,
+    pub to_keep: Vec<String>,
+    pub bytes_freed: u64,
+}
+
+/// Decide which copies to delete under a built-in keep policy
+///
+/// Nothing is touched on disk besides reading modification times; pass
+/// `to_delete` to [`super::ops::recycle`] once the user has confirmed.
+pub fn plan_deletions(report: &DuplicateReport, policy: KeepPolicy) -> DeletionPlan {
+    plan_deletions_with(report, &policy)
+}
+
+/// Decide which copies to delete with a custom keep rule
+///
+/// Hardlink groups are skipped: their paths share one physical file, so
+/// deleting any of them frees nothing. Likewise `bytes_freed` counts each
+/// physical file once, and not at all while a kept path links to it, as in
+/// reports built without `collapse_hardlinks`.
+pub fn plan_deletions_with(report: &DuplicateReport, rule: &dyn KeepRule) -> DeletionPlan {
+    let mut plan = DeletionPlan::default();
+    let mut deleted_sizes = Vec::new();
+
+    for group in report
+        .groups
+        .iter()
+        .filter(|g| !g.is_hardlinked && g.paths.len() > 1)
+    {
+        let mut candidates: Vec<KeepCandidate> = group
+            .paths
+            .iter()
+            .map(|path| KeepCandidate {
+                path: path.clone(),
+                modified: std::fs::metadata(path).and_then(|m| m.modified()).ok(),
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let keep = rule.keep(&candidates).min(candidates.len() - 1);
+        for (i, candidate) in candidates.into_iter().enumerate() {
+            if i == keep {
+                plan.to_keep.push(candidate.path);
+            } else {
+                plan.to_delete.push(candidate.path);
+                deleted_sizes.push(group.size);
+            }
+        }
+    }
+
+    // Unknown identities count as distinct files
+    let kept: HashSet<FileIdentity> = plan
+        .to_keep
+        .iter()
+        .filter_map(|path| file_identity(Path::new(path)))
+        .collect();
+    let mut freed = HashSet::new();
+    for (path, size) in plan.to_delete.iter().zip(deleted_sizes) {
+        match file_identity(Path::new(path)) {
+            Some(id) if kept.contains(&id) || !freed.insert(id) => {}
+            _ => plan.bytes_freed += size,
+        }
+    }
+
+    plan
+}
+
+/// A group of files in one directory with near-identical names
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SimilarNameGroup {
+    /// Directory containing the files
+    pub directory: String,
+    /// Full paths of the files in the group
+    pub paths: Vec<String>,
+    /// Lowest pairwise similarity linking the group (0.0 - 1.0)
+    pub similarity: f64,
+}
+
+/// Find files whose names are near-identical within the same directory
+///
+/// Catches the "user saved five copies" case (`report.docx`,
+/// `report (1).docx`, `report_copy.docx`) that content hashing misses once
+/// copies are edited. Names are compared case-insensitively by
+/// Jaro-Winkler similarity of their stems, and only files sharing an
+/// extension are compared. Pairs scoring at least `threshold` are linked
+/// and linked files form a group.
+///
+/// Comparisons are bounded to each directory and directories are
+/// processed in parallel.
+pub fn find_similar_names(entries: &[FileEntry], threshold: f64) -> Vec<SimilarNameGroup> {
+    let mut by_dir: HashMap<&str, Vec<&FileEntry>> = HashMap::new();
+    for entry in entries.iter().filter(|e| !e.is_dir) {
+        by_dir.entry(entry.parent.as_str()).or_default().push(entry);
+    }
+
+    let mut groups: Vec<SimilarNameGroup> = by_dir
+        .into_par_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .flat_map_iter(|(dir, files)| similar_in_dir(dir, &files, threshold))
+        .collect();
+
+    groups.sort_by(|a, b| a.directory.cmp(&b.directory).then(a.paths.cmp(&b.paths)));
+    groups
+}
+
+/// Group similar names among the files of a single directory
+fn similar_in_dir(dir: &str, files: &[&FileEntry], threshold: f64) -> Vec<SimilarNameGroup> {
+    let keys: Vec<(String, String)> = files.iter().map(|f| name_key(&f.name)).collect();
+
+    // Union-find over files linked by a similarity edge
+    let mut parent: Vec<usize> = (0..files.len()).collect();
+    let mut link_score: Vec<f64> = vec![1.0; files.len()];
+
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        let mut node = i;
+        while parent[node] != root {
+            let next = parent[node];
+            parent[node] = root;
+            node = next;
+        }
+        root
+    }
+
+    for i in 0..files.len() {
+        for j in (i + 1)..files.len() {
+            let (stem_a, ext_a) = &keys[i];
+            let (stem_b, ext_b) = &keys[j];
+            if ext_a != ext_b {
+                continue;
+            }
+
+            let score = strsim::jaro_winkler(stem_a, stem_b);
+            if score >= threshold {
+                let root_a = find(&mut parent, i);
+                let root_b = find(&mut parent, j);
+                let min_score = score.min(link_score[root_a]).min(link_score[root_b]);
+                parent[root_b] = root_a;
+                link_score[root_a] = min_score;
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..files.len() {
+        let root = find(&mut parent, i);
+        members.entry(root).or_default().push(i);
+    }
+
+    members
+        .into_iter()
+        .filter(|(_, idx)| idx.len() > 1)
+        .map(|(root, idx)| {
+            let mut paths: Vec<String> = idx.iter().map(|&i| files[i].path.clone()).collect();
+            paths.sort();
+            SimilarNameGroup {
+                directory: dir.to_string(),
+                paths,
+                similarity: link_score[root],
+            }
+        })
+        .collect()
+}
+
+/// Split a file name into a lowercase (stem, extension) comparison key
+fn name_key(name: &str) -> (String, String) {
+    let lower = name.to_lowercase();
+    match lower.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.trim().to_string(), ext.to_string()),
+        _ => (lower, String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn disk_file(path: &Path) -> FileEntry {
+        let mut entry = file(
+            &path.parent().unwrap().to_string_lossy(),
+            &path.file_name().unwrap().to_string_lossy(),
+        );
+        entry.path = path.to_string_lossy().to_string();
+        entry.size = std::fs::metadata(path).unwrap().len();
+        entry
+    }
+
+    fn file(dir: &str, name: &str) -> FileEntry {
+        FileEntry {
+            path: format!("{}\\{}", dir, name),
+            name: name.to_string(),
+            extension: name.rsplit_once('.').map(|(_, e)| e.to_string()),
+            size: 1,
+            parent: dir.to_string(),
+            drive: 'C',
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_similar_names() {
+        let entries = vec![
+            file("C:\\docs", "report.docx"),
+            file("C:\\docs", "report (1).docx"),
+            file("C:\\docs", "Report_copy.docx"),
+            file("C:\\docs", "budget.xlsx"),
+            file("C:\\docs", "report.pdf"),
+        ];
+
+        let groups = find_similar_names(&entries, 0.85);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].directory, "C:\\docs");
+        assert_eq!(groups[0].paths.len(), 3);
+        assert!(groups[0].similarity >= 0.85 && groups[0].similarity <= 1.0);
+        assert!(!groups[0].paths.iter().any(|p| p.ends_with(".pdf")));
+    }
+
+    #[test]
+    fn test_similar_names_scoped_to_directory() {
+        let entries = vec![
+            file("C:\\a", "report.docx"),
+            file("C:\\b", "report (1).docx"),
+        ];
+
+        assert!(find_similar_names(&entries, 0.85).is_empty());
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.bin");
+        let b = dir.path().join("b.bin");
+        let c = dir.path().join("c.bin");
+        std::fs::write(&a, b"same content").unwrap();
+        std::fs::write(&b, b"same content").unwrap();
+        std::fs::write(&c, b"diff content").unwrap();
+
+        let entries: Vec<FileEntry> = [&a, &b, &c].iter().map(|p| disk_file(p)).collect();
+        let groups = find_duplicates(
+            &entries,
+            &ContentHasher::default(),
+            &DedupOptions::default(),
+        );
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].paths.len(), 2);
+        assert!(!groups[0].is_hardlinked);
+        assert_eq!(groups[0].reclaimable_bytes, 12);
+
+        // Hashing instead of comparing directly finds the same group
+        let hashed = find_duplicates(
+            &entries,
+            &ContentHasher::default().with_direct_compare_threshold(0),
+            &DedupOptions::default(),
+        );
+        assert_eq!(hashed.len(), 1);
+        assert_eq!(hashed[0].hash, groups[0].hash);
+        let sorted = |g: &DuplicateGroup| {
+            let mut paths = g.paths.clone();
+            paths.sort();
+            paths
+        };
+        assert_eq!(sorted(&hashed[0]), sorted(&groups[0]));
+    }
+
+    #[test]
+    fn test_text_normalized_strategy() {
+        let dir = tempdir().unwrap();
+        let mut entries = Vec::new();
+        for (name, content) in [
+            ("unix.txt", "one\ntwo\n"),
+            ("windows.TXT", "one\r\ntwo\r\n"),
+            ("other.txt", "one\ntwo\nthree\n"),
+            ("unix.bin", "one\ntwo\n"),
+            ("windows.bin", "one\r\ntwo\r\n"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            entries.push(disk_file(&path));
+        }
+        let hasher = ContentHasher::default();
+
+        // Byte comparison pairs each text file with its binary twin
+        let exact = find_duplicates(&entries, &hasher, &DedupOptions::default());
+        assert_eq!(exact.len(), 2);
+        assert!(exact.iter().all(|g| g.paths.len() == 2));
+
+        let options = DedupOptions {
+            strategy: DedupStrategy::TextNormalized,
+            ..Default::default()
+        };
+        // Text files now only match each other, across line endings
+        let groups = find_duplicates(&entries, &hasher, &options);
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[0]
+            .paths
+            .iter()
+            .map(|p| p.rsplit(['\\', '/']).next().unwrap())
+            .collect();
+        assert_eq!(names, ["unix.txt", "windows.TXT"]);
+        assert_eq!(groups[0].size, 8);
+        assert_eq!(groups[0].reclaimable_bytes, 8);
+    }
+
+    #[test]
+    fn test_read_order_matches_parallel() {
+        let dir = tempdir().unwrap();
+        let mut entries = Vec::new();
+        for (name, content) in [
+            ("a.bin", "alpha"),
+            ("b.bin", "alpha"),
+            ("c.bin", "bravo"),
+            ("d.bin", "bravo"),
+            ("e.bin", "other"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            entries.push(disk_file(&path));
+        }
+        let hasher = ContentHasher::default();
+
+        let parallel = find_duplicates(&entries, &hasher, &DedupOptions::default());
+        let ordered = find_duplicates(
+            &entries,
+            &hasher,
+            &DedupOptions {
+                optimize_read_order: true,
+                ..Default::default()
+            },
+        );
+
+        let paths = |groups: &[DuplicateGroup]| {
+            let mut paths: Vec<Vec<String>> = groups.iter().map(|g| g.paths.clone()).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(ordered.len(), 2);
+        assert_eq!(paths(&parallel), paths(&ordered));
+    }
+
+    #[test]
+    fn test_plan_deletions() {
+        let dir = tempdir().unwrap();
+        let old = dir.path().join("old.bin");
+        let new = dir.path().join("archive").join("new.bin");
+        std::fs::create_dir(dir.path().join("archive")).unwrap();
+        for (path, age) in [(&old, 3600), (&new, 60)] {
+            std::fs::write(path, b"same content").unwrap();
+            let modified = std::time::SystemTime::now() - std::time::Duration::from_secs(age);
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+
+        let entries: Vec<FileEntry> = [&old, &new].iter().map(|p| disk_file(p)).collect();
+        let report = DuplicateReport::scan(
+            &entries,
+            &ContentHasher::default(),
+            &DedupOptions::default(),
+        );
+        let old = old.to_string_lossy().to_string();
+        let new = new.to_string_lossy().to_string();
+
+        let plan = plan_deletions(&report, KeepPolicy::Oldest);
+        assert_eq!(plan.to_keep, vec![old.clone()]);
+        assert_eq!(plan.to_delete, vec![new.clone()]);
+        assert_eq!(plan.bytes_freed, report.reclaimable_bytes());
+
+        let plan = plan_deletions(&report, KeepPolicy::Newest);
+        assert_eq!(plan.to_keep, vec![new.clone()]);
+
+        let plan = plan_deletions(&report, KeepPolicy::ShortestPath);
+        assert_eq!(plan.to_keep, vec![old]);
+
+        // Custom rules plug in through KeepRule
+        struct KeepLast;
+        impl KeepRule for KeepLast {
+            fn keep(&self, candidates: &[KeepCandidate]) -> usize {
+                candidates.len() - 1
+            }
+        }
+        let plan = plan_deletions_with(&report, &KeepLast);
+        assert_eq!(plan.to_keep.len(), 1);
+        assert_eq!(plan.to_delete.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_counts_hardlinks_once() {
+        let dir = tempdir().unwrap();
+        let original = dir.path().join("a.bin");
+        let link = dir.path().join("link.bin");
+        let copy = dir.path().join("copy.bin");
+        std::fs::write(&original, b"linked content").unwrap();
+        std::fs::hard_link(&original, &link).unwrap();
+        std::fs::write(&copy, b"linked content").unwrap();
+
+        let entries: Vec<FileEntry> = [&original, &link, &copy]
+            .iter()
+            .map(|p| disk_file(p))
+            .collect();
+        let options = DedupOptions {
+            collapse_hardlinks: false,
+            ..Default::default()
+        };
+        let report = DuplicateReport::scan(&entries, &ContentHasher::default(), &options);
+
+        // Deleting the kept file's hardlink frees nothing
+        let plan = plan_deletions(&report, KeepPolicy::ShortestPath);
+        assert_eq!(plan.to_keep, vec![original.to_string_lossy().to_string()]);
+        assert_eq!(plan.to_delete.len(), 2);
+        assert_eq!(plan.bytes_freed, 14);
+    }
+
+    #[test]
+    fn test_hardlinks_are_not_duplicates() {
+        let dir = tempdir().unwrap();
+        let original = dir.path().join("original.bin");
+        let link = dir.path().join("link.bin");
+        let copy = dir.path().join("copy.bin");
+        std::fs::write(&original, b"linked content").unwrap();
+        std::fs::hard_link(&original, &link).unwrap();
+        std::fs::write(&copy, b"linked content").unwrap();
+
+        let entries: Vec<FileEntry> = [&original, &link, &copy]
+            .iter()
+            .map(|p| disk_file(p))
+            .collect();
+        let hasher = ContentHasher::default();
+
+        let groups = find_duplicates(&entries, &hasher, &DedupOptions::default());
+        let copies = groups.iter().find(|g| !g.is_hardlinked).unwrap();
+        let linked = groups.iter().find(|g| g.is_hardlinked).unwrap();
+
+        // One real copy to reclaim; the hardlink pair reclaims nothing
+        assert_eq!(copies.paths.len(), 2);
+        assert_eq!(copies.reclaimable_bytes, 14);
+        assert_eq!(linked.paths.len(), 2);
+        assert_eq!(linked.reclaimable_bytes, 0);
+
+        let uncollapsed = find_duplicates(
+            &entries,
+            &hasher,
+            &DedupOptions {
+                collapse_hardlinks: false,
+                ..Default::default()
+            },
+        );
+        assert_eq!(uncollapsed.len(), 1);
+        assert_eq!(uncollapsed[0].paths.len(), 3);
+    }
+
+    #[test]
+    fn test_dfs_paths_to_one_file_are_not_duplicates() {
+        let namespace = r"\\corp\dfs\projects\plan.docx";
+        let server = r"\\FS01\Projects\plan.docx";
+        let resolved = HashMap::from([(namespace, r"\\fs01\projects\plan.docx")]);
+        let paths = vec![
+            namespace.to_string(),
+            server.to_string(),
+            r"\\fs02\backup\plan.docx".to_string(),
+        ];
+
+        let groups = content_group_to_duplicates(
+            100,
+            "hash".to_string(),
+            paths,
+            &resolved,
+            &DedupOptions::default(),
+        );
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].paths.len(), 2);
+        assert_eq!(groups[0].reclaimable_bytes, 100);
+        assert!(groups[1].is_hardlinked);
+        assert_eq!(groups[1].paths, vec![server, namespace]);
+    }
+
+    #[test]
+    fn test_find_duplicate_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let write = |rel: &str, content: &[u8]| {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        let big = [7u8; 100];
+        for copy in ["a", "b"] {
+            write(&format!("{}/x.txt", copy), b"hello world!");
+            write(&format!("{}/sub/y.bin", copy), &big);
+        }
+        // Same totals as `a`, but one file differs and one is renamed
+        write("c/x.txt", b"hello there!");
+        write("c/sub/y.bin", &big);
+        write("d/x.txt", b"hello world!");
+        write("d/sub/z.bin", &big);
+
+        let entries: Vec<FileEntry> = walkdir::WalkDir::new(root)
+            .min_depth(1)
+            .into_iter()
+            .map(|e| {
+                let e = e.unwrap();
+                let mut entry = disk_file(e.path());
+                entry.is_dir = e.file_type().is_dir();
+                entry
+            })
+            .collect();
+
+        let groups = find_duplicate_dirs(&entries, &ContentHasher::default());
+        let paths = |rels: &[&str]| -> Vec<String> {
+            rels.iter()
+                .map(|r| root.join(r).to_string_lossy().to_string())
+                .collect()
+        };
+
+        // `a/sub` and `b/sub` are implied by `a` and `b`, but `c/sub` also
+        // matches, so their group is reported with it
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].paths, paths(&["a/sub", "b/sub", "c/sub"]));
+        assert_eq!(groups[0].file_count, 1);
+        assert_eq!(groups[0].reclaimable_bytes, 200);
+        assert_eq!(groups[1].paths, paths(&["a", "b"]));
+        assert_eq!(groups[1].file_count, 2);
+        assert_eq!(groups[1].size, 112);
+        assert_eq!(groups[1].reclaimable_bytes, 112);
+    }
+}
@@ -0,0 +1,265 @@
+//! Precompiled entry filter for the indexing hot loop
+//!
+//! `IndexConfig` filters are evaluated for every one of millions of
+//! entries, so they are compiled once up front: extensions into a sorted
+//! list and, past a handful of patterns, exclude substrings into a single
+//! Aho-Corasick automaton. Checks whose config is empty are skipped
+//! entirely.
+
+use super::IndexConfig;
+use crate::{has_suffix_ignore_case, ExtensionMatch, FileEntry};
+use aho_corasick::{AhoCorasick, AhoCorasickKind};
+use std::borrow::Cow;
+
+/// Up to this many exclude patterns, per-pattern `str::contains` (SIMD in
+/// std) is as fast as one automaton pass; beyond it the automaton wins.
+/// See the `should_include` benchmarks.
+const MAX_SUBSTRING_PATTERNS: usize = 8;
+
+#[derive(Debug, Clone)]
+enum Excludes {
+    None,
+    /// Lowercased, matched against the lowercased path
+    Substrings(Vec<String>),
+    Automaton(AhoCorasick),
+}
+
+impl Excludes {
+    fn is_match(&self, path: &str) -> bool {
+        match self {
+            Excludes::None => false,
+            Excludes::Substrings(patterns) => {
+                let path = if path.bytes().any(|b| b.is_ascii_uppercase()) {
+                    Cow::Owned(path.to_ascii_lowercase())
+                } else {
+                    Cow::Borrowed(path)
+                };
+                patterns.iter().any(|p| path.contains(p.as_str()))
+            }
+            Excludes::Automaton(automaton) => automaton.is_match(path),
+        }
+    }
+}
+
+/// Compiled form of the `IndexConfig` include/exclude rules
+#[derive(Debug, Clone)]
+pub struct EntryFilter {
+    include_hidden: bool,
+    include_system: bool,
+    include_dirs: bool,
+    include_files: bool,
+    excludes: Excludes,
+    /// Sorted lowercase extensions to keep; `None` means all. A binary
+    /// search over a short sorted list beats hashing each extension.
+    extensions: Option<Vec<String>>,
+    extension_match: ExtensionMatch,
+}
+
+impl EntryFilter {
+    /// Compile the filter rules of `config`
+    pub fn new(config: &IndexConfig) -> Self {
+        let patterns: Vec<&str> = config
+            .exclude_dirs
+            .iter()
+            .map(String::as_str)
+            .filter(|p| !p.is_empty())
+            .collect();
+        let excludes = if patterns.is_empty() {
+            Excludes::None
+        } else if patterns.len() <= MAX_SUBSTRING_PATTERNS {
+            Excludes::Substrings(patterns.iter().map(|p| p.to_ascii_lowercase()).collect())
+        } else {
+            // Short directory names share common bytes with most paths, so
+            // the prefilter mostly produces false candidates
+            match AhoCorasick::builder()
+                .kind(Some(AhoCorasickKind::DFA))
+                .prefilter(false)
+                .ascii_case_insensitive(true)
+                .build(&patterns)
+            {
+                Ok(automaton) => Excludes::Automaton(automaton),
+                // Only fails when automaton size limits are exceeded
+                Err(e) => {
+                    tracing::warn!("Failed to compile exclude patterns: {}", e);
+                    Excludes::Substrings(patterns.iter().map(|p| p.to_ascii_lowercase()).collect())
+                }
+            }
+        };
+
+        let extensions = if config.extensions.is_empty() {
+            None
+        } else {
+            let mut extensions: Vec<String> = config
+                .extensions
+                .iter()
+                .map(|e| e.strip_prefix('.').unwrap_or(e).to_ascii_lowercase())
+                .collect();
+            extensions.sort();
+            extensions.dedup();
+            Some(extensions)
+        };
+
+        Self {
+            include_hidden: config.include_hidden,
+            include_system: config.include_system,
+            include_dirs: config.index_directories || config.directories_only,
+            include_files: !config.directories_only,
+            excludes,
+            extensions,
+            extension_match: config.extension_match,
+        }
+    }
+
+    /// Whether `entry` passes the include/exclude rules
+    pub fn matches(&self, entry: &FileEntry) -> bool {
+        let kind_wanted = if entry.is_dir {
+            self.include_dirs
+        } else {
+            self.include_files
+        };
+        if !kind_wanted {
+            return false;
+        }
+        if !self.include_hidden && entry.is_hidden {
+            return false;
+        }
+        if !self.include_system && entry.is_system {
+            return false;
+        }
+
+        if self.excludes.is_match(&entry.path) {
+            return false;
+        }
+
+        if let Some(extensions) = &self.extensions {
+            if !entry.is_dir && self.extension_match == ExtensionMatch::Suffix {
+                if !extensions
+                    .iter()
+                    .any(|suffix| has_suffix_ignore_case(&entry.name, suffix))
+                {
+                    return false;
+                }
+            } else if !entry.is_dir {
+                let Some(ext) = &entry.extension else {
+                    return false;
+                };
+                let found = if ext.bytes().any(|b| b.is_ascii_uppercase()) {
+                    extensions.binary_search(&ext.to_ascii_lowercase())
+                } else {
+                    extensions.binary_search_by(|e| e.as_str().cmp(ext))
+                };
+                if found.is_err() {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, ext: Option<&str>) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            extension: ext.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_config_rules() {
+        let config = IndexConfig {
+            extensions: vec!["PDF".to_string(), "txt".to_string()],
+            include_system: false,
+            ..Default::default()
+        };
+        let filter = EntryFilter::new(&config);
+
+        assert!(filter.matches(&file("C:\\Users\\me\\a.pdf", Some("pdf"))));
+        assert!(filter.matches(&file("C:\\Users\\me\\b.TXT", Some("TXT"))));
+        assert!(!filter.matches(&file("C:\\Users\\me\\c.exe", Some("exe"))));
+        assert!(!filter.matches(&file("C:\\Users\\me\\noext", None)));
+        assert!(!filter.matches(&file("C:\\Program Files\\x.pdf", Some("pdf"))));
+        assert!(!filter.matches(&FileEntry {
+            is_system: true,
+            ..file("C:\\Users\\me\\sys.pdf", Some("pdf"))
+        }));
+        // Extension rules don't apply to directories
+        assert!(filter.matches(&FileEntry {
+            is_dir: true,
+            ..file("C:\\Users\\me", None)
+        }));
+    }
+
+    #[test]
+    fn test_empty_config_matches_everything() {
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            include_system: true,
+            ..Default::default()
+        };
+        let filter = EntryFilter::new(&config);
+        assert!(filter.matches(&file("C:\\Windows\\notepad.exe", Some("exe"))));
+    }
+
+    #[test]
+    fn test_files_or_directories_only() {
+        let dir = FileEntry {
+            is_dir: true,
+            ..file("C:\\Users\\me", None)
+        };
+        let doc = file("C:\\Users\\me\\a.pdf", Some("pdf"));
+
+        let files_only = EntryFilter::new(&IndexConfig {
+            index_directories: false,
+            ..Default::default()
+        });
+        assert!(!files_only.matches(&dir));
+        assert!(files_only.matches(&doc));
+
+        let dirs_only = EntryFilter::new(&IndexConfig {
+            directories_only: true,
+            ..Default::default()
+        });
+        assert!(dirs_only.matches(&dir));
+        assert!(!dirs_only.matches(&doc));
+    }
+
+    #[test]
+    fn test_suffix_extension_match() {
+        let config = IndexConfig {
+            extensions: vec![".min.js".to_string(), "D.TS".to_string()],
+            extension_match: ExtensionMatch::Suffix,
+            ..Default::default()
+        };
+        let filter = EntryFilter::new(&config);
+        let named = |name: &str| FileEntry {
+            name: name.to_string(),
+            ..file(&format!("C:\\web\\{}", name), name.rsplit('.').next())
+        };
+
+        assert!(filter.matches(&named("app.min.js")));
+        assert!(filter.matches(&named("Types.D.ts")));
+        assert!(!filter.matches(&named("app.js")));
+        assert!(!filter.matches(&named("appmin.js")));
+    }
+
+    #[test]
+    fn test_many_excludes_use_automaton() {
+        let mut config = IndexConfig::default();
+        config
+            .exclude_dirs
+            .extend((0..10).map(|i| format!("skip_{}", i)));
+        let filter = EntryFilter::new(&config);
+
+        assert!(matches!(filter.excludes, Excludes::Automaton(_)));
+        assert!(!filter.matches(&file("C:\\src\\skip_7\\a.txt", Some("txt"))));
+        assert!(!filter.matches(&file("C:\\Windows\\a.txt", Some("txt"))));
+        assert!(filter.matches(&file("C:\\src\\keep\\a.txt", Some("txt"))));
+        assert!(!filter.matches(&file("C:\\src\\SKIP_7\\a.txt", Some("txt"))));
+    }
+}
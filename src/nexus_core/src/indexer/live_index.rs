@@ -0,0 +1,116 @@
+//! Live in-memory index with point-in-time snapshots
+//!
+//! Holds the current entry set behind an `Arc` so readers can take a
+//! stable snapshot while a USN sync keeps updating the index.
+
+use crate::FileEntry;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Mutable in-memory index supporting cheap, immutable snapshots
+///
+/// Snapshots share the underlying `Vec` with the index. Updates are
+/// copy-on-write: if no snapshot is outstanding the entries are mutated in
+/// place, otherwise the `Vec` is cloned first and the snapshot keeps the
+/// old version.
+///
+/// Memory: each distinct version still referenced by a snapshot is a full
+/// copy of the entry set. Holding a snapshot across many updates costs at
+/// most one extra copy (the snapshot's version plus the current one), but
+/// long-lived snapshots of different versions each keep their own copy
+/// alive. Drop snapshots promptly on large indexes.
+pub struct LiveIndex {
+    current: Mutex<Arc<Vec<FileEntry>>>,
+}
+
+impl LiveIndex {
+    /// Create a live index from an initial entry set
+    pub fn new(entries: Vec<FileEntry>) -> Self {
+        Self {
+            current: Mutex::new(Arc::new(entries)),
+        }
+    }
+
+    /// Take a point-in-time snapshot of the index
+    ///
+    /// The snapshot never changes, even while updates continue.
+    /// Cloning it is as cheap as cloning an `Arc`.
+    pub fn snapshot(&self) -> Arc<Vec<FileEntry>> {
+        self.current.lock().clone()
+    }
+
+    /// Apply an update to the entries
+    ///
+    /// Clones the entry set first only if a snapshot of the current
+    /// version is still alive.
+    pub fn update<F: FnOnce(&mut Vec<FileEntry>)>(&self, f: F) {
+        let mut current = self.current.lock();
+        f(Arc::make_mut(&mut current));
+    }
+
+    /// Replace the whole entry set, e.g. after a full re-index
+    pub fn replace(&self, entries: Vec<FileEntry>) {
+        *self.current.lock() = Arc::new(entries);
+    }
+
+    /// Number of entries in the current version
+    pub fn len(&self) -> usize {
+        self.current.lock().len()
+    }
+
+    /// Whether the current version is empty
+    pub fn is_empty(&self) -> bool {
+        self.current.lock().is_empty()
+    }
+}
+
+impl Default for LiveIndex {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> FileEntry {
+        FileEntry {
+            path: format!("C:\\{}", name),
+            name: name.to_string(),
+            size: 0,
+            parent: "C:".to_string(),
+            drive: 'C',
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_snapshot_is_stable_across_updates() {
+        let index = LiveIndex::new(vec![entry("a"), entry("b")]);
+        let snapshot = index.snapshot();
+
+        index.update(|entries| {
+            entries.retain(|e| e.name != "a");
+            entries.push(entry("c"));
+        });
+
+        let names: Vec<&str> = snapshot.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+
+        let current: Vec<String> = index.snapshot().iter().map(|e| e.name.clone()).collect();
+        assert_eq!(current, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_update_without_snapshot_is_in_place() {
+        let index = LiveIndex::new(vec![entry("a")]);
+        let before = Arc::as_ptr(&index.snapshot());
+
+        index.update(|entries| entries[0].size = 42);
+
+        let after = index.snapshot();
+        assert_eq!(Arc::as_ptr(&after), before);
+        assert_eq!(after[0].size, 42);
+    }
+}
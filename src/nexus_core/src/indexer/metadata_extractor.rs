@@ -34,11 +34,11 @@ impl MetadataExtractor {
         };
 
         let name = path.file_name()?.to_string_lossy().to_string();
-        let path_str = path.to_string_lossy().to_string();
+        let path_str = normalize_separators(&path.to_string_lossy());
 
         // Extract extension
         let extension = if !metadata.is_dir() {
-            path.extension().map(|e| e.to_string_lossy().to_lowercase())
+            path.extension().map(|e| e.to_string_lossy().into_owned())
         } else {
             None
         };
@@ -46,11 +46,11 @@ impl MetadataExtractor {
         // Extract parent
         let parent = path
             .parent()
-            .map(|p| p.to_string_lossy().to_string())
+            .map(|p| normalize_separators(&p.to_string_lossy()))
             .unwrap_or_default();
 
-        // Extract drive letter
-        let drive = path_str.chars().next().unwrap_or('C').to_ascii_uppercase();
+        let volume = super::volume::volume_root(&path_str).unwrap_or_default();
+        let drive = super::volume::drive_letter(&path_str).unwrap_or('\\');
 
         // Convert times
         let created = metadata.created().ok().and_then(system_time_to_datetime);
@@ -60,7 +60,8 @@ impl MetadataExtractor {
         let accessed = metadata.accessed().ok().and_then(system_time_to_datetime);
 
         // Check file attributes (Windows-specific)
-        let (is_hidden, is_system) = get_file_attributes(path);
+        let (is_hidden, is_system, is_offline) = get_file_attributes(path, &metadata);
+        let is_cycle = metadata.file_type().is_symlink() && links_to_ancestor(path);
 
         Some(FileEntry {
             path: path_str,
@@ -73,11 +74,30 @@ impl MetadataExtractor {
             is_dir: metadata.is_dir(),
             is_hidden,
             is_system,
+            is_offline,
             content_hash: None,
             parent,
             drive,
+            volume,
+            volume_id: None,
+            resolved_path: None,
+            is_cycle,
+            tags: Vec::new(),
         })
     }
+
+    /// Fill in created, modified and accessed times for an entry built
+    /// without them (MFT enumeration); returns false if the file can't be
+    /// stat'ed
+    pub fn fill_timestamps(&self, entry: &mut FileEntry) -> bool {
+        let Ok(metadata) = fs::symlink_metadata(&entry.path) else {
+            return false;
+        };
+        entry.created = metadata.created().ok().and_then(system_time_to_datetime);
+        entry.modified = metadata.modified().ok().and_then(system_time_to_datetime);
+        entry.accessed = metadata.accessed().ok().and_then(system_time_to_datetime);
+        true
+    }
 }
 
 impl Default for MetadataExtractor {
@@ -86,6 +106,30 @@ impl Default for MetadataExtractor {
     }
 }
 
+/// Use `\` throughout on Windows, so a root given as `C:/Users` doesn't
+/// produce `C:/Users\file.txt`; elsewhere `\` is a valid file name
+/// character and paths are left alone
+fn normalize_separators(path: &str) -> String {
+    if cfg!(windows) {
+        to_backslashes(path)
+    } else {
+        path.to_string()
+    }
+}
+
+fn to_backslashes(path: &str) -> String {
+    path.replace('/', "\\")
+}
+
+/// Whether the link at `path` resolves to one of its own ancestors (or
+/// itself), e.g. `C:\a\b` junctioned to `C:\a`
+pub(crate) fn links_to_ancestor(path: &Path) -> bool {
+    let (Ok(target), Some(parent)) = (fs::canonicalize(path), path.parent()) else {
+        return false;
+    };
+    fs::canonicalize(parent).is_ok_and(|parent| parent.starts_with(&target))
+}
+
 /// Convert SystemTime to DateTime<Utc>
 fn system_time_to_datetime(time: SystemTime) -> Option<DateTime<Utc>> {
     time.duration_since(SystemTime::UNIX_EPOCH)
@@ -93,29 +137,59 @@ fn system_time_to_datetime(time: SystemTime) -> Option<DateTime<Utc>> {
         .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, d.subsec_nanos()))
 }
 
-/// Get Windows file attributes
+/// Check whether raw file attributes mark an offline / cloud placeholder.
+///
+/// Opening such a file for reading makes the cloud provider download it,
+/// so content must never be read for these entries.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn is_offline_attributes(attrs: u32) -> bool {
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+    attrs
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+/// Get Windows file attributes as (hidden, system, offline)
 #[cfg(windows)]
-fn get_file_attributes(path: &Path) -> (bool, bool) {
+fn get_file_attributes(_path: &Path, metadata: &fs::Metadata) -> (bool, bool, bool) {
     use std::os::windows::fs::MetadataExt;
 
-    if let Ok(metadata) = fs::metadata(path) {
-        let attrs = metadata.file_attributes();
-        let is_hidden = (attrs & 0x02) != 0; // FILE_ATTRIBUTE_HIDDEN
-        let is_system = (attrs & 0x04) != 0; // FILE_ATTRIBUTE_SYSTEM
-        (is_hidden, is_system)
-    } else {
-        (false, false)
-    }
+    // Reuse the metadata we already have; it was read without opening the
+    // file's data stream, so it is safe for cloud placeholders.
+    let attrs = metadata.file_attributes();
+    let is_hidden = (attrs & 0x02) != 0; // FILE_ATTRIBUTE_HIDDEN
+    let is_system = (attrs & 0x04) != 0; // FILE_ATTRIBUTE_SYSTEM
+    (is_hidden, is_system, is_offline_attributes(attrs))
 }
 
 #[cfg(not(windows))]
-fn get_file_attributes(path: &Path) -> (bool, bool) {
+fn get_file_attributes(path: &Path, _metadata: &fs::Metadata) -> (bool, bool, bool) {
     // On non-Windows, check if filename starts with '.'
     let is_hidden = path
         .file_name()
         .map(|n| n.to_string_lossy().starts_with('.'))
         .unwrap_or(false);
-    (is_hidden, false)
+    (is_hidden, false, false)
+}
+
+/// Check whether a path on disk is an offline / cloud placeholder
+#[cfg(windows)]
+pub(crate) fn is_offline_path(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    fs::symlink_metadata(path)
+        .map(|m| is_offline_attributes(m.file_attributes()))
+        .unwrap_or(false)
+}
+
+#[cfg(not(windows))]
+pub(crate) fn is_offline_path(_path: &Path) -> bool {
+    false
 }
 
 #[cfg(test)]
@@ -127,14 +201,16 @@ mod tests {
     #[test]
     fn test_extract_file_metadata() {
         let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.txt");
+        let file_path = dir.path().join("test.TXT");
         File::create(&file_path).unwrap();
 
         let extractor = MetadataExtractor::new();
         let entry = extractor.extract(&file_path).unwrap();
 
-        assert_eq!(entry.name, "test.txt");
-        assert_eq!(entry.extension, Some("txt".to_string()));
+        // Original case is kept; comparisons ignore it
+        assert_eq!(entry.name, "test.TXT");
+        assert_eq!(entry.extension, Some("TXT".to_string()));
+        assert!(entry.has_extension(".txt", crate::ExtensionMatch::Equals));
         assert!(!entry.is_dir);
     }
 
@@ -151,4 +227,90 @@ mod tests {
         assert!(entry.extension.is_none());
         assert!(entry.is_dir);
     }
+
+    #[test]
+    fn test_to_backslashes() {
+        assert_eq!(to_backslashes(r"C:/Users\a/b.txt"), r"C:\Users\a\b.txt");
+        assert_eq!(to_backslashes(r"D:\data"), r"D:\data");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_forward_slash_root() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        File::create(dir.path().join("sub").join("a.txt")).unwrap();
+        let root = dir.path().to_string_lossy().replace('\\', "/");
+
+        let extractor = MetadataExtractor::new();
+        let entry = extractor
+            .extract(&Path::new(&root).join("sub").join("a.txt"))
+            .unwrap();
+
+        assert!(!entry.path.contains('/'));
+        assert!(!entry.parent.contains('/'));
+        assert!(entry.path.ends_with(r"\sub\a.txt"));
+        assert_eq!(
+            entry.drive,
+            root.chars().next().unwrap().to_ascii_uppercase()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_to_ancestor_is_cycle() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("a"), nested.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&nested, dir.path().join("shortcut")).unwrap();
+
+        let extractor = MetadataExtractor::new();
+        assert!(extractor.extract(&nested.join("loop")).unwrap().is_cycle);
+        // Points down the tree, not up: no cycle
+        assert!(
+            !extractor
+                .extract(&dir.path().join("shortcut"))
+                .unwrap()
+                .is_cycle
+        );
+        assert!(!extractor.extract(&nested).unwrap().is_cycle);
+    }
+
+    #[test]
+    fn test_fill_timestamps() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("mft.txt");
+        File::create(&file_path).unwrap();
+
+        let extractor = MetadataExtractor::new();
+        let mut entry = extractor.extract(&file_path).unwrap();
+        entry.modified = None;
+        entry.accessed = None;
+
+        assert!(extractor.fill_timestamps(&mut entry));
+        assert!(entry.modified.is_some());
+        assert!(entry.accessed.is_some());
+
+        entry.path = dir.path().join("gone.txt").display().to_string();
+        assert!(!extractor.fill_timestamps(&mut entry));
+    }
+
+    #[test]
+    fn test_offline_attributes() {
+        assert!(is_offline_attributes(0x0000_1000)); // OFFLINE
+        assert!(is_offline_attributes(0x0040_0000 | 0x20)); // RECALL_ON_DATA_ACCESS | ARCHIVE
+        assert!(!is_offline_attributes(0x20)); // ARCHIVE only
+        assert!(!is_offline_attributes(0x10)); // DIRECTORY
+    }
+
+    #[test]
+    fn test_local_file_not_offline() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("local.txt");
+        File::create(&file_path).unwrap();
+
+        let entry = MetadataExtractor::new().extract(&file_path).unwrap();
+        assert!(!entry.is_offline);
+    }
 }
@@ -3,9 +3,14 @@
 //! Reads the NTFS Master File Table directly for sub-second file listing.
 //! This is the same technique used by "Everything" search.
 
+use super::metadata_extractor::{is_offline_attributes, links_to_ancestor};
+#[cfg(windows)]
+use super::usn_record::{parse_usn_records, ref_key};
 use crate::{FileEntry, NexusError, Result};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use tracing::{debug, info, warn};
+#[cfg(windows)]
+use tracing::info;
 
 #[cfg(windows)]
 use windows::{
@@ -16,10 +21,56 @@ use windows::{
             CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_SHARE_DELETE, FILE_SHARE_READ,
             FILE_SHARE_WRITE, OPEN_EXISTING,
         },
-        System::Ioctl::{FSCTL_ENUM_USN_DATA, FSCTL_GET_NTFS_VOLUME_DATA, NTFS_VOLUME_DATA_BUFFER},
+        System::Ioctl::{
+            FSCTL_ENUM_USN_DATA, FSCTL_GET_NTFS_FILE_RECORD, FSCTL_GET_NTFS_VOLUME_DATA,
+            NTFS_FILE_RECORD_INPUT_BUFFER, NTFS_FILE_RECORD_OUTPUT_BUFFER, NTFS_VOLUME_DATA_BUFFER,
+        },
     },
 };
 
+/// MFT record number of the volume root directory
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) const ROOT_FILE_REF: u128 = 5;
+
+/// MFT records below this number are reserved for NTFS metafiles
+/// (`$MFT`, `$LogFile`, `$Bitmap`, `$Secure`, `$Extend`, ...)
+#[cfg_attr(not(windows), allow(dead_code))]
+const FIRST_USER_FILE_REF: u64 = 16;
+
+/// Options controlling MFT enumeration
+#[derive(Debug, Clone, Default)]
+pub struct MftScanOptions {
+    /// Emit NTFS metafiles (`$MFT`, `$Bitmap`, `$Extend\...`) as entries.
+    /// They are not user files, so this is off by default.
+    pub include_metafiles: bool,
+    /// Read each entry's own MFT record for its size and created, modified
+    /// and accessed times. Enumeration alone carries none of them, so
+    /// without this sizes are 0 and times `None`. Costs one more
+    /// `FSCTL_GET_NTFS_FILE_RECORD` per entry.
+    pub resolve_sizes: bool,
+}
+
+/// Size and times read from a file's MFT record
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RecordDetails {
+    /// Size of the unnamed data stream; `None` for directories, and when
+    /// the stream is described in another record (attribute lists)
+    size: Option<u64>,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+}
+
+/// Compact per-record data collected during enumeration;
+/// full entries are only built when emitted
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) struct RawRecord {
+    /// File references keyed by [`ref_key`]
+    file_ref: u128,
+    parent_ref: u128,
+    attributes: u32,
+}
+
 /// MFT Reader for NTFS volumes
 pub struct MftReader {
     #[allow(dead_code)]
@@ -42,10 +93,97 @@ impl MftReader {
     ///
     /// This is the fastest method to enumerate all files on an NTFS volume.
     /// Returns all file entries in sub-second time for typical drives.
-    #[cfg(windows)]
     pub fn scan_volume(drive: char) -> Result<Vec<FileEntry>> {
-        info!("Scanning drive {} using MFT reader", drive);
+        let mut entries = Vec::new();
+        Self::enumerate_with(drive, |entry| entries.push(entry))?;
+        Ok(entries)
+    }
+
+    /// Scan an NTFS volume, skipping records whose file reference number
+    /// matches `skip_refs`
+    ///
+    /// Skipped records are never reconstructed into entries, though their
+    /// names still make up the paths of their children. Metafile filtering
+    /// applies as in `scan_volume`, so e.g. `|r| r < 27` drops records 16-26
+    /// as well.
+    pub fn scan_volume_filtered(
+        drive: char,
+        skip_refs: impl Fn(u64) -> bool,
+    ) -> Result<Vec<FileEntry>> {
+        let mut entries = Vec::new();
+        Self::enumerate_filtered(drive, &MftScanOptions::default(), &skip_refs, |entry| {
+            entries.push(entry);
+            true
+        })?;
+        Ok(entries)
+    }
+
+    /// Enumerate an NTFS volume with default options
+    ///
+    /// See `enumerate_with_options`.
+    pub fn enumerate_with<F: FnMut(FileEntry)>(drive: char, callback: F) -> Result<u64> {
+        Self::enumerate_with_options(drive, &MftScanOptions::default(), callback)
+    }
+
+    /// Enumerate an NTFS volume, invoking `callback` once per reconstructed entry
+    ///
+    /// Unlike `scan_volume`, entries are handed to the callback and dropped
+    /// instead of being collected. Path reconstruction still needs the
+    /// compact file-reference map for the whole volume, but the full
+    /// `FileEntry` set is never held in memory at once.
+    ///
+    /// Returns the number of entries passed to the callback.
+    pub fn enumerate_with_options<F: FnMut(FileEntry)>(
+        drive: char,
+        options: &MftScanOptions,
+        mut callback: F,
+    ) -> Result<u64> {
+        Self::enumerate_filtered(drive, options, &|_| false, |entry| {
+            callback(entry);
+            true
+        })
+    }
+
+    /// Enumerate an NTFS volume until `callback` returns false
+    ///
+    /// As `enumerate_with_options`, but no further entries are built (nor
+    /// their records read for `resolve_sizes`) once the callback asks to
+    /// stop. The volume's records are still all read first, as paths can't
+    /// be reconstructed without them. Returns the number of entries passed
+    /// to the callback.
+    pub fn enumerate_while<F: FnMut(FileEntry) -> bool>(
+        drive: char,
+        options: &MftScanOptions,
+        callback: F,
+    ) -> Result<u64> {
+        Self::enumerate_filtered(drive, options, &|_| false, callback)
+    }
 
+    /// Number of records the volume's MFT has room for
+    ///
+    /// Read from the volume's NTFS data without enumerating anything, so
+    /// it's cheap. An upper bound on the entries a scan finds: free records,
+    /// metafiles and extension records are counted too. Needs the same
+    /// access as a scan.
+    #[cfg(windows)]
+    pub fn record_count(drive: char) -> Result<u64> {
+        let (handle, volume_data) = Self::open_volume(drive)?;
+        let _ = unsafe { CloseHandle(handle) };
+
+        let record_size = u64::from(volume_data.BytesPerFileRecordSegment.max(1));
+        Ok(volume_data.MftValidDataLength.max(0) as u64 / record_size)
+    }
+
+    #[cfg(not(windows))]
+    pub fn record_count(_drive: char) -> Result<u64> {
+        Err(NexusError::Windows(
+            "MFT reader is only available on Windows".into(),
+        ))
+    }
+
+    /// Open a volume and read its NTFS data; the caller closes the handle
+    #[cfg(windows)]
+    fn open_volume(drive: char) -> Result<(HANDLE, NTFS_VOLUME_DATA_BUFFER)> {
         let volume_path: Vec<u16> = format!("\\\\.\\{}:", drive)
             .encode_utf16()
             .chain(std::iter::once(0))
@@ -96,42 +234,92 @@ impl MftReader {
             return Err(NexusError::Windows("Failed to get NTFS volume data".into()));
         }
 
+        Ok((handle, volume_data))
+    }
+
+    /// Enumerate an NTFS volume, skipping records matching `skip_refs`,
+    /// until `callback` returns false
+    #[cfg(windows)]
+    fn enumerate_filtered<F: FnMut(FileEntry) -> bool>(
+        drive: char,
+        options: &MftScanOptions,
+        skip_refs: &dyn Fn(u64) -> bool,
+        mut callback: F,
+    ) -> Result<u64> {
+        info!("Scanning drive {} using MFT reader", drive);
+
+        let (handle, volume_data) = Self::open_volume(drive)?;
+
         info!(
             "NTFS Volume {}: Total clusters: {}, Bytes per cluster: {}",
             drive, volume_data.TotalClusters, volume_data.BytesPerCluster
         );
 
         // Enumerate USN data to get all files
-        let entries = Self::enumerate_usn_data(handle, drive, &volume_data)?;
+        let result = Self::enumerate_usn_data(
+            handle,
+            drive,
+            &volume_data,
+            options,
+            skip_refs,
+            &mut callback,
+        );
 
         let _ = unsafe { CloseHandle(handle) };
 
-        Ok(entries)
+        result
     }
 
     #[cfg(windows)]
     fn enumerate_usn_data(
         handle: HANDLE,
         drive: char,
-        _volume_data: &NTFS_VOLUME_DATA_BUFFER,
-    ) -> Result<Vec<FileEntry>> {
+        volume_data: &NTFS_VOLUME_DATA_BUFFER,
+        options: &MftScanOptions,
+        skip_refs: &dyn Fn(u64) -> bool,
+        callback: &mut dyn FnMut(FileEntry) -> bool,
+    ) -> Result<u64> {
+        let (records, file_refs) = Self::read_usn_data(handle);
+        info!("MFT enumeration found {} raw records", records.len());
+
+        let record_size = volume_data.BytesPerFileRecordSegment as usize;
+        let details = |file_ref: u64| Self::read_record_details(handle, file_ref, record_size);
+        let emitted = emit_entries(
+            drive, records, file_refs, options, skip_refs, &details, callback,
+        );
+
+        info!("Reconstructed {} file paths", emitted);
+        Ok(emitted)
+    }
+
+    /// Enumerate every record of an open volume: the raw records, and
+    /// file_ref -> (name, parent_ref) for path reconstruction
+    ///
+    /// Asks for V2 or V3 records, so volumes with 128-bit file references
+    /// enumerate too. Enumeration stops quietly at the first failed request.
+    #[cfg(windows)]
+    pub(super) fn read_usn_data(handle: HANDLE) -> (Vec<RawRecord>, HashMap<u128, (String, u128)>) {
         use std::mem::size_of;
 
-        let mut entries = Vec::new();
-        let mut file_refs: HashMap<u64, (String, u64)> = HashMap::new(); // file_ref -> (name, parent_ref)
+        let mut records: Vec<RawRecord> = Vec::new();
+        let mut file_refs: HashMap<u128, (String, u128)> = HashMap::new();
 
-        // MFT enumeration input buffer
+        // MFT_ENUM_DATA_V1
         #[repr(C)]
         struct MftEnumData {
             start_file_reference: u64,
             low_usn: i64,
             high_usn: i64,
+            min_major_version: u16,
+            max_major_version: u16,
         }
 
         let mut enum_data = MftEnumData {
             start_file_reference: 0,
             low_usn: 0,
             high_usn: i64::MAX,
+            min_major_version: 2,
+            max_major_version: 3,
         };
 
         let buffer_size = 64 * 1024; // 64KB buffer
@@ -152,186 +340,322 @@ impl MftReader {
                 )
             };
 
-            if result.is_err() || bytes_returned == 0 {
+            if result.is_err() || bytes_returned <= 8 {
                 break;
             }
 
-            // Parse USN records from buffer
-            let next_usn = unsafe { *(buffer.as_ptr() as *const u64) };
-            let mut offset = 8usize; // Skip the next USN value
-
-            while offset < bytes_returned as usize {
-                // USN_RECORD_V2 structure
-                #[repr(C)]
-                #[allow(non_snake_case)]
-                struct UsnRecord {
-                    RecordLength: u32,
-                    MajorVersion: u16,
-                    MinorVersion: u16,
-                    FileReferenceNumber: u64,
-                    ParentFileReferenceNumber: u64,
-                    Usn: i64,
-                    TimeStamp: i64,
-                    Reason: u32,
-                    SourceInfo: u32,
-                    SecurityId: u32,
-                    FileAttributes: u32,
-                    FileNameLength: u16,
-                    FileNameOffset: u16,
-                    // FileName follows (variable length)
-                }
+            // The buffer starts with the reference to continue from
+            let next_ref = u64::from_ne_bytes(buffer[..8].try_into().unwrap_or_default());
 
-                // Validate we have enough bytes for the record header
-                if offset + size_of::<UsnRecord>() > bytes_returned as usize {
-                    break;
+            for record in parse_usn_records(&buffer[8..bytes_returned as usize]) {
+                if record.name.is_empty() {
+                    continue;
                 }
+                let file_ref = ref_key(record.file_ref);
+                let parent_ref = ref_key(record.parent_ref);
+
+                // Full entries are built after all records are collected
+                records.push(RawRecord {
+                    file_ref,
+                    parent_ref,
+                    attributes: record.attributes,
+                });
+                file_refs.insert(file_ref, (record.name, parent_ref));
+            }
 
-                let record = unsafe { &*(buffer.as_ptr().add(offset) as *const UsnRecord) };
+            enum_data.start_file_reference = next_ref;
+        }
 
-                // Validate record length is reasonable and non-zero
-                if record.RecordLength == 0 || record.RecordLength as usize > buffer_size {
-                    break;
-                }
+        (records, file_refs)
+    }
 
-                // Validate the record doesn't extend beyond buffer
-                if offset + record.RecordLength as usize > bytes_returned as usize {
-                    warn!("USN record extends beyond buffer at offset {}", offset);
-                    break;
-                }
+    /// Fetch the MFT record of `file_ref` and parse its size and times
+    #[cfg(windows)]
+    fn read_record_details(
+        handle: HANDLE,
+        file_ref: u64,
+        record_size: usize,
+    ) -> Option<RecordDetails> {
+        use std::mem::{offset_of, size_of};
 
-                // Extract filename
-                let name_offset = offset + record.FileNameOffset as usize;
-                let name_len = record.FileNameLength as usize / 2; // UTF-16
-
-                // Validate filename offset and length are within the record bounds
-                let name_end = name_offset + record.FileNameLength as usize;
-                let record_end = offset + record.RecordLength as usize;
-
-                if name_offset < offset + size_of::<UsnRecord>()
-                    || name_end > record_end
-                    || name_end > bytes_returned as usize
-                {
-                    debug!("Invalid filename bounds at offset {}: name_offset={}, name_len={}, record_len={}",
-                        offset, name_offset, name_len, record.RecordLength);
-                    offset += record.RecordLength as usize;
-                    continue;
-                }
+        let input = NTFS_FILE_RECORD_INPUT_BUFFER {
+            FileReferenceNumber: file_ref as i64,
+        };
+        let header = offset_of!(NTFS_FILE_RECORD_OUTPUT_BUFFER, FileRecordBuffer);
+        let mut buffer = vec![0u8; header + record_size.max(1024)];
+        let mut bytes_returned: u32 = 0;
 
-                if name_len > 0
-                    && name_offset + record.FileNameLength as usize <= bytes_returned as usize
-                {
-                    let name_ptr = unsafe { buffer.as_ptr().add(name_offset) as *const u16 };
-                    let name_slice = unsafe { std::slice::from_raw_parts(name_ptr, name_len) };
-                    let name = String::from_utf16_lossy(name_slice);
-
-                    // Store for path reconstruction
-                    file_refs.insert(
-                        record.FileReferenceNumber & 0x0000FFFFFFFFFFFF, // Mask sequence number
-                        (
-                            name.clone(),
-                            record.ParentFileReferenceNumber & 0x0000FFFFFFFFFFFF,
-                        ),
-                    );
-
-                    // Create file entry
-                    let is_dir = (record.FileAttributes & 0x10) != 0; // FILE_ATTRIBUTE_DIRECTORY
-                    let is_hidden = (record.FileAttributes & 0x02) != 0; // FILE_ATTRIBUTE_HIDDEN
-                    let is_system = (record.FileAttributes & 0x04) != 0; // FILE_ATTRIBUTE_SYSTEM
-
-                    let extension = if !is_dir {
-                        name.rsplit('.').next().map(|s| s.to_lowercase())
-                    } else {
-                        None
-                    };
-
-                    // We'll reconstruct full paths after collecting all records
-                    entries.push((
-                        record.FileReferenceNumber & 0x0000FFFFFFFFFFFF,
-                        record.ParentFileReferenceNumber & 0x0000FFFFFFFFFFFF,
-                        FileEntry {
-                            path: String::new(), // Will be filled later
-                            name: name.clone(),
-                            extension,
-                            size: 0, // MFT enum doesn't give size directly
-                            created: None,
-                            modified: None,
-                            accessed: None,
-                            is_dir,
-                            is_hidden,
-                            is_system,
-                            content_hash: None,
-                            parent: String::new(),
-                            drive,
-                        },
-                    ));
-                }
+        unsafe {
+            windows::Win32::System::IO::DeviceIoControl(
+                handle,
+                FSCTL_GET_NTFS_FILE_RECORD,
+                Some(&input as *const _ as *const _),
+                size_of::<NTFS_FILE_RECORD_INPUT_BUFFER>() as u32,
+                Some(buffer.as_mut_ptr() as *mut _),
+                buffer.len() as u32,
+                Some(&mut bytes_returned),
+                None,
+            )
+        }
+        .ok()?;
+
+        // The volume returns the nearest lower record in use when this one
+        // isn't, so make sure it's the one asked for. The Vec's buffer is
+        // only byte-aligned, so the header is read field by field.
+        let at = offset_of!(NTFS_FILE_RECORD_OUTPUT_BUFFER, FileReferenceNumber);
+        let returned_ref = u64::from_le_bytes(buffer.get(at..at + 8)?.try_into().ok()?);
+        if returned_ref & 0x0000FFFFFFFFFFFF != file_ref {
+            return None;
+        }
+        let at = offset_of!(NTFS_FILE_RECORD_OUTPUT_BUFFER, FileRecordLength);
+        let length = u32::from_le_bytes(buffer.get(at..at + 4)?.try_into().ok()?);
+        let end = (header + length as usize).min(bytes_returned as usize);
+        let record = buffer.get_mut(header..end)?;
+        apply_fixups(record)?;
+        parse_file_record(record)
+    }
 
-                offset += record.RecordLength as usize;
-                if record.RecordLength == 0 {
-                    break;
-                }
-            }
+    #[cfg(not(windows))]
+    fn enumerate_filtered<F: FnMut(FileEntry) -> bool>(
+        _drive: char,
+        _options: &MftScanOptions,
+        _skip_refs: &dyn Fn(u64) -> bool,
+        _callback: F,
+    ) -> Result<u64> {
+        Err(NexusError::Windows(
+            "MFT reader is only available on Windows".into(),
+        ))
+    }
+}
 
-            // Update for next iteration
-            enum_data.start_file_reference = next_usn;
+/// Reconstruct full paths for collected records and hand each entry to
+/// `callback`, stopping once it returns false
+///
+/// Returns the number of entries emitted.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn emit_entries(
+    drive: char,
+    records: Vec<RawRecord>,
+    mut file_refs: HashMap<u128, (String, u128)>,
+    options: &MftScanOptions,
+    skip_refs: &dyn Fn(u64) -> bool,
+    details: &dyn Fn(u64) -> Option<RecordDetails>,
+    callback: &mut dyn FnMut(FileEntry) -> bool,
+) -> u64 {
+    // Reconstruct full paths
+    file_refs.insert(ROOT_FILE_REF, (format!("{}:", drive), 0));
+
+    fn build_path(
+        file_ref: u128,
+        file_refs: &HashMap<u128, (String, u128)>,
+        cache: &mut HashMap<u128, String>,
+    ) -> String {
+        if let Some(cached) = cache.get(&file_ref) {
+            return cached.clone();
         }
 
-        info!("MFT enumeration found {} raw records", entries.len());
+        if let Some((name, parent_ref)) = file_refs.get(&file_ref) {
+            let path = if *parent_ref == 0 || *parent_ref == file_ref {
+                name.clone()
+            } else {
+                let parent_path = build_path(*parent_ref, file_refs, cache);
+                format!("{}\\{}", parent_path, name)
+            };
+            cache.insert(file_ref, path.clone());
+            path
+        } else {
+            String::new()
+        }
+    }
 
-        // Reconstruct full paths
-        let root_ref = 5u64; // MFT root directory reference
-        file_refs.insert(root_ref, (format!("{}:", drive), 0));
+    let mut path_cache: HashMap<u128, String> = HashMap::new();
+
+    // Metafile directories (e.g. `$Extend`) have children with ordinary
+    // record numbers, so those are matched by path prefix instead.
+    let metafile_roots: Vec<String> = if options.include_metafiles {
+        Vec::new()
+    } else {
+        (0..FIRST_USER_FILE_REF as u128)
+            .filter(|r| *r != ROOT_FILE_REF && file_refs.contains_key(r))
+            .map(|r| format!("{}\\", build_path(r, &file_refs, &mut path_cache)))
+            .collect()
+    };
+
+    let mut emitted = 0u64;
+
+    for record in records {
+        // NTFS record number; 128-bit references (ReFS) have none
+        let number = u64::try_from(record.file_ref).ok();
+        if !options.include_metafiles && number.is_some_and(|n| n < FIRST_USER_FILE_REF) {
+            continue;
+        }
+        if number.is_some_and(skip_refs) {
+            continue;
+        }
 
-        fn build_path(
-            file_ref: u64,
-            file_refs: &HashMap<u64, (String, u64)>,
-            cache: &mut HashMap<u64, String>,
-        ) -> String {
-            if let Some(cached) = cache.get(&file_ref) {
-                return cached.clone();
-            }
+        let path = build_path(record.file_ref, &file_refs, &mut path_cache);
+        if path.is_empty() {
+            continue;
+        }
 
-            if let Some((name, parent_ref)) = file_refs.get(&file_ref) {
-                let path = if *parent_ref == 0 || *parent_ref == file_ref {
-                    name.clone()
-                } else {
-                    let parent_path = build_path(*parent_ref, file_refs, cache);
-                    format!("{}\\{}", parent_path, name)
-                };
-                cache.insert(file_ref, path.clone());
-                path
-            } else {
-                String::new()
-            }
+        if metafile_roots.iter().any(|root| path.starts_with(root)) {
+            continue;
         }
 
-        let mut path_cache: HashMap<u64, String> = HashMap::new();
+        let name = match file_refs.get(&record.file_ref) {
+            Some((name, _)) => name.clone(),
+            None => continue,
+        };
 
-        let final_entries: Vec<FileEntry> = entries
-            .into_iter()
-            .filter_map(|(file_ref, parent_ref, mut entry)| {
-                let path = build_path(file_ref, &file_refs, &mut path_cache);
-                if path.is_empty() {
-                    return None;
-                }
+        let is_dir = (record.attributes & 0x10) != 0; // FILE_ATTRIBUTE_DIRECTORY
+        let is_hidden = (record.attributes & 0x02) != 0; // FILE_ATTRIBUTE_HIDDEN
+        let is_system = (record.attributes & 0x04) != 0; // FILE_ATTRIBUTE_SYSTEM
+        let is_offline = is_offline_attributes(record.attributes);
+        // Only reparse-point directories (junctions, directory symlinks) can loop
+        let is_cycle = is_dir
+            && (record.attributes & 0x400) != 0 // FILE_ATTRIBUTE_REPARSE_POINT
+            && links_to_ancestor(std::path::Path::new(&path));
+
+        let extension = if !is_dir {
+            name.rsplit('.').next().map(|s| s.to_string())
+        } else {
+            None
+        };
 
-                entry.path = path.clone();
-                entry.parent = build_path(parent_ref, &file_refs, &mut path_cache);
+        // MFT enumeration gives neither sizes nor times
+        let details = if options.resolve_sizes {
+            number.and_then(details).unwrap_or_default()
+        } else {
+            RecordDetails::default()
+        };
 
-                Some(entry)
-            })
-            .collect();
+        let keep_going = callback(FileEntry {
+            path,
+            name,
+            extension,
+            size: details.size.unwrap_or(0),
+            created: details.created,
+            modified: details.modified,
+            accessed: details.accessed,
+            is_dir,
+            is_hidden,
+            is_system,
+            is_offline,
+            content_hash: None,
+            parent: build_path(record.parent_ref, &file_refs, &mut path_cache),
+            drive,
+            volume: format!("{}:", drive),
+            volume_id: None,
+            resolved_path: None,
+            is_cycle,
+            tags: Vec::new(),
+        });
+        emitted += 1;
+        if !keep_going {
+            break;
+        }
+    }
+
+    emitted
+}
 
-        info!("Reconstructed {} file paths", final_entries.len());
-        Ok(final_entries)
+/// Restore the last two bytes of each sector of an NTFS FILE record
+///
+/// On disk they hold the update sequence number, with the real bytes kept
+/// in the update sequence array; a sector whose bytes don't match the
+/// number was torn by an interrupted write and fails the record.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn apply_fixups(record: &mut [u8]) -> Option<()> {
+    const SECTOR: usize = 512;
+
+    let u16_at = |record: &[u8], at: usize| {
+        Some(u16::from_le_bytes(record.get(at..at + 2)?.try_into().ok()?))
+    };
+    let array = u16_at(record, 0x04)? as usize;
+    let count = u16_at(record, 0x06)? as usize;
+    let usn = u16_at(record, array)?;
+
+    for sector in 1..count {
+        let end = sector * SECTOR;
+        if end > record.len() {
+            break;
+        }
+        if u16_at(record, end - 2)? != usn {
+            return None;
+        }
+        let original = u16_at(record, array + 2 * sector)?;
+        record[end - 2..end].copy_from_slice(&original.to_le_bytes());
     }
+    Some(())
+}
 
-    #[cfg(not(windows))]
-    pub fn scan_volume(drive: char) -> Result<Vec<FileEntry>> {
-        Err(NexusError::Windows(
-            "MFT reader is only available on Windows".into(),
-        ))
+/// Parse the `$STANDARD_INFORMATION` and unnamed `$DATA` attributes of an
+/// NTFS FILE record
+///
+/// The record must have had [`apply_fixups`] applied, or values that
+/// straddle a sector end come out wrong.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_file_record(record: &[u8]) -> Option<RecordDetails> {
+    const STANDARD_INFORMATION: u32 = 0x10;
+    const DATA: u32 = 0x80;
+    const END: u32 = 0xFFFF_FFFF;
+
+    let u16_at = |at: usize| Some(u16::from_le_bytes(record.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(record.get(at..at + 4)?.try_into().ok()?));
+    let u64_at = |at: usize| Some(u64::from_le_bytes(record.get(at..at + 8)?.try_into().ok()?));
+
+    if record.get(..4)? != b"FILE" {
+        return None;
+    }
+    let used = (u32_at(0x18)? as usize).min(record.len());
+
+    let mut details = RecordDetails::default();
+    let mut offset = u16_at(0x14)? as usize;
+    while offset + 16 <= used {
+        let kind = u32_at(offset)?;
+        let length = u32_at(offset + 4)? as usize;
+        if kind == END || length == 0 {
+            break;
+        }
+        let non_resident = record[offset + 8] != 0;
+        let name_length = record[offset + 9];
+
+        match kind {
+            STANDARD_INFORMATION if !non_resident => {
+                let value = offset + u16_at(offset + 0x14)? as usize;
+                let time = |at: usize| u64_at(value + at).and_then(filetime_to_datetime);
+                details.created = time(0);
+                details.modified = time(8);
+                details.accessed = time(24);
+            }
+            DATA if name_length == 0 => {
+                details.size = if non_resident {
+                    // Only the first extent (starting VCN 0) carries sizes
+                    match u64_at(offset + 0x10)? {
+                        0 => u64_at(offset + 0x30),
+                        _ => details.size,
+                    }
+                } else {
+                    u32_at(offset + 0x10).map(u64::from)
+                };
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+    Some(details)
+}
+
+/// Convert a FILETIME (100ns intervals since 1601) to UTC; 0 means unset
+#[cfg_attr(not(windows), allow(dead_code))]
+fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    const UNIX_EPOCH_SECS: i64 = 11_644_473_600;
+    if filetime == 0 {
+        return None;
     }
+    let secs = (filetime / 10_000_000) as i64 - UNIX_EPOCH_SECS;
+    let nanos = (filetime % 10_000_000) as u32 * 100;
+    DateTime::from_timestamp(secs, nanos)
 }
 
 impl Drop for MftReader {
@@ -344,3 +668,227 @@ impl Drop for MftReader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a small synthetic volume: NTFS metafiles plus a user file
+    fn synthetic_volume() -> (Vec<RawRecord>, HashMap<u128, (String, u128)>) {
+        let raw = [
+            (0u128, ROOT_FILE_REF, "$MFT", 0x06u32),
+            (6, ROOT_FILE_REF, "$Bitmap", 0x06),
+            (11, ROOT_FILE_REF, "$Extend", 0x16),
+            (40, 11, "$UsnJrnl", 0x06),
+            (100, ROOT_FILE_REF, "Users", 0x10),
+            (101, 100, "notes.txt", 0x20),
+        ];
+
+        let records = raw
+            .iter()
+            .map(|(file_ref, parent_ref, _, attributes)| RawRecord {
+                file_ref: *file_ref,
+                parent_ref: *parent_ref,
+                attributes: *attributes,
+            })
+            .collect();
+        let file_refs = raw
+            .iter()
+            .map(|(file_ref, parent_ref, name, _)| (*file_ref, (name.to_string(), *parent_ref)))
+            .collect();
+
+        (records, file_refs)
+    }
+
+    fn emit(options: &MftScanOptions) -> Vec<FileEntry> {
+        emit_skipping(options, &|_| false)
+    }
+
+    fn emit_skipping(options: &MftScanOptions, skip_refs: &dyn Fn(u64) -> bool) -> Vec<FileEntry> {
+        let (records, file_refs) = synthetic_volume();
+        let mut entries = Vec::new();
+        // Every file is 42 bytes, modified at the Unix epoch
+        let details = |_| {
+            Some(RecordDetails {
+                size: Some(42),
+                modified: DateTime::from_timestamp(0, 0),
+                ..Default::default()
+            })
+        };
+        emit_entries(
+            'C',
+            records,
+            file_refs,
+            options,
+            skip_refs,
+            &details,
+            &mut |e| {
+                entries.push(e);
+                true
+            },
+        );
+        entries
+    }
+
+    #[test]
+    fn test_skip_refs() {
+        // Skipped parents still name their children's paths
+        let entries = emit_skipping(&MftScanOptions::default(), &|r| r == 100);
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["C:\\Users\\notes.txt"]);
+
+        let entries = emit_skipping(
+            &MftScanOptions {
+                include_metafiles: true,
+                ..Default::default()
+            },
+            &|r| r < 27,
+        );
+        assert!(!entries.iter().any(|e| e.name == "$MFT"));
+        assert!(entries.iter().any(|e| e.name == "$UsnJrnl"));
+    }
+
+    #[test]
+    fn test_emit_stops_when_asked() {
+        let (records, file_refs) = synthetic_volume();
+        let looked_up = std::cell::Cell::new(0);
+        let details = |_| {
+            looked_up.set(looked_up.get() + 1);
+            None
+        };
+        let mut names = Vec::new();
+        let emitted = emit_entries(
+            'C',
+            records,
+            file_refs,
+            &MftScanOptions {
+                include_metafiles: true,
+                resolve_sizes: true,
+            },
+            &|_| false,
+            &details,
+            &mut |e| {
+                names.push(e.name);
+                names.len() < 2
+            },
+        );
+
+        assert_eq!(emitted, 2);
+        assert_eq!(names, vec!["$MFT", "$Bitmap"]);
+        assert_eq!(looked_up.get(), 2);
+    }
+
+    #[test]
+    fn test_metafiles_filtered_by_default() {
+        let entries = emit(&MftScanOptions::default());
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+
+        assert!(!entries.iter().any(|e| e.name == "$MFT"));
+        assert!(!entries.iter().any(|e| e.name.starts_with('$')));
+        assert_eq!(paths, vec!["C:\\Users", "C:\\Users\\notes.txt"]);
+    }
+
+    #[test]
+    fn test_include_metafiles() {
+        let entries = emit(&MftScanOptions {
+            include_metafiles: true,
+            ..Default::default()
+        });
+
+        assert!(entries.iter().any(|e| e.path == "C:\\$MFT"));
+        assert!(entries.iter().any(|e| e.path == "C:\\$Extend\\$UsnJrnl"));
+        assert_eq!(entries.len(), 6);
+    }
+
+    #[test]
+    fn test_emitted_entry_fields() {
+        let entries = emit(&MftScanOptions::default());
+        let file = entries.iter().find(|e| e.name == "notes.txt").unwrap();
+
+        assert_eq!(file.parent, "C:\\Users");
+        assert_eq!(file.extension.as_deref(), Some("txt"));
+        assert_eq!(file.drive, 'C');
+        assert!(!file.is_dir);
+        assert_eq!(file.size, 0);
+        assert_eq!(file.modified, None);
+
+        let entries = emit(&MftScanOptions {
+            resolve_sizes: true,
+            ..Default::default()
+        });
+        let file = entries.iter().find(|e| e.name == "notes.txt").unwrap();
+        assert_eq!(file.size, 42);
+        assert_eq!(file.modified, DateTime::from_timestamp(0, 0));
+    }
+
+    /// A FILE record with `$STANDARD_INFORMATION` and an unnamed `$DATA`
+    fn file_record(data: &[u8]) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[..4].copy_from_slice(b"FILE");
+        record[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        record[0x18..0x1C].copy_from_slice(&0x200u32.to_le_bytes());
+
+        let mut put = |at: usize, bytes: &[u8]| record[at..at + bytes.len()].copy_from_slice(bytes);
+        // $STANDARD_INFORMATION, resident, value at +0x18
+        let epoch = 116_444_736_000_000_000u64;
+        put(0x38, &0x10u32.to_le_bytes());
+        put(0x3C, &0x60u32.to_le_bytes());
+        put(0x38 + 0x14, &0x18u16.to_le_bytes());
+        put(0x38 + 0x18, &epoch.to_le_bytes());
+        put(0x38 + 0x20, &(epoch + 10_000_000).to_le_bytes());
+        // $DATA at 0x98
+        put(0x98, &0x80u32.to_le_bytes());
+        put(0x9C, &0x48u32.to_le_bytes());
+        put(0x98 + 8, data);
+        put(0xE0, &0xFFFF_FFFFu32.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn test_parse_file_record() {
+        // Resident data: value length at +0x10
+        let mut resident = [0u8; 0x14];
+        resident[8..12].copy_from_slice(&13u32.to_le_bytes());
+        let details = parse_file_record(&file_record(&resident)).unwrap();
+        assert_eq!(details.size, Some(13));
+        assert_eq!(details.created, DateTime::from_timestamp(0, 0));
+        assert_eq!(details.modified, DateTime::from_timestamp(1, 0));
+        assert_eq!(details.accessed, None);
+
+        // Non-resident data: real size at +0x30, first extent only
+        let mut non_resident = [0u8; 0x30];
+        non_resident[0] = 1;
+        non_resident[0x28..0x30].copy_from_slice(&5_000_000u64.to_le_bytes());
+        let details = parse_file_record(&file_record(&non_resident)).unwrap();
+        assert_eq!(details.size, Some(5_000_000));
+
+        non_resident[8] = 4; // starting VCN
+        let details = parse_file_record(&file_record(&non_resident)).unwrap();
+        assert_eq!(details.size, None);
+
+        assert_eq!(parse_file_record(b"BAAD"), None);
+    }
+
+    #[test]
+    fn test_apply_fixups() {
+        let mut record = file_record(&[0u8; 0x14]);
+        // Update sequence array at 0x30: number 7, then one entry per sector
+        record[0x04..0x06].copy_from_slice(&0x30u16.to_le_bytes());
+        record[0x06..0x08].copy_from_slice(&3u16.to_le_bytes());
+        record[0x30..0x32].copy_from_slice(&7u16.to_le_bytes());
+        record[0x32..0x34].copy_from_slice(&[0xAB, 0xCD]);
+        record[0x34..0x36].copy_from_slice(&[0x12, 0x34]);
+        record[510..512].copy_from_slice(&7u16.to_le_bytes());
+        record[1022..1024].copy_from_slice(&7u16.to_le_bytes());
+
+        let mut fixed = record.clone();
+        assert_eq!(apply_fixups(&mut fixed), Some(()));
+        assert_eq!(fixed[510..512], [0xAB, 0xCD]);
+        assert_eq!(fixed[1022..1024], [0x12, 0x34]);
+        assert!(parse_file_record(&fixed).is_some());
+
+        // A torn second sector
+        record[1022..1024].copy_from_slice(&8u16.to_le_bytes());
+        assert_eq!(apply_fixups(&mut record), None);
+    }
+}
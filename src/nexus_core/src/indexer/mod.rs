@@ -5,27 +5,69 @@
 //! - USN Journal for real-time change tracking
 //! - Parallel directory traversal for non-NTFS volumes
 
+mod backend;
 mod content_hasher;
+pub mod dedup;
+mod filter;
+mod live_index;
 mod metadata_extractor;
 mod mft_reader;
+pub mod ops;
+mod owner;
+mod persist;
+mod power;
+mod progress;
+pub mod scan;
+pub mod shortcuts;
+mod stats;
+pub mod treemap;
 mod usn_journal;
+mod usn_record;
+mod volume;
 
-pub use content_hasher::ContentHasher;
+pub use backend::{EmitFn, IndexBackend, MftBackend, WalkdirBackend};
+pub use content_hasher::{ContentHasher, HashFormat};
+pub use filter::EntryFilter;
+pub use live_index::LiveIndex;
 pub use metadata_extractor::MetadataExtractor;
-pub use mft_reader::MftReader;
-pub use usn_journal::UsnJournal;
+pub use mft_reader::{MftReader, MftScanOptions};
+pub use persist::{SavedDrive, SavedIndexHeader, INDEX_FORMAT_VERSION};
+pub use power::is_on_battery;
+pub use progress::{ProgressInterval, ProgressThrottle, ScanProgressCallback};
+pub use stats::{ExtensionTotals, StatsAccumulator};
+pub use usn_journal::{ChangeType, JournalPosition, UsnChange, UsnJournal};
+pub use volume::{
+    drive_for_volume, drive_letter, resolve_network_path, volume_guid, volume_root, volume_serial,
+};
 
-use crate::{FileEntry, IndexStats, NexusError, Result};
+use crate::{ExtensionMatch, FileEntry, IndexStats, NexusError, Result};
 use dashmap::DashMap;
+use owner::OwnerFilter;
+use power::PowerGate;
 use rayon::prelude::*;
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info, warn};
 
 /// Index configuration
+///
+/// Timestamps by scan path:
+///
+/// | Scan                                 | created | modified | accessed |
+/// |--------------------------------------|---------|----------|----------|
+/// | walkdir (non-NTFS, fallback, dirs)   | yes     | yes      | yes      |
+/// | MFT                                  | no      | no       | no       |
+/// | MFT with `mft_timestamps`            | yes     | yes      | yes      |
+/// | MFT with `resolve_sizes`             | yes     | yes      | yes      |
+///
+/// MFT scans also leave `size` at 0 unless `resolve_sizes` is set.
+///
+/// Fields missing from serialized configs take their defaults.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct IndexConfig {
     /// Drives to index (e.g., ['C', 'D', 'E'])
     pub drives: Vec<char>,
@@ -33,18 +75,81 @@ pub struct IndexConfig {
     pub include_hidden: bool,
     /// Include system files
     pub include_system: bool,
+    /// Include directory entries; `false` gives a files-only index
+    pub index_directories: bool,
+    /// Include only directory entries, for a pure folder index
+    pub directories_only: bool,
     /// Compute content hashes for deduplication
     pub compute_hashes: bool,
     /// Maximum file size for hashing (in bytes)
     pub max_hash_size: u64,
+    /// String format for `FileEntry::content_hash`
+    pub hash_format: HashFormat,
     /// File extensions to index (empty = all)
     pub extensions: Vec<String>,
-    /// Directories to exclude
+    /// Compare `extensions` to the extension or as a name suffix
+    pub extension_match: ExtensionMatch,
+    /// Directories to exclude, as path substrings matched ignoring ASCII
+    /// case
     pub exclude_dirs: Vec<String>,
     /// Use MFT reader when available (faster)
     pub use_mft: bool,
+    /// Include NTFS metafiles ($MFT, $Bitmap, ...) in MFT scans
+    pub include_metafiles: bool,
+    /// Stat each MFT entry kept by the filters to fill in its created,
+    /// modified and accessed times. MFT enumeration carries no timestamps,
+    /// so without this they are `None`; with it, expect a large part of
+    /// the MFT speed advantage to be lost. Narrow the scan with
+    /// `extensions` / `exclude_dirs` to bound the cost.
+    pub mft_timestamps: bool,
+    /// Read each MFT entry's own file record for its size and times, which
+    /// enumeration alone doesn't provide: one more volume request per entry,
+    /// with no per-file open as `mft_timestamps` needs, which this makes
+    /// unnecessary. Leave off when only names and paths are needed.
+    pub resolve_sizes: bool,
+    /// Resolve DFS namespace paths to the share serving them, stored in
+    /// `FileEntry::resolved_path`. Costs a syscall and network round trip
+    /// per network entry; requires the `dfs` feature (no-op otherwise).
+    pub resolve_network_paths: bool,
     /// Number of parallel threads
     pub threads: usize,
+    /// Stop indexing once this many entries have been accepted (None = no cap).
+    /// Protects low-memory systems; pair with `index_all_with_sink` for
+    /// bounded-memory indexing of very large volumes.
+    pub max_entries: Option<usize>,
+    /// Return entries from `index_all`, `index_all_incremental` and
+    /// `index_directory_with` sorted so every directory precedes its
+    /// contents. Scans collect entries in no particular order; this adds a
+    /// parallel O(n log n) sort by path once the scan is done. Sink-based
+    /// indexing streams entries and is unaffected.
+    pub topological_order: bool,
+    /// Pause scanning while the machine runs on battery, resuming when AC
+    /// power returns. The power source is checked about once a second, so
+    /// a scan stops within a second of unplugging. Windows only; ignored
+    /// elsewhere.
+    pub pause_on_battery: bool,
+    /// How often scan progress is reported, every N entries or T ms;
+    /// per-entry callbacks would dominate a scan, especially across FFI
+    pub progress_interval: ProgressInterval,
+    /// Called with (entries indexed, estimated total) as drives and
+    /// directories are scanned. Not serialized.
+    #[serde(skip)]
+    pub progress_callback: Option<ScanProgressCallback>,
+    /// Entries the scan is expected to find, e.g. the size of the previous
+    /// index, reported as the progress total. Never below the entries
+    /// indexed so far. Without it, drive scans estimate the total from the
+    /// backends (the MFT record count, an overestimate); failing that, it
+    /// is only known at the end.
+    pub expected_entries: Option<u64>,
+    /// Keep only entries owned by the user running the process, for shared
+    /// machines and drives. Reads each entry's owner (its security
+    /// descriptor on Windows), so it's slow on a whole drive: prefer it with
+    /// `index_directory` on a subtree. Folders owned by others, such as
+    /// `C:\Users`, are dropped too, though their contents are still checked.
+    /// Elevated administrators often own new files through the
+    /// Administrators group rather than their own account, so those files
+    /// don't count as theirs.
+    pub current_user_only: bool,
 }
 
 impl Default for IndexConfig {
@@ -53,70 +158,444 @@ impl Default for IndexConfig {
             drives: vec!['C', 'D', 'E', 'F', 'G'],
             include_hidden: true,
             include_system: false,
+            index_directories: true,
+            directories_only: false,
             compute_hashes: false,
             max_hash_size: 100 * 1024 * 1024, // 100MB
+            hash_format: HashFormat::HexLower,
             extensions: vec![],
-            exclude_dirs: vec![
-                "$Recycle.Bin".to_string(),
-                "System Volume Information".to_string(),
-                "Windows".to_string(),
-                "Program Files".to_string(),
-                "Program Files (x86)".to_string(),
-                "ProgramData".to_string(),
-            ],
+            extension_match: ExtensionMatch::Equals,
+            exclude_dirs: to_strings(DEFAULT_EXCLUDE_DIRS),
             use_mft: true,
+            include_metafiles: false,
+            mft_timestamps: false,
+            resolve_sizes: false,
+            resolve_network_paths: false,
             threads: num_cpus::get(),
+            max_entries: None,
+            topological_order: false,
+            pause_on_battery: false,
+            progress_interval: ProgressInterval::default(),
+            progress_callback: None,
+            expected_entries: None,
+            current_user_only: false,
+        }
+    }
+}
+
+/// Default `exclude_dirs`: system, program and temporary folders
+///
+/// Matched as substrings of the path ignoring case, so `\Temp\` skips
+/// the contents of every folder named `Temp` or `temp`, and `Windows`
+/// alone would already cover `Windows.old`.
+const DEFAULT_EXCLUDE_DIRS: &[&str] = &[
+    "$Recycle.Bin",
+    "System Volume Information",
+    "Windows",
+    "Windows.old",
+    "$WinREAgent",
+    "Program Files",
+    "Program Files (x86)",
+    "ProgramData",
+    "AppData\\Local\\Temp",
+    "\\Temp\\",
+];
+
+/// Build outputs, dependency caches and tool state, skipped by
+/// [`Preset::Developer`]; generic names are bounded by separators so
+/// e.g. `targets.txt` is kept
+const DEVELOPER_EXCLUDE_DIRS: &[&str] = &[
+    "node_modules",
+    "\\.git\\",
+    "\\target\\",
+    "\\bin\\Debug\\",
+    "\\bin\\Release\\",
+    "\\obj\\",
+    "__pycache__",
+    "\\.venv\\",
+    "\\.vs\\",
+    "\\.idea\\",
+    "\\.gradle\\",
+];
+
+fn to_strings(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// Named starting points for an [`IndexConfig`]
+///
+/// Every preset starts from the default config; adjust the result as
+/// needed. Exclusions are path substrings (see `exclude_dirs`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Preset {
+    /// The defaults: skips the recycle bin, `System Volume Information`,
+    /// `Windows`, `Windows.old`, `$WinREAgent`, both `Program Files`,
+    /// `ProgramData`, `AppData\Local\Temp` and the contents of any
+    /// `Temp` folder, plus system files
+    #[default]
+    Standard,
+    /// Documents, pictures and the like: the default exclusions plus all
+    /// of `AppData`, and no hidden or system files
+    UserFilesOnly,
+    /// No exclusions at all, hidden and system files included
+    Everything,
+    /// The default exclusions plus build outputs and dependency caches:
+    /// `node_modules`, `.git`, `target`, `bin\Debug`, `bin\Release`, `obj`,
+    /// `__pycache__`, `.venv`, `.vs`, `.idea` and `.gradle`
+    Developer,
+}
+
+/// Preset trade-off between scan speed and detail
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanMode {
+    /// Names, sizes and timestamps only, from the MFT where possible;
+    /// suited to instant search
+    #[default]
+    Quick,
+    /// Also hash file contents (up to `max_hash_size`) for deduplication,
+    /// and read sizes and times of MFT entries (`resolve_sizes`).
+    /// Content and shell property extraction will join this mode.
+    Deep,
+}
+
+impl IndexConfig {
+    /// The default config with the exclusions of `preset`
+    pub fn preset(preset: Preset) -> Self {
+        let mut config = Self::default();
+        match preset {
+            Preset::Standard => {}
+            Preset::UserFilesOnly => {
+                config.exclude_dirs.push("AppData".to_string());
+                config.include_hidden = false;
+                config.include_system = false;
+            }
+            Preset::Everything => {
+                config.exclude_dirs.clear();
+                config.include_hidden = true;
+                config.include_system = true;
+            }
+            Preset::Developer => {
+                config
+                    .exclude_dirs
+                    .extend(to_strings(DEVELOPER_EXCLUDE_DIRS));
+            }
+        }
+        config
+    }
+
+    /// Set the toggles bundled by `mode`, leaving filters and drives as is
+    ///
+    /// Each mode sets all of its toggles, so the last call wins.
+    pub fn with_scan_mode(mut self, mode: ScanMode) -> Self {
+        match mode {
+            ScanMode::Quick => {
+                self.use_mft = true;
+                self.compute_hashes = false;
+                self.resolve_sizes = false;
+            }
+            ScanMode::Deep => {
+                self.use_mft = true;
+                self.compute_hashes = true;
+                self.resolve_sizes = true;
+            }
+        }
+        self
+    }
+}
+
+/// Options for indexing a single directory
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexDirectoryOptions {
+    /// Descend into subdirectories
+    pub recursive: bool,
+    /// Maximum depth below the root (root = 0); ignored when not recursive
+    pub max_depth: Option<usize>,
+}
+
+impl Default for IndexDirectoryOptions {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            max_depth: None,
+        }
+    }
+}
+
+/// Running totals shared by the parallel indexing workers
+#[derive(Default)]
+struct IndexCounters {
+    total_files: AtomicU64,
+    total_dirs: AtomicU64,
+    total_size: AtomicU64,
+    admitted: AtomicU64,
+    truncated: AtomicBool,
+    cycles: parking_lot::Mutex<Vec<String>>,
+    /// Set by the caller to stop the scan
+    cancel: Option<Arc<AtomicBool>>,
+    /// Paces `IndexConfig::progress_callback`; present when one is set
+    progress: Option<ProgressThrottle>,
+    /// Progress total: `IndexConfig::expected_entries` or an estimate
+    expected: Option<u64>,
+}
+
+impl IndexCounters {
+    /// Reserve a slot for one more entry under the `max_entries` cap
+    fn admit(&self, max_entries: Option<usize>) -> bool {
+        let Some(max) = max_entries else {
+            return true;
+        };
+
+        if self.admitted.fetch_add(1, Ordering::Relaxed) < max as u64 {
+            true
+        } else {
+            self.truncated.store(true, Ordering::Relaxed);
+            false
+        }
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Entries recorded so far
+    fn indexed(&self) -> u64 {
+        self.total_files.load(Ordering::Relaxed) + self.total_dirs.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, entry: &FileEntry) {
+        if entry.is_cycle {
+            warn!("Link points to its own ancestor: {}", entry.path);
+            self.cycles.lock().push(entry.path.clone());
+        }
+        if entry.is_dir {
+            self.total_dirs.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.total_files.fetch_add(1, Ordering::Relaxed);
+            self.total_size.fetch_add(entry.size, Ordering::Relaxed);
         }
     }
 }
 
+/// Hashed entries from a previous index, by path
+type PriorHashes<'a> = HashMap<&'a str, &'a FileEntry>;
+
 /// Fast file indexer
 pub struct FastIndexer {
     config: IndexConfig,
+    filter: EntryFilter,
     metadata_extractor: MetadataExtractor,
-    #[allow(dead_code)]
     content_hasher: ContentHasher,
+    /// Tried in order for each drive until one succeeds
+    backends: Vec<Box<dyn IndexBackend>>,
+    power: PowerGate,
+    /// Set by `current_user_only`
+    owner: Option<OwnerFilter>,
 }
 
 impl FastIndexer {
     /// Create a new indexer with the given configuration
+    ///
+    /// Drives are read from the MFT when `use_mft` is set, falling back to
+    /// walking the directory tree.
     pub fn new(config: IndexConfig) -> Self {
+        let mut backends: Vec<Box<dyn IndexBackend>> = Vec::new();
+        if config.use_mft {
+            backends.push(Box::new(MftBackend::new(MftScanOptions {
+                include_metafiles: config.include_metafiles,
+                resolve_sizes: config.resolve_sizes,
+            })));
+        }
+        backends.push(Box::new(WalkdirBackend::new(None)));
+        Self::with_backends(config, backends)
+    }
+
+    /// Create an indexer that scans drives with `backends`, trying each in
+    /// order until one succeeds
+    ///
+    /// `use_mft` and `include_metafiles` are ignored; the other settings
+    /// apply to entries from every backend. Directory indexing always walks
+    /// the tree.
+    pub fn with_backends(config: IndexConfig, backends: Vec<Box<dyn IndexBackend>>) -> Self {
         Self {
+            filter: EntryFilter::new(&config),
             config: config.clone(),
             metadata_extractor: MetadataExtractor::new(),
-            content_hasher: ContentHasher::new(config.max_hash_size),
+            content_hasher: ContentHasher::new(config.max_hash_size)
+                .with_hash_format(config.hash_format),
+            backends,
+            power: PowerGate::new(config.pause_on_battery),
+            owner: config.current_user_only.then(OwnerFilter::current_user),
         }
     }
 
     /// Index all configured drives
+    ///
+    /// Every entry is held until the scan ends; for large volumes prefer
+    /// [`index_all_streaming`](Self::index_all_streaming).
     pub fn index_all(&self) -> Result<(Vec<FileEntry>, IndexStats)> {
+        self.collect_all(None, None)
+    }
+
+    /// Index all configured drives, stopping early once `cancel` is set
+    ///
+    /// The flag is checked before each drive and each entry. A cancelled
+    /// scan returns the entries found so far, with `cancelled` set in the
+    /// stats. A drive read from the MFT still has all its records read
+    /// first, but stops building entries once cancelled.
+    pub fn index_all_cancellable(
+        &self,
+        cancel: Arc<AtomicBool>,
+    ) -> Result<(Vec<FileEntry>, IndexStats)> {
+        self.collect_all(None, Some(cancel))
+    }
+
+    /// Index all configured drives, reusing hashes from a previous index
+    ///
+    /// With `compute_hashes`, a file whose path, size and modified time
+    /// match a hashed entry in `prior` keeps that entry's `content_hash`
+    /// instead of being read again. Entries without a modified time are
+    /// always re-hashed, since they can't be shown to be unchanged; for MFT
+    /// scans that means enabling `mft_timestamps`.
+    pub fn index_all_incremental(
+        &self,
+        prior: &[FileEntry],
+    ) -> Result<(Vec<FileEntry>, IndexStats)> {
+        let prior: PriorHashes = prior
+            .iter()
+            .filter(|e| e.content_hash.is_some())
+            .map(|e| (e.path.as_str(), e))
+            .collect();
+        self.collect_all(Some(&prior), None)
+    }
+
+    fn collect_all(
+        &self,
+        prior: Option<&PriorHashes>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<(Vec<FileEntry>, IndexStats)> {
+        let entries: DashMap<String, FileEntry> = DashMap::new();
+
+        let stats = self.scan_all(
+            &|entry: FileEntry| {
+                entries.insert(entry.path.clone(), entry);
+            },
+            prior,
+            cancel,
+        )?;
+
+        let result: Vec<FileEntry> = entries.into_iter().map(|(_, e)| e).collect();
+        Ok((self.ordered(result), stats))
+    }
+
+    /// Sort parent-before-child if `topological_order` is set
+    ///
+    /// A directory's path is a strict prefix of its contents' paths, so it
+    /// sorts first. Separators compare below every other byte, so each
+    /// subtree is also kept together (`a\b` before `a b`).
+    fn ordered(&self, mut entries: Vec<FileEntry>) -> Vec<FileEntry> {
+        if self.config.topological_order {
+            entries.par_sort_unstable_by(|a, b| path_order(&a.path, &b.path));
+        }
+        entries
+    }
+
+    /// Index all configured drives, handing each entry to `sink` as it is found
+    ///
+    /// No result `Vec` is built, so memory stays flat regardless of volume
+    /// size. This is the zero-retain path for consumers that write entries
+    /// to their own store.
+    ///
+    /// Thread safety: drives are indexed in parallel and the walkdir
+    /// fallback runs on the rayon pool, so `sink` is called concurrently
+    /// from multiple threads and in no particular order. It must be `Sync`
+    /// and do its own synchronization if it mutates shared state. Feed a
+    /// [`StatsAccumulator`] from the sink for live totals during the scan.
+    pub fn index_all_with_sink<S>(&self, sink: S) -> Result<IndexStats>
+    where
+        S: Fn(&FileEntry) + Sync,
+    {
+        self.scan_all(&|entry| sink(&entry), None, None)
+    }
+
+    /// Index all configured drives, handing each entry to `callback` by
+    /// value as it is found
+    ///
+    /// Like [`index_all_with_sink`](Self::index_all_with_sink), but the
+    /// callback owns each entry, so it can move it into a search engine
+    /// batch or a writer without cloning. Nothing is retained by the
+    /// indexer. The callback is called concurrently from several threads,
+    /// in no particular order, and so must be `Send + Sync`; it can't be
+    /// `FnMut`, so buffer through a channel or a lock.
+    ///
+    /// Entries can't be taken back once handed over: should a backend fail
+    /// partway through a drive, the next one (e.g. walkdir after the MFT)
+    /// rescans it from the start and may deliver some paths a second time.
+    /// Key the store by path if that matters.
+    pub fn index_all_streaming<F>(&self, callback: F) -> Result<IndexStats>
+    where
+        F: Fn(FileEntry) + Send + Sync,
+    {
+        self.scan_all(&callback, None, None)
+    }
+
+    fn scan_all(
+        &self,
+        sink: &(dyn Fn(FileEntry) + Sync),
+        prior: Option<&PriorHashes>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<IndexStats> {
         let start = Instant::now();
-        let entries: Arc<DashMap<String, FileEntry>> = Arc::new(DashMap::new());
-        let total_files = AtomicU64::new(0);
-        let total_dirs = AtomicU64::new(0);
-        let total_size = AtomicU64::new(0);
+        let mut counters = self.counters(cancel);
+        if counters.progress.is_some() && counters.expected.is_none() {
+            counters.expected = self.estimate_entries();
+        }
 
         info!("Starting indexing of drives: {:?}", self.config.drives);
 
         // Index drives in parallel
         self.config.drives.par_iter().for_each(|&drive| {
+            if counters.is_cancelled() {
+                return;
+            }
             info!("Indexing drive {}:", drive);
 
-            match self.index_drive(drive, &entries, &total_files, &total_dirs, &total_size) {
+            match self.index_drive(drive, sink, &counters, prior) {
                 Ok(count) => info!("Drive {}: indexed {} files", drive, count),
                 Err(e) => warn!("Error indexing drive {}: {}", drive, e),
             }
         });
+        self.report_final_progress(&counters);
 
         let elapsed = start.elapsed();
         let stats = IndexStats {
-            total_files: total_files.load(Ordering::Relaxed),
-            total_dirs: total_dirs.load(Ordering::Relaxed),
-            total_size: total_size.load(Ordering::Relaxed),
+            total_files: counters.total_files.load(Ordering::Relaxed),
+            total_dirs: counters.total_dirs.load(Ordering::Relaxed),
+            total_size: counters.total_size.load(Ordering::Relaxed),
             index_time_ms: elapsed.as_millis() as u64,
             drives_indexed: self.config.drives.clone(),
+            truncated: counters.is_truncated(),
+            cancelled: counters.is_cancelled(),
+            cycles: counters.cycles.into_inner(),
         };
 
+        if stats.truncated {
+            warn!(
+                "Indexing stopped at max_entries limit ({:?})",
+                self.config.max_entries
+            );
+        }
+        if stats.cancelled {
+            info!("Indexing cancelled");
+        }
+
         info!(
             "Indexing complete: {} files, {} dirs, {} total in {}ms",
             stats.total_files,
@@ -125,150 +604,357 @@ impl FastIndexer {
             stats.index_time_ms
         );
 
-        let result: Vec<FileEntry> = entries.iter().map(|e| e.value().clone()).collect();
-        Ok((result, stats))
+        Ok(stats)
+    }
+
+    /// Sum of the backends' estimates for the configured drives, from the
+    /// first backend with one for each drive
+    fn estimate_entries(&self) -> Option<u64> {
+        self.config
+            .drives
+            .iter()
+            .filter_map(|&drive| {
+                let root = format!("{}:\\", drive);
+                self.backends
+                    .iter()
+                    .find_map(|backend| backend.estimate_entries(Path::new(&root)))
+            })
+            .reduce(|a, b| a + b)
     }
 
     /// Index a single drive
     fn index_drive(
         &self,
         drive: char,
-        entries: &Arc<DashMap<String, FileEntry>>,
-        total_files: &AtomicU64,
-        total_dirs: &AtomicU64,
-        total_size: &AtomicU64,
+        sink: &(dyn Fn(FileEntry) + Sync),
+        counters: &IndexCounters,
+        prior: Option<&PriorHashes>,
     ) -> Result<u64> {
         let root = format!("{}:\\", drive);
+        let root = Path::new(&root);
 
-        // Try MFT reader first (fastest), fall back to walkdir
-        if self.config.use_mft {
-            match MftReader::scan_volume(drive) {
-                Ok(mft_entries) => {
-                    let count = mft_entries.len() as u64;
-                    for entry in mft_entries {
-                        if self.should_include(&entry) {
-                            if entry.is_dir {
-                                total_dirs.fetch_add(1, Ordering::Relaxed);
-                            } else {
-                                total_files.fetch_add(1, Ordering::Relaxed);
-                                total_size.fetch_add(entry.size, Ordering::Relaxed);
-                            }
-                            entries.insert(entry.path.clone(), entry);
-                        }
-                    }
-                    return Ok(count);
-                }
+        for backend in &self.backends {
+            match self.scan_with(backend.as_ref(), root, sink, counters, prior) {
+                Ok(count) => return Ok(count),
                 Err(e) => {
                     debug!(
-                        "MFT reader failed for drive {}: {}, falling back to walkdir",
-                        drive, e
+                        "{} backend failed for drive {}: {}, trying the next",
+                        backend.name(),
+                        drive,
+                        e
                     );
                 }
             }
         }
 
-        // Fallback to walkdir (still parallel)
-        self.index_with_walkdir(&root, entries, total_files, total_dirs, total_size)
+        Err(NexusError::Index(format!(
+            "No backend could scan drive {}",
+            drive
+        )))
     }
 
     /// Index using walkdir (fallback method)
     fn index_with_walkdir(
         &self,
-        root: &str,
-        entries: &Arc<DashMap<String, FileEntry>>,
-        total_files: &AtomicU64,
-        total_dirs: &AtomicU64,
-        total_size: &AtomicU64,
+        root: impl AsRef<Path>,
+        max_depth: Option<usize>,
+        sink: &(dyn Fn(FileEntry) + Sync),
+        counters: &IndexCounters,
+        prior: Option<&PriorHashes>,
     ) -> Result<u64> {
-        use walkdir::WalkDir;
+        let backend = WalkdirBackend::new(max_depth);
+        self.scan_with(&backend, root.as_ref(), sink, counters, prior)
+    }
 
+    /// Scan `root` with one backend, passing the entries that survive the
+    /// filters and the `max_entries` cap to `sink`
+    fn scan_with(
+        &self,
+        backend: &dyn IndexBackend,
+        root: &Path,
+        sink: &(dyn Fn(FileEntry) + Sync),
+        counters: &IndexCounters,
+        prior: Option<&PriorHashes>,
+    ) -> Result<u64> {
         let count = AtomicU64::new(0);
 
-        WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .par_bridge()
-            .filter_map(|e| e.ok())
-            .for_each(|entry| {
-                if let Some(file_entry) = self.metadata_extractor.extract(entry.path()) {
-                    if self.should_include(&file_entry) {
-                        if file_entry.is_dir {
-                            total_dirs.fetch_add(1, Ordering::Relaxed);
-                        } else {
-                            total_files.fetch_add(1, Ordering::Relaxed);
-                            total_size.fetch_add(file_entry.size, Ordering::Relaxed);
-                        }
-                        count.fetch_add(1, Ordering::Relaxed);
-                        entries.insert(file_entry.path.clone(), file_entry);
-                    }
+        // Resolve the volume once per scan rather than per entry
+        let drive = root.to_str().and_then(drive_letter);
+        let volume_id = drive.and_then(volume_guid);
+        let fill_timestamps = self.config.mft_timestamps && !backend.has_timestamps();
+
+        backend.scan_each(root, &|mut entry: FileEntry| {
+            if counters.is_truncated() || counters.is_cancelled() {
+                return false;
+            }
+            self.power.wait_for_ac(|| counters.is_cancelled());
+            if counters.is_cancelled() {
+                return false;
+            }
+            if self.should_include(&entry) && counters.admit(self.config.max_entries) {
+                entry.volume_id = volume_id.clone();
+                if fill_timestamps {
+                    self.metadata_extractor.fill_timestamps(&mut entry);
+                }
+                if self.config.resolve_network_paths && volume::is_network_path(&entry.path) {
+                    entry.resolved_path = resolve_network_path(&entry.path);
                 }
-            });
+                self.hash_if_enabled(&mut entry, prior);
+                counters.record(&entry);
+                count.fetch_add(1, Ordering::Relaxed);
+                sink(entry);
+                self.report_progress(counters, drive);
+            }
+            true
+        })?;
 
         Ok(count.load(Ordering::Relaxed))
     }
 
-    /// Check if a file entry should be included based on config
-    fn should_include(&self, entry: &FileEntry) -> bool {
-        // Check hidden
-        if !self.config.include_hidden && entry.is_hidden {
-            return false;
+    /// Counters for one scan, pacing progress reports if a callback is set
+    fn counters(&self, cancel: Option<Arc<AtomicBool>>) -> IndexCounters {
+        IndexCounters {
+            cancel,
+            progress: self
+                .config
+                .progress_callback
+                .as_ref()
+                .map(|_| ProgressThrottle::new(self.config.progress_interval)),
+            expected: self.config.expected_entries,
+            ..Default::default()
         }
+    }
 
-        // Check system
-        if !self.config.include_system && entry.is_system {
-            return false;
+    /// Count one indexed entry from `drive`, calling the progress callback
+    /// when a report is due
+    fn report_progress(&self, counters: &IndexCounters, drive: Option<char>) {
+        let (Some(callback), Some(throttle)) = (&self.config.progress_callback, &counters.progress)
+        else {
+            return;
+        };
+        if let Some(current) = throttle.tick() {
+            let total = counters.expected.unwrap_or(0).max(current);
+            callback.call(current, total, drive);
         }
+    }
 
-        // Check excluded directories
-        for exclude in &self.config.exclude_dirs {
-            if entry.path.contains(exclude) {
-                return false;
-            }
+    /// Report the final count as both progress and total, for no drive in
+    /// particular
+    fn report_final_progress(&self, counters: &IndexCounters) {
+        if let Some(callback) = &self.config.progress_callback {
+            let indexed = counters.indexed();
+            callback.call(indexed, indexed, None);
         }
+    }
 
-        // Check extensions (if specified)
-        if !self.config.extensions.is_empty() && !entry.is_dir {
-            if let Some(ext) = &entry.extension {
-                if !self
-                    .config
-                    .extensions
-                    .iter()
-                    .any(|e| e.eq_ignore_ascii_case(ext))
-                {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
+    /// Check if a file entry should be included based on config
+    fn should_include(&self, entry: &FileEntry) -> bool {
+        self.filter.matches(entry)
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| owner.owns(Path::new(&entry.path)))
+    }
 
-        true
+    /// Fill in `content_hash` when `compute_hashes` is set, reusing the
+    /// prior hash of an unchanged file
+    fn hash_if_enabled(&self, entry: &mut FileEntry, prior: Option<&PriorHashes>) {
+        if !self.config.compute_hashes || entry.is_dir || entry.is_offline {
+            return;
+        }
+        let unchanged = prior
+            .and_then(|p| p.get(entry.path.as_str()))
+            .filter(|p| entry.modified.is_some() && p.modified == entry.modified)
+            .filter(|p| p.size == entry.size);
+        entry.content_hash = match unchanged {
+            Some(p) => p.content_hash.clone(),
+            None => self.content_hasher.sha256_hash(Path::new(&entry.path)),
+        };
     }
 
-    /// Index a single directory
+    /// Index a single directory recursively
     pub fn index_directory<P: AsRef<Path>>(&self, path: P) -> Result<Vec<FileEntry>> {
+        self.index_directory_with(path, IndexDirectoryOptions::default())
+    }
+
+    /// Index a single directory with explicit recursion control
+    ///
+    /// A non-recursive index lists only the directory's direct children,
+    /// which suits file browsers that index lazily as folders are expanded.
+    pub fn index_directory_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: IndexDirectoryOptions,
+    ) -> Result<Vec<FileEntry>> {
         let path = path.as_ref();
         if !path.exists() {
             return Err(NexusError::InvalidPath(path.display().to_string()));
         }
 
-        let entries: Arc<DashMap<String, FileEntry>> = Arc::new(DashMap::new());
-        let total_files = AtomicU64::new(0);
-        let total_dirs = AtomicU64::new(0);
-        let total_size = AtomicU64::new(0);
+        let entries: DashMap<String, FileEntry> = DashMap::new();
+        let counters = self.counters(None);
+
+        let max_depth = if options.recursive {
+            options.max_depth
+        } else {
+            Some(1)
+        };
 
         self.index_with_walkdir(
-            path.to_str().unwrap_or_default(),
-            &entries,
-            &total_files,
-            &total_dirs,
-            &total_size,
+            path,
+            max_depth,
+            &|entry: FileEntry| {
+                entries.insert(entry.path.clone(), entry);
+            },
+            &counters,
+            None,
         )?;
+        self.report_final_progress(&counters);
+
+        Ok(self.ordered(entries.into_iter().map(|(_, e)| e).collect()))
+    }
+
+    /// Save entries to `path` for [`load_index`](Self::load_index) to read
+    /// back, replacing the file atomically
+    ///
+    /// The header records the drives the entries cover and the volume each
+    /// held, so a later [`SavedIndexHeader::is_stale`] can tell whether the
+    /// cache still describes the disk.
+    pub fn save_index(entries: &[FileEntry], path: &Path) -> Result<()> {
+        persist::save(entries, path)?;
+        info!("Saved {} entries to {}", entries.len(), path.display());
+        Ok(())
+    }
+
+    /// Load entries saved by [`save_index`](Self::save_index)
+    ///
+    /// Fails with [`NexusError::Index`] if the file isn't a saved index,
+    /// was written with another format version, or is truncated.
+    pub fn load_index(path: &Path) -> Result<Vec<FileEntry>> {
+        let (header, entries) = persist::load(path)?;
+        debug!(
+            "Loaded {} entries saved at {} from {}",
+            entries.len(),
+            header.saved_at,
+            path.display()
+        );
+        Ok(entries)
+    }
+
+    /// Read only the header of a saved index, to check
+    /// [`is_stale`](SavedIndexHeader::is_stale) before loading the entries
+    pub fn read_index_header(path: &Path) -> Result<SavedIndexHeader> {
+        persist::load_header(path)
+    }
+
+    /// Bring an entry map keyed by path up to date with journal changes
+    ///
+    /// Meant for draining a [`UsnJournal`] receiver into an index built by
+    /// a full scan. Created and modified paths are read from disk again and
+    /// go through the same filters, hashing and lookups as a scan, so a
+    /// change that makes an entry excluded removes it. Deletes remove the
+    /// entry and, for a directory, everything below it; renames move it
+    /// and its contents to the new path, keeping their tags. A rename whose
+    /// old path is unknown counts as a create. Paths the monitor couldn't
+    /// resolve, or that no longer exist by the time the change is applied,
+    /// are skipped; security and unknown changes refresh an entry already
+    /// in the map.
+    pub fn apply_changes(&self, changes: &[UsnChange], entries: &mut DashMap<String, FileEntry>) {
+        for change in changes {
+            match &change.change_type {
+                ChangeType::Deleted => {
+                    entries.remove(&change.path);
+                    if change.is_directory {
+                        entries.retain(|path, _| !is_below(path, &change.path));
+                    }
+                }
+                ChangeType::Renamed { old_path, .. } if !old_path.is_empty() => {
+                    let tags = entries
+                        .remove(old_path)
+                        .map(|(_, e)| e.tags)
+                        .unwrap_or_default();
+                    self.refresh_entry(&change.path, tags, entries);
+                    if change.is_directory {
+                        self.move_contents(old_path, &change.path, entries);
+                    }
+                }
+                ChangeType::SecurityChange | ChangeType::Unknown => {
+                    if let Some(tags) = entries.get(&change.path).map(|e| e.tags.clone()) {
+                        self.refresh_entry(&change.path, tags, entries);
+                    }
+                }
+                ChangeType::Created | ChangeType::Modified | ChangeType::Renamed { .. } => {
+                    let tags = entries
+                        .get(&change.path)
+                        .map(|e| e.tags.clone())
+                        .unwrap_or_default();
+                    self.refresh_entry(&change.path, tags, entries);
+                }
+            }
+        }
+    }
+
+    /// Read `path` from disk into `entries`, or drop it if it's gone or
+    /// excluded
+    fn refresh_entry(&self, path: &str, tags: Vec<String>, entries: &DashMap<String, FileEntry>) {
+        let entry = self
+            .metadata_extractor
+            .extract(Path::new(path))
+            .filter(|e| self.should_include(e));
+        let Some(mut entry) = entry else {
+            entries.remove(path);
+            return;
+        };
 
-        Ok(entries.iter().map(|e| e.value().clone()).collect())
+        entry.volume_id = drive_letter(&entry.path).and_then(volume_guid);
+        if self.config.resolve_network_paths && volume::is_network_path(&entry.path) {
+            entry.resolved_path = resolve_network_path(&entry.path);
+        }
+        self.hash_if_enabled(&mut entry, None);
+        entry.tags = tags;
+        entries.insert(path.to_string(), entry);
+    }
+
+    /// Re-key everything below a renamed directory
+    fn move_contents(&self, old_dir: &str, new_dir: &str, entries: &DashMap<String, FileEntry>) {
+        let moved: Vec<String> = entries
+            .iter()
+            .filter(|e| is_below(e.key(), old_dir))
+            .map(|e| e.key().clone())
+            .collect();
+        for old_path in moved {
+            let Some((_, mut entry)) = entries.remove(&old_path) else {
+                continue;
+            };
+            entry.path = format!("{}{}", new_dir, &old_path[old_dir.len()..]);
+            if let Some(rest) = entry.parent.strip_prefix(old_dir) {
+                entry.parent = format!("{}{}", new_dir, rest);
+            }
+            if self.should_include(&entry) {
+                entries.insert(entry.path.clone(), entry);
+            }
+        }
     }
 }
 
+/// Whether `path` lies inside directory `dir`
+fn is_below(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with(['\\', '/']))
+}
+
+/// Compare paths byte-wise with `\` and `/` ordered before any other byte
+fn path_order(a: &str, b: &str) -> std::cmp::Ordering {
+    let key = |byte: &u8| match byte {
+        b'\\' | b'/' => 0,
+        &other => other,
+    };
+    a.as_bytes()
+        .iter()
+        .map(key)
+        .cmp(b.as_bytes().iter().map(key))
+}
+
 /// Format file size for display
 fn format_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -287,10 +973,620 @@ fn format_size(size: u64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_config_missing_fields_default() {
+        let config: IndexConfig =
+            serde_json::from_str(r#"{"drives": ["D"], "include_hidden": false}"#).unwrap();
+        assert_eq!(config.drives, vec!['D']);
+        assert!(!config.include_hidden);
+        assert_eq!(config.exclude_dirs, IndexConfig::default().exclude_dirs);
+        assert!(config.use_mft);
+    }
+
+    #[test]
+    fn test_presets() {
+        let path = |p: &str| FileEntry {
+            path: p.to_string(),
+            ..Default::default()
+        };
+        let keeps = |preset: Preset, p: &str| {
+            EntryFilter::new(&IndexConfig::preset(preset)).matches(&path(p))
+        };
+
+        assert_eq!(
+            IndexConfig::preset(Preset::Standard).exclude_dirs,
+            IndexConfig::default().exclude_dirs
+        );
+        assert!(!keeps(Preset::Standard, "C:\\Windows.old\\x.dll"));
+        assert!(!keeps(
+            Preset::Standard,
+            "C:\\Users\\me\\AppData\\Local\\Temp\\x.tmp"
+        ));
+        assert!(!keeps(Preset::Standard, "D:\\Temp\\x.tmp"));
+        assert!(!keeps(Preset::Standard, "D:\\temp\\x.tmp"));
+        assert!(!keeps(Preset::Standard, "C:\\WINDOWS\\notepad.exe"));
+        assert!(keeps(Preset::Standard, "C:\\Users\\me\\Templates\\x.dotx"));
+
+        assert!(!keeps(
+            Preset::UserFilesOnly,
+            "C:\\Users\\me\\AppData\\Roaming\\x.json"
+        ));
+        assert!(keeps(Preset::Everything, "C:\\Windows\\notepad.exe"));
+
+        assert!(!keeps(
+            Preset::Developer,
+            "C:\\src\\app\\target\\debug\\app.exe"
+        ));
+        assert!(!keeps(
+            Preset::Developer,
+            "C:\\src\\web\\node_modules\\x.js"
+        ));
+        assert!(!keeps(Preset::Developer, "C:\\src\\App\\Target\\x.exe"));
+        assert!(!keeps(Preset::Developer, "C:\\src\\App\\bin\\debug\\x.dll"));
+        assert!(keeps(Preset::Developer, "C:\\src\\app\\targets.txt"));
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(0), "0.00 B");
         assert_eq!(format_size(1024), "1.00 KB");
         assert_eq!(format_size(1024 * 1024), "1.00 MB");
     }
+
+    /// Emits fixed entries, or fails without emitting any
+    #[derive(Default)]
+    struct MockBackend {
+        entries: Option<Vec<FileEntry>>,
+        estimate: Option<u64>,
+    }
+
+    impl IndexBackend for MockBackend {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn estimate_entries(&self, _root: &Path) -> Option<u64> {
+            self.estimate
+        }
+
+        fn scan_each(&self, root: &Path, emit: &EmitFn) -> Result<()> {
+            let entries = self
+                .entries
+                .as_ref()
+                .ok_or_else(|| NexusError::Index(format!("cannot scan {}", root.display())))?;
+            for entry in entries {
+                if !emit(entry.clone()) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Walks a fixed directory whatever drive it is asked for
+    struct DirBackend(std::path::PathBuf);
+
+    impl IndexBackend for DirBackend {
+        fn name(&self) -> &'static str {
+            "dir"
+        }
+
+        fn scan_each(&self, _root: &Path, emit: &EmitFn) -> Result<()> {
+            WalkdirBackend::new(None).scan_each(&self.0, emit)
+        }
+    }
+
+    #[test]
+    fn test_index_all_streaming() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        for name in ["one.txt", "a/two.txt", "a/b/three.txt"] {
+            std::fs::write(dir.path().join(name), name).unwrap();
+        }
+
+        let indexer = FastIndexer::with_backends(
+            IndexConfig {
+                drives: vec!['C'],
+                exclude_dirs: vec![],
+                ..Default::default()
+            },
+            vec![Box::new(DirBackend(dir.path().to_path_buf()))],
+        );
+        let seen = parking_lot::Mutex::new(Vec::new());
+        let stats = indexer
+            .index_all_streaming(|entry| seen.lock().push(entry.path))
+            .unwrap();
+
+        let mut seen = seen.into_inner();
+        seen.sort();
+        let mut expected: Vec<String> = walkdir::WalkDir::new(dir.path())
+            .into_iter()
+            .map(|e| e.unwrap().path().to_string_lossy().to_string())
+            .collect();
+        expected.sort();
+        // Every entry once: the root, two directories and three files
+        assert_eq!(seen, expected);
+        assert_eq!(seen.len(), 6);
+        assert_eq!(stats.total_files + stats.total_dirs, 6);
+    }
+
+    #[test]
+    fn test_backend_fallback() {
+        let file = |path: &str| FileEntry {
+            path: path.to_string(),
+            name: path.rsplit('\\').next().unwrap().to_string(),
+            size: 10,
+            ..Default::default()
+        };
+        let backends: Vec<Box<dyn IndexBackend>> = vec![
+            Box::new(MockBackend::default()),
+            Box::new(MockBackend {
+                entries: Some(vec![
+                    file("C:\\Users\\a.txt"),
+                    file("C:\\Windows\\b.dll"),
+                    file("C:\\Users\\c.txt"),
+                ]),
+                ..Default::default()
+            }),
+        ];
+        let config = IndexConfig {
+            drives: vec!['C'],
+            ..Default::default()
+        };
+
+        let (entries, stats) = FastIndexer::with_backends(config.clone(), backends)
+            .index_all()
+            .unwrap();
+        // Filters apply to backend entries
+        assert_eq!(entries.len(), 2);
+        assert_eq!(stats.total_size, 20);
+
+        // The cap stops the scan
+        let capped = FastIndexer::with_backends(
+            IndexConfig {
+                max_entries: Some(1),
+                ..config
+            },
+            vec![Box::new(MockBackend {
+                entries: Some(vec![file("C:\\a"), file("C:\\b")]),
+                ..Default::default()
+            })],
+        );
+        let (entries, stats) = capped.index_all().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(stats.truncated);
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b/c")).unwrap();
+        std::fs::create_dir_all(dir.path().join("a b")).unwrap();
+        std::fs::write(dir.path().join("a/b/c/d.txt"), b"d").unwrap();
+        std::fs::write(dir.path().join("a/e.txt"), b"e").unwrap();
+
+        let indexer = FastIndexer::new(IndexConfig {
+            exclude_dirs: vec![],
+            topological_order: true,
+            ..Default::default()
+        });
+        let entries = indexer.index_directory(dir.path()).unwrap();
+
+        let position = |path: &Path| {
+            let path = path.to_str().unwrap();
+            entries.iter().position(|e| e.path == path).unwrap()
+        };
+        for entry in &entries {
+            let parent = Path::new(&entry.path).parent().unwrap();
+            if parent.starts_with(dir.path()) && parent != dir.path() {
+                assert!(position(parent) < position(Path::new(&entry.path)));
+            }
+        }
+
+        // Everything under `a` sits between `a` and its sibling `a b`
+        let a = position(&dir.path().join("a"));
+        let a_b = position(&dir.path().join("a b"));
+        let d = position(&dir.path().join("a/b/c/d.txt"));
+        let e = position(&dir.path().join("a/e.txt"));
+        assert!(a < d && d < a_b);
+        assert!(a < e && e < a_b);
+    }
+
+    #[test]
+    fn test_index_all_with_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"a").unwrap();
+        std::fs::write(dir.path().join("b.txt"), b"bb").unwrap();
+
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            ..Default::default()
+        };
+        let indexer = FastIndexer::new(config);
+        let seen = parking_lot::Mutex::new(Vec::new());
+        let counters = IndexCounters::default();
+
+        indexer
+            .index_with_walkdir(
+                dir.path().to_str().unwrap(),
+                None,
+                &|entry: FileEntry| seen.lock().push(entry.name),
+                &counters,
+                None,
+            )
+            .unwrap();
+
+        let mut seen = seen.into_inner();
+        seen.sort();
+        assert!(seen.contains(&"a.txt".to_string()));
+        assert!(seen.contains(&"b.txt".to_string()));
+        assert_eq!(counters.total_files.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.total_size.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_max_entries_truncates() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), b"x").unwrap();
+        }
+
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            max_entries: Some(4),
+            ..Default::default()
+        };
+        let indexer = FastIndexer::new(config);
+        let counters = IndexCounters::default();
+        let seen = AtomicU64::new(0);
+
+        indexer
+            .index_with_walkdir(
+                dir.path().to_str().unwrap(),
+                None,
+                &|_: FileEntry| {
+                    seen.fetch_add(1, Ordering::Relaxed);
+                },
+                &counters,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(seen.load(Ordering::Relaxed), 4);
+        assert!(counters.is_truncated());
+        assert_eq!(indexer.index_directory(dir.path()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), b"x").unwrap();
+        }
+
+        let reports = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            progress_interval: ProgressInterval {
+                entries: 2,
+                millis: 0,
+            },
+            progress_callback: Some(ScanProgressCallback::new(move |current, total, _drive| {
+                sink.lock().push((current, total))
+            })),
+            expected_entries: Some(4),
+            ..Default::default()
+        };
+        let entries = FastIndexer::new(config)
+            .index_directory(dir.path())
+            .unwrap();
+
+        let reports = reports.lock();
+        let count = entries.len() as u64;
+        assert_eq!(reports[0], (2, 4));
+        assert!(reports.iter().all(|&(current, total)| current <= total));
+        assert_eq!(reports.last(), Some(&(count, count)));
+    }
+
+    #[test]
+    fn test_progress_total_estimated() {
+        let reports = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let config = IndexConfig {
+            drives: vec!['C', 'D'],
+            progress_interval: ProgressInterval {
+                entries: 1,
+                millis: 0,
+            },
+            progress_callback: Some(ScanProgressCallback::new(move |current, total, _drive| {
+                sink.lock().push((current, total))
+            })),
+            ..Default::default()
+        };
+        let backend = MockBackend {
+            entries: Some(vec![FileEntry {
+                path: "C:\\a.txt".to_string(),
+                name: "a.txt".to_string(),
+                ..Default::default()
+            }]),
+            estimate: Some(50),
+        };
+
+        FastIndexer::with_backends(config, vec![Box::new(backend)])
+            .index_all()
+            .unwrap();
+
+        // Both drives' estimates, until the final report
+        let reports = reports.lock();
+        assert_eq!(reports[0], (1, 100));
+        assert_eq!(reports.last(), Some(&(2, 2)));
+    }
+
+    #[test]
+    fn test_cancel_stops_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), b"x").unwrap();
+        }
+
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            ..Default::default()
+        };
+        let indexer = FastIndexer::new(config);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let counters = IndexCounters {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        let seen = AtomicU64::new(0);
+
+        indexer
+            .index_with_walkdir(
+                dir.path().to_str().unwrap(),
+                None,
+                &|_: FileEntry| {
+                    if seen.fetch_add(1, Ordering::Relaxed) == 2 {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                },
+                &counters,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(seen.load(Ordering::Relaxed), 3);
+        assert!(counters.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancelled_before_start() {
+        let indexer = FastIndexer::new(IndexConfig::default());
+        let (entries, stats) = indexer
+            .index_all_cancellable(Arc::new(AtomicBool::new(true)))
+            .unwrap();
+
+        assert!(entries.is_empty());
+        assert!(stats.cancelled);
+    }
+
+    #[test]
+    fn test_index_directory_recursion() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub").join("deeper")).unwrap();
+        std::fs::write(dir.path().join("top.txt"), b"top").unwrap();
+        std::fs::write(dir.path().join("sub").join("mid.txt"), b"mid").unwrap();
+        std::fs::write(
+            dir.path().join("sub").join("deeper").join("low.txt"),
+            b"low",
+        )
+        .unwrap();
+
+        let config = IndexConfig {
+            exclude_dirs: vec![],
+            ..Default::default()
+        };
+        let indexer = FastIndexer::new(config);
+        let names = |entries: Vec<FileEntry>| {
+            let mut names: Vec<String> = entries.into_iter().map(|e| e.name).collect();
+            names.sort();
+            names
+        };
+
+        let all = indexer.index_directory(dir.path()).unwrap();
+        assert!(names(all).contains(&"low.txt".to_string()));
+
+        let shallow = indexer
+            .index_directory_with(
+                dir.path(),
+                IndexDirectoryOptions {
+                    recursive: false,
+                    max_depth: None,
+                },
+            )
+            .unwrap();
+        let shallow = names(shallow);
+        assert!(shallow.contains(&"top.txt".to_string()));
+        assert!(shallow.contains(&"sub".to_string()));
+        assert!(!shallow.contains(&"mid.txt".to_string()));
+
+        let depth_two = indexer
+            .index_directory_with(
+                dir.path(),
+                IndexDirectoryOptions {
+                    recursive: true,
+                    max_depth: Some(2),
+                },
+            )
+            .unwrap();
+        let depth_two = names(depth_two);
+        assert!(depth_two.contains(&"mid.txt".to_string()));
+        assert!(!depth_two.contains(&"low.txt".to_string()));
+    }
+
+    #[test]
+    fn test_scan_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"a").unwrap();
+
+        let hashes = |mode| {
+            let config = IndexConfig {
+                exclude_dirs: vec![],
+                use_mft: false,
+                ..Default::default()
+            }
+            .with_scan_mode(mode);
+            assert!(config.use_mft);
+            FastIndexer::new(config)
+                .index_directory(dir.path())
+                .unwrap()
+                .into_iter()
+                .filter_map(|e| e.content_hash)
+                .count()
+        };
+
+        assert_eq!(hashes(ScanMode::Quick), 0);
+        assert_eq!(hashes(ScanMode::Deep), 1);
+
+        // The last mode wins
+        let config = IndexConfig::default()
+            .with_scan_mode(ScanMode::Deep)
+            .with_scan_mode(ScanMode::Quick);
+        assert!(!config.compute_hashes);
+        assert!(!config.resolve_sizes);
+    }
+
+    #[test]
+    fn test_incremental_hash_reuse() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("same.txt"), b"a").unwrap();
+        std::fs::write(dir.path().join("grown.txt"), b"bb").unwrap();
+
+        let indexer = FastIndexer::new(
+            IndexConfig {
+                exclude_dirs: vec![],
+                ..Default::default()
+            }
+            .with_scan_mode(ScanMode::Deep),
+        );
+        let mut prior = indexer.index_directory(dir.path()).unwrap();
+        for entry in &mut prior {
+            if !entry.is_dir {
+                entry.content_hash = Some("prior".to_string());
+            }
+            if entry.name == "grown.txt" {
+                entry.size = 1;
+            }
+        }
+        let prior: PriorHashes = prior
+            .iter()
+            .filter(|e| e.content_hash.is_some())
+            .map(|e| (e.path.as_str(), e))
+            .collect();
+
+        let hashes = DashMap::new();
+        indexer
+            .index_with_walkdir(
+                dir.path().to_str().unwrap(),
+                None,
+                &|entry: FileEntry| {
+                    hashes.insert(entry.name, entry.content_hash);
+                },
+                &IndexCounters::default(),
+                Some(&prior),
+            )
+            .unwrap();
+
+        // Unchanged size and mtime: reused without reading the file
+        assert_eq!(hashes.get("same.txt").unwrap().as_deref(), Some("prior"));
+        // Size differs: re-hashed
+        let grown = hashes.get("grown.txt").unwrap().clone();
+        assert!(grown.is_some_and(|h| h != "prior"));
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs").join("a.txt"), b"a").unwrap();
+        std::fs::write(root.join("edit.txt"), b"short").unwrap();
+        std::fs::write(root.join("gone.txt"), b"gone").unwrap();
+
+        let indexer = FastIndexer::new(IndexConfig {
+            exclude_dirs: vec![],
+            ..Default::default()
+        });
+        let mut entries: DashMap<String, FileEntry> = indexer
+            .index_directory(root)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.path.clone(), e))
+            .collect();
+        // The root, `docs` and three files
+        assert_eq!(entries.len(), 5);
+
+        let path = |rel: &str| root.join(rel).to_string_lossy().to_string();
+        let change = |rel: &str, change_type: ChangeType, is_directory: bool| UsnChange {
+            path: path(rel),
+            change_type,
+            is_directory,
+            timestamp: chrono::Utc::now(),
+        };
+        entries.get_mut(&path("docs/a.txt")).unwrap().tags = vec!["keep".to_string()];
+
+        std::fs::write(root.join("new.txt"), b"new").unwrap();
+        std::fs::write(root.join("edit.txt"), b"much longer now").unwrap();
+        std::fs::remove_file(root.join("gone.txt")).unwrap();
+        std::fs::rename(root.join("docs"), root.join("papers")).unwrap();
+        indexer.apply_changes(
+            &[
+                change("new.txt", ChangeType::Created, false),
+                change("edit.txt", ChangeType::Modified, false),
+                change("gone.txt", ChangeType::Deleted, false),
+                change(
+                    "papers",
+                    ChangeType::Renamed {
+                        old_name: "docs".to_string(),
+                        old_path: path("docs"),
+                    },
+                    true,
+                ),
+                // Already gone again: skipped
+                change("vanished.txt", ChangeType::Created, false),
+            ],
+            &mut entries,
+        );
+
+        assert!(entries.contains_key(&path("new.txt")));
+        assert_eq!(entries.get(&path("edit.txt")).unwrap().size, 15);
+        assert!(!entries.contains_key(&path("gone.txt")));
+        assert!(!entries.contains_key(&path("vanished.txt")));
+        assert!(!entries.contains_key(&path("docs")));
+        assert!(!entries.contains_key(&path("docs/a.txt")));
+        let moved = entries.get(&path("papers/a.txt")).unwrap().clone();
+        assert_eq!(moved.parent, path("papers"));
+        assert_eq!(moved.tags, ["keep"]);
+        assert!(entries.get(&path("papers")).unwrap().is_dir);
+
+        // Security and unknown changes refresh indexed entries only
+        std::fs::write(root.join("papers").join("a.txt"), b"abc").unwrap();
+        std::fs::write(root.join("stray.txt"), b"stray").unwrap();
+        indexer.apply_changes(
+            &[
+                change("papers/a.txt", ChangeType::SecurityChange, false),
+                change("stray.txt", ChangeType::Unknown, false),
+            ],
+            &mut entries,
+        );
+        let refreshed = entries.get(&path("papers/a.txt")).unwrap().clone();
+        assert_eq!(refreshed.size, 3);
+        assert_eq!(refreshed.tags, ["keep"]);
+        assert!(!entries.contains_key(&path("stray.txt")));
+
+        // Deleting a directory takes its contents along
+        indexer.apply_changes(&[change("papers", ChangeType::Deleted, true)], &mut entries);
+        assert_eq!(entries.len(), 3);
+    }
 }
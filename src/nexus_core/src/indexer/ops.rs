@@ -0,0 +1,131 @@
+//! File operations on indexed entries
+//!
+//! Destructive operations go through the shell so they can be undone:
+//! deleted files land in the Recycle Bin rather than being removed outright.
+
+use crate::{NexusError, Result};
+use std::path::{Path, PathBuf};
+
+/// Move files and directories to the Recycle Bin
+///
+/// Returns one result per input path, in order. The batch is handed to the
+/// shell in a single operation; paths it left in place are retried one at a
+/// time so each error is attributed to the right file. Paths that don't
+/// exist fail without touching the others.
+///
+/// Where a file can't be recycled (e.g. on some network shares) the shell
+/// asks before deleting it permanently. If the user declines, nothing left
+/// in place is retried, so the question isn't asked again; those paths fail
+/// as cancelled. On other platforms every path fails and nothing is
+/// deleted.
+pub fn recycle(paths: &[&Path]) -> Vec<Result<()>> {
+    let checked: Vec<Result<PathBuf>> = paths.iter().map(|p| prepare(p)).collect();
+    let batch: Vec<&Path> = checked.iter().filter_map(|p| p.as_deref().ok()).collect();
+
+    // Other errors are recovered per path below
+    let aborted = !batch.is_empty() && matches!(send_to_recycle_bin(&batch), Ok(false));
+
+    checked
+        .into_iter()
+        .map(|path| {
+            let path = path?;
+            // Existed before the batch, so gone now means recycled, even
+            // when the batch as a whole reported failure
+            if path.symlink_metadata().is_err() {
+                return Ok(());
+            }
+            if aborted || !send_to_recycle_bin(&[&path])? {
+                return Err(cancelled());
+            }
+            Ok(())
+        })
+        .collect()
+}
+
+fn cancelled() -> NexusError {
+    NexusError::Windows("Recycle operation was cancelled".to_string())
+}
+
+/// The shell needs absolute paths for undo to work
+fn prepare(path: &Path) -> Result<PathBuf> {
+    if path.symlink_metadata().is_err() {
+        return Err(NexusError::InvalidPath(path.display().to_string()));
+    }
+    Ok(std::path::absolute(path)?)
+}
+
+/// Delete `paths` through the shell; `Ok(false)` if the user cancelled
+/// any part of it, such as by declining a permanent deletion
+#[cfg(windows)]
+fn send_to_recycle_bin(paths: &[&Path]) -> Result<bool> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::UI::Shell::{
+        SHFileOperationW, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FOF_NOERRORUI, FOF_SILENT,
+        FOF_WANTNUKEWARNING, FO_DELETE, SHFILEOPSTRUCTW,
+    };
+
+    // Double-NUL-terminated list of NUL-terminated paths
+    let mut from: Vec<u16> = Vec::new();
+    for path in paths {
+        from.extend(path.as_os_str().encode_wide());
+        from.push(0);
+    }
+    from.push(0);
+
+    let flags = FOF_ALLOWUNDO.0
+        | FOF_NOCONFIRMATION.0
+        | FOF_NOERRORUI.0
+        | FOF_SILENT.0
+        | FOF_WANTNUKEWARNING.0;
+    let mut operation = SHFILEOPSTRUCTW {
+        wFunc: FO_DELETE,
+        pFrom: PCWSTR(from.as_ptr()),
+        fFlags: flags as u16,
+        ..Default::default()
+    };
+
+    let code = unsafe { SHFileOperationW(&mut operation) };
+    if code != 0 {
+        return Err(NexusError::Windows(format!(
+            "SHFileOperationW failed: 0x{:X}",
+            code
+        )));
+    }
+    Ok(!operation.fAnyOperationsAborted.as_bool())
+}
+
+#[cfg(not(windows))]
+fn send_to_recycle_bin(_paths: &[&Path]) -> Result<bool> {
+    Err(NexusError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the Recycle Bin is only available on Windows",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recycle_results_per_path() {
+        let dir = tempdir().unwrap();
+        let present = dir.path().join("present.txt");
+        std::fs::write(&present, b"x").unwrap();
+        let missing = dir.path().join("missing.txt");
+
+        let results = recycle(&[&present, &missing]);
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[1], Err(NexusError::InvalidPath(_))));
+
+        if cfg!(windows) {
+            assert!(results[0].is_ok());
+            assert!(!present.exists());
+        } else {
+            // Never falls back to permanent deletion
+            assert!(results[0].is_err());
+            assert!(present.exists());
+        }
+    }
+}
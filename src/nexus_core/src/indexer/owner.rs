@@ -0,0 +1,164 @@
+//! File ownership checks
+//!
+//! Backs `IndexConfig::current_user_only`: a file is kept only if its owner
+//! is the user the process runs as. On Windows that compares the owner SID
+//! of the file's security descriptor with the process token's user SID; on
+//! Unix it compares the file's uid with the effective uid.
+
+use std::path::Path;
+use tracing::warn;
+
+/// Matches files owned by the user running the process
+pub(super) struct OwnerFilter {
+    /// `None` when the current user couldn't be determined; then nothing
+    /// matches, rather than everything
+    user: Option<Owner>,
+}
+
+#[cfg(windows)]
+type Owner = Vec<u8>;
+#[cfg(unix)]
+type Owner = u32;
+#[cfg(not(any(windows, unix)))]
+type Owner = ();
+
+impl OwnerFilter {
+    pub(super) fn current_user() -> Self {
+        let user = current_user();
+        if user.is_none() {
+            warn!("Can't determine the current user; current_user_only will match no files");
+        }
+        Self { user }
+    }
+
+    /// Whether `path` is owned by the current user; false if its owner
+    /// can't be read
+    pub(super) fn owns(&self, path: &Path) -> bool {
+        match &self.user {
+            Some(user) => file_owner(path).is_some_and(|owner| &owner == user),
+            None => false,
+        }
+    }
+}
+
+/// SID of the process token's user
+#[cfg(windows)]
+fn current_user() -> Option<Owner> {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.ok()?;
+
+    // First call only reports the size needed
+    let mut length = 0u32;
+    let _ = unsafe { GetTokenInformation(token, TokenUser, None, 0, &mut length) };
+    // u64 elements keep the buffer aligned for reading it as TOKEN_USER
+    let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut _),
+            length,
+            &mut length,
+        )
+    };
+    let _ = unsafe { CloseHandle(token) };
+    result.ok()?;
+
+    let user = unsafe { &*(buffer.as_ptr() as *const TOKEN_USER) };
+    sid_bytes(user.User.Sid)
+}
+
+/// Owner SID from the file's security descriptor
+#[cfg(windows)]
+fn file_owner(path: &Path) -> Option<Owner> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+    use windows::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows::Win32::Security::{OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID};
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut owner = PSID::default();
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    let status = unsafe {
+        GetNamedSecurityInfoW(
+            PCWSTR(wide.as_ptr()),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            Some(&mut owner),
+            None,
+            None,
+            None,
+            &mut descriptor,
+        )
+    };
+    if status.is_err() {
+        return None;
+    }
+
+    // `owner` points into the descriptor, so copy it before freeing
+    let sid = sid_bytes(owner);
+    let _ = unsafe { LocalFree(HLOCAL(descriptor.0)) };
+    sid
+}
+
+/// Copy a SID out of memory owned by someone else
+#[cfg(windows)]
+fn sid_bytes(sid: windows::Win32::Security::PSID) -> Option<Owner> {
+    use windows::Win32::Security::GetLengthSid;
+
+    if sid.is_invalid() {
+        return None;
+    }
+    let length = unsafe { GetLengthSid(sid) } as usize;
+    (length > 0).then(|| unsafe { std::slice::from_raw_parts(sid.0 as *const u8, length) }.to_vec())
+}
+
+#[cfg(unix)]
+fn current_user() -> Option<Owner> {
+    Some(unsafe { libc::geteuid() })
+}
+
+#[cfg(unix)]
+fn file_owner(path: &Path) -> Option<Owner> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::symlink_metadata(path).ok().map(|m| m.uid())
+}
+
+#[cfg(not(any(windows, unix)))]
+fn current_user() -> Option<Owner> {
+    None
+}
+
+#[cfg(not(any(windows, unix)))]
+fn file_owner(_path: &Path) -> Option<Owner> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_owner_filter() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("mine.txt");
+        std::fs::write(&file, b"mine").unwrap();
+
+        let filter = OwnerFilter::current_user();
+        assert!(filter.owns(&file));
+        assert!(!filter.owns(&dir.path().join("missing.txt")));
+
+        let nobody = OwnerFilter { user: None };
+        assert!(!nobody.owns(&file));
+    }
+}
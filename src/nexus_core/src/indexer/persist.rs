@@ -0,0 +1,207 @@
+//! Saved indexes
+//!
+//! A full scan of a large volume takes seconds even from the MFT; a saved
+//! index lets a restart begin from the last known entries instead. The file
+//! holds a magic number and format version, a [`SavedIndexHeader`], then the
+//! entries, each part CBOR-encoded. The version is checked before anything
+//! else is decoded, so an index written by an incompatible build is
+//! rejected rather than misread.
+
+use super::volume::{volume_guid, volume_serial};
+use crate::{FileEntry, NexusError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"NEXUSIDX";
+
+/// Bump whenever [`FileEntry`] or the header changes incompatibly
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// What a saved index was built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedIndexHeader {
+    /// Drives the entries cover, in the order they were first seen
+    pub drives: Vec<SavedDrive>,
+    /// Number of entries that follow
+    pub entry_count: u64,
+    pub saved_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A drive as it was when the index was saved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedDrive {
+    pub letter: char,
+    /// Volume GUID path; `None` when unresolved
+    pub volume_id: Option<String>,
+    /// File system serial number; `None` when unresolved
+    pub serial: Option<u32>,
+}
+
+impl SavedDrive {
+    fn current(letter: char) -> Self {
+        Self {
+            letter,
+            volume_id: volume_guid(letter),
+            serial: volume_serial(letter),
+        }
+    }
+}
+
+impl SavedIndexHeader {
+    /// Whether any drive now holds a different volume than when the index
+    /// was saved: reformatted, swapped for another disk, or unmounted
+    ///
+    /// Drives whose volume couldn't be identified at save time are assumed
+    /// unchanged. An index that isn't stale can still miss changes made
+    /// since it was saved.
+    pub fn is_stale(&self) -> bool {
+        self.drives.iter().any(|saved| {
+            let current = SavedDrive::current(saved.letter);
+            (saved.serial.is_some() && saved.serial != current.serial)
+                || (saved.volume_id.is_some() && saved.volume_id != current.volume_id)
+        })
+    }
+}
+
+pub(super) fn save(entries: &[FileEntry], path: &Path) -> Result<()> {
+    let mut drives: Vec<SavedDrive> = Vec::new();
+    for entry in entries {
+        if !drives.iter().any(|d| d.letter == entry.drive) {
+            drives.push(SavedDrive::current(entry.drive));
+        }
+    }
+    let header = SavedIndexHeader {
+        drives,
+        entry_count: entries.len() as u64,
+        saved_at: chrono::Utc::now(),
+    };
+
+    let encode_error = |e| NexusError::Index(format!("Failed to save index: {}", e));
+    let tmp = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&INDEX_FORMAT_VERSION.to_le_bytes())?;
+    ciborium::into_writer(&header, &mut writer).map_err(encode_error)?;
+    ciborium::into_writer(entries, &mut writer).map_err(encode_error)?;
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(super) fn load(path: &Path) -> Result<(SavedIndexHeader, Vec<FileEntry>)> {
+    let mut reader = open(path)?;
+    let header = read_header(&mut reader, path)?;
+    let entries: Vec<FileEntry> = ciborium::from_reader(&mut reader)
+        .map_err(|e| NexusError::Index(format!("Corrupt saved index {}: {}", path.display(), e)))?;
+    if entries.len() as u64 != header.entry_count {
+        return Err(NexusError::Index(format!(
+            "Saved index {} has {} entries, header says {}",
+            path.display(),
+            entries.len(),
+            header.entry_count
+        )));
+    }
+    Ok((header, entries))
+}
+
+pub(super) fn load_header(path: &Path) -> Result<SavedIndexHeader> {
+    read_header(&mut open(path)?, path)
+}
+
+fn open(path: &Path) -> Result<BufReader<File>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut preamble = [0u8; 12];
+    reader
+        .read_exact(&mut preamble)
+        .map_err(|_| NexusError::Index(format!("{} is not a saved index", path.display())))?;
+    if &preamble[..8] != MAGIC {
+        return Err(NexusError::Index(format!(
+            "{} is not a saved index",
+            path.display()
+        )));
+    }
+    let version = u32::from_le_bytes([preamble[8], preamble[9], preamble[10], preamble[11]]);
+    if version != INDEX_FORMAT_VERSION {
+        return Err(NexusError::Index(format!(
+            "Saved index {} has format version {}, expected {}",
+            path.display(),
+            version,
+            INDEX_FORMAT_VERSION
+        )));
+    }
+    Ok(reader)
+}
+
+fn read_header(reader: &mut BufReader<File>, path: &Path) -> Result<SavedIndexHeader> {
+    ciborium::from_reader(reader)
+        .map_err(|e| NexusError::Index(format!("Corrupt saved index {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(path: &str, drive: char) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            name: path.rsplit('\\').next().unwrap().to_string(),
+            drive,
+            size: 42,
+            modified: Some(chrono::Utc::now()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_index() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("index.bin");
+        let entries = vec![
+            entry(r"C:\a.txt", 'C'),
+            entry(r"D:\b.txt", 'D'),
+            entry(r"C:\c.txt", 'C'),
+        ];
+
+        save(&entries, &file).unwrap();
+        let (header, loaded) = load(&file).unwrap();
+        assert_eq!(header.entry_count, 3);
+        let letters: Vec<char> = header.drives.iter().map(|d| d.letter).collect();
+        assert_eq!(letters, ['C', 'D']);
+        assert_eq!(load_header(&file).unwrap(), header);
+
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[1].path, entries[1].path);
+        assert_eq!(loaded[1].modified, entries[1].modified);
+        assert!(!dir.path().join("index.tmp").exists());
+
+        // A different format version is rejected before decoding
+        let mut bytes = std::fs::read(&file).unwrap();
+        bytes[8..12].copy_from_slice(&(INDEX_FORMAT_VERSION + 1).to_le_bytes());
+        std::fs::write(&file, &bytes).unwrap();
+        assert!(matches!(load(&file), Err(NexusError::Index(_))));
+
+        std::fs::write(&file, b"not an index").unwrap();
+        assert!(matches!(load(&file), Err(NexusError::Index(_))));
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let saved = |serial| SavedIndexHeader {
+            drives: vec![SavedDrive {
+                letter: 'Q',
+                volume_id: None,
+                serial,
+            }],
+            entry_count: 0,
+            saved_at: chrono::Utc::now(),
+        };
+        // Unidentified volumes are trusted; a known one that no longer
+        // matches (Q: isn't mounted here) is not
+        assert!(!saved(None).is_stale());
+        assert!(saved(Some(0x1234_ABCD)).is_stale());
+    }
+}
@@ -0,0 +1,96 @@
+//! AC/battery power detection
+//!
+//! Lets background indexing hold off while a laptop runs on battery. Only
+//! Windows reports a power source; elsewhere the machine is always treated
+//! as plugged in.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How often a scan re-checks the power source
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often a paused scan polls for AC power
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a paused scan checks whether it was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the machine is running on battery power
+///
+/// False when plugged in, when there is no battery (desktops), when the
+/// state is unknown, and on non-Windows platforms.
+#[cfg(windows)]
+pub fn is_on_battery() -> bool {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    if unsafe { GetSystemPowerStatus(&mut status) }.is_err() {
+        return false;
+    }
+    // 0 = offline, 1 = online, 255 = unknown
+    status.ACLineStatus == 0
+}
+
+#[cfg(not(windows))]
+pub fn is_on_battery() -> bool {
+    false
+}
+
+/// Blocks scan workers while on battery, for `IndexConfig::pause_on_battery`
+///
+/// The power source is checked at most once per [`CHECK_INTERVAL`], so the
+/// per-entry cost is an atomic load. The worker that finds the machine on
+/// battery sleeps until AC power returns; the others stop at their next
+/// check.
+pub(super) struct PowerGate {
+    enabled: bool,
+    start: Instant,
+    /// Milliseconds after `start` of the next check
+    next_check_ms: AtomicU64,
+}
+
+impl PowerGate {
+    pub(super) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            start: Instant::now(),
+            next_check_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Return at once on AC power; otherwise wait until it returns or
+    /// `cancelled` reports true
+    pub(super) fn wait_for_ac(&self, cancelled: impl Fn() -> bool) {
+        if !self.enabled {
+            return;
+        }
+        let now = self.start.elapsed().as_millis() as u64;
+        if now < self.next_check_ms.load(Ordering::Relaxed) {
+            return;
+        }
+        self.next_check_ms
+            .store(now + CHECK_INTERVAL.as_millis() as u64, Ordering::Relaxed);
+
+        if !is_on_battery() {
+            return;
+        }
+        info!("On battery power, indexing paused until AC power returns");
+        let mut next_poll = Instant::now() + PAUSED_POLL_INTERVAL;
+        loop {
+            if cancelled() {
+                info!("Indexing cancelled while paused on battery");
+                return;
+            }
+            if Instant::now() >= next_poll {
+                if !is_on_battery() {
+                    break;
+                }
+                next_poll += PAUSED_POLL_INTERVAL;
+            }
+            std::thread::sleep(CANCEL_POLL_INTERVAL);
+        }
+        info!("AC power restored, resuming indexing");
+    }
+}
@@ -0,0 +1,186 @@
+//! Progress reporting cadence
+//!
+//! A scan visits millions of entries; calling a progress callback for each
+//! one, especially across FFI, costs more than the work it reports on.
+//! [`ProgressThrottle`] lets through only the reports an interval allows.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Receives scan progress as (entries indexed, estimated total, drive of
+/// the latest entry)
+///
+/// Called from the scan workers, at most as often as
+/// `IndexConfig::progress_interval` allows, plus once when the scan ends
+/// with no drive. Drives are scanned in parallel, so consecutive reports
+/// may name different drives.
+#[derive(Clone)]
+pub struct ScanProgressCallback(Arc<dyn Fn(u64, u64, Option<char>) + Send + Sync>);
+
+impl ScanProgressCallback {
+    pub fn new(callback: impl Fn(u64, u64, Option<char>) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, current: u64, total: u64, drive: Option<char>) {
+        (self.0)(current, total, drive)
+    }
+}
+
+impl std::fmt::Debug for ScanProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ScanProgressCallback")
+    }
+}
+
+/// How often progress is reported: every `entries` entries or every
+/// `millis` milliseconds, whichever comes first
+///
+/// Zero turns a limit off; with both zero every entry is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ProgressInterval {
+    pub entries: u64,
+    pub millis: u64,
+}
+
+impl Default for ProgressInterval {
+    fn default() -> Self {
+        Self {
+            entries: 10_000,
+            millis: 100,
+        }
+    }
+}
+
+/// Decides which progress updates reach the callback
+///
+/// Safe to share between scan workers: the per-entry cost is a few atomic
+/// operations, and of several workers crossing the same interval only one
+/// reports.
+#[derive(Debug)]
+pub struct ProgressThrottle {
+    entries: AtomicU64,
+    millis: AtomicU64,
+    start: Instant,
+    /// Entries counted by [`tick`](Self::tick)
+    count: AtomicU64,
+    /// Count and milliseconds after `start` of the last report
+    last_count: AtomicU64,
+    last_ms: AtomicU64,
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: ProgressInterval) -> Self {
+        Self {
+            entries: AtomicU64::new(interval.entries),
+            millis: AtomicU64::new(interval.millis),
+            start: Instant::now(),
+            count: AtomicU64::new(0),
+            last_count: AtomicU64::new(0),
+            last_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Change the interval; takes effect from the next update
+    pub fn set_interval(&self, interval: ProgressInterval) {
+        self.entries.store(interval.entries, Ordering::Relaxed);
+        self.millis.store(interval.millis, Ordering::Relaxed);
+    }
+
+    /// The current interval
+    pub fn interval(&self) -> ProgressInterval {
+        ProgressInterval {
+            entries: self.entries.load(Ordering::Relaxed),
+            millis: self.millis.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Start counting from zero for a new operation
+    pub fn reset(&self) {
+        self.count.store(0, Ordering::Relaxed);
+        self.last_count.store(0, Ordering::Relaxed);
+        self.last_ms.store(self.elapsed_ms(), Ordering::Relaxed);
+    }
+
+    /// Count one entry; returns the new count when a report is due
+    pub fn tick(&self) -> Option<u64> {
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        self.is_due(count).then_some(count)
+    }
+
+    /// Whether progress at `current` should be reported, for callers that
+    /// keep their own count
+    ///
+    /// A `true` counts as the report, restarting both intervals.
+    pub fn is_due(&self, current: u64) -> bool {
+        let entries = self.entries.load(Ordering::Relaxed);
+        let millis = self.millis.load(Ordering::Relaxed);
+        if entries == 0 && millis == 0 {
+            return true;
+        }
+
+        let last = self.last_count.load(Ordering::Relaxed);
+        let now = self.elapsed_ms();
+        let due = (entries > 0 && current.saturating_sub(last) >= entries)
+            || (millis > 0 && now.saturating_sub(self.last_ms.load(Ordering::Relaxed)) >= millis);
+        if !due {
+            return false;
+        }
+
+        // Of the workers that found it due, the one that moves the mark wins
+        if self
+            .last_count
+            .compare_exchange(last, current, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        self.last_ms.store(now, Ordering::Relaxed);
+        true
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+impl Default for ProgressThrottle {
+    fn default() -> Self {
+        Self::new(ProgressInterval::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_throttle() {
+        let throttle = ProgressThrottle::new(ProgressInterval {
+            entries: 100,
+            millis: 0,
+        });
+        let reported: Vec<u64> = (0..350).filter_map(|_| throttle.tick()).collect();
+        assert_eq!(reported, [100, 200, 300]);
+
+        // Time alone also triggers a report
+        throttle.set_interval(ProgressInterval {
+            entries: 0,
+            millis: 20,
+        });
+        throttle.reset();
+        assert!(!throttle.is_due(1));
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert!(throttle.is_due(2));
+        assert!(!throttle.is_due(3));
+
+        // No limits: every update
+        throttle.set_interval(ProgressInterval {
+            entries: 0,
+            millis: 0,
+        });
+        assert!((0..10).all(|_| throttle.tick().is_some()));
+    }
+}
@@ -0,0 +1,153 @@
+//! Known-file scanning
+//!
+//! Finds indexed files whose content matches a list of known SHA-256
+//! hashes, e.g. known-bad samples or a known-good software inventory.
+
+use super::{ContentHasher, HashFormat};
+use crate::FileEntry;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Hash every indexed file and report those whose SHA-256 is in `known`
+///
+/// Returns `(path, hash)` pairs sorted by path, with the hash in the
+/// hasher's format. `known` must use that format too; hex hashes compare
+/// case-insensitively. Files are always re-read rather than trusting a
+/// `content_hash` from the index, which may predate a change. Directories,
+/// offline files and files above the hasher's size limit are skipped.
+pub fn match_hashes(
+    entries: &[FileEntry],
+    known: &HashSet<String>,
+    hasher: &ContentHasher,
+) -> Vec<(String, String)> {
+    let known = normalize_all(known.iter(), hasher.hash_format());
+    match_candidates(
+        entries.par_iter().filter(|e| is_candidate(e)),
+        &known,
+        hasher,
+    )
+}
+
+/// Like [`match_hashes`], with the size of each known file (hash -> size)
+///
+/// Only files whose size appears in `known` are read, which usually rules
+/// out almost every file without touching its content. MFT entries carry
+/// a size of 0, so those are sized from the file's metadata instead.
+pub fn match_hashes_sized(
+    entries: &[FileEntry],
+    known: &HashMap<String, u64>,
+    hasher: &ContentHasher,
+) -> Vec<(String, String)> {
+    let sizes: HashSet<u64> = known.values().copied().collect();
+    let known = normalize_all(known.keys(), hasher.hash_format());
+    match_candidates(
+        entries
+            .par_iter()
+            .filter(|e| is_candidate(e) && sizes.contains(&entry_size(e))),
+        &known,
+        hasher,
+    )
+}
+
+fn is_candidate(entry: &FileEntry) -> bool {
+    !entry.is_dir && !entry.is_offline
+}
+
+fn entry_size(entry: &FileEntry) -> u64 {
+    if entry.size > 0 {
+        return entry.size;
+    }
+    std::fs::metadata(&entry.path).map_or(0, |m| m.len())
+}
+
+fn match_candidates<'a>(
+    candidates: impl ParallelIterator<Item = &'a FileEntry>,
+    known: &HashSet<String>,
+    hasher: &ContentHasher,
+) -> Vec<(String, String)> {
+    let format = hasher.hash_format();
+    let mut matches: Vec<(String, String)> = candidates
+        .filter_map(|entry| {
+            let hash = hasher.sha256_hash(Path::new(&entry.path))?;
+            known
+                .contains(normalize(&hash, format).as_str())
+                .then(|| (entry.path.clone(), hash))
+        })
+        .collect();
+    matches.sort_unstable();
+    matches
+}
+
+fn normalize_all<'a>(
+    hashes: impl Iterator<Item = &'a String>,
+    format: HashFormat,
+) -> HashSet<String> {
+    hashes.map(|h| normalize(h.trim(), format)).collect()
+}
+
+/// Base64 is case-sensitive; every other format is hex
+fn normalize(hash: &str, format: HashFormat) -> String {
+    match format {
+        HashFormat::Base64 => hash.to_string(),
+        _ => hash.to_ascii_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_match_hashes() {
+        let dir = tempdir().unwrap();
+        let file = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            FileEntry {
+                path: path.to_str().unwrap().to_string(),
+                name: name.to_string(),
+                size: content.len() as u64,
+                ..Default::default()
+            }
+        };
+        let entries = vec![
+            file("bad.exe", b"malware"),
+            file("copy.bin", b"malware"),
+            file("good.txt", b"hello"),
+        ];
+
+        let hasher = ContentHasher::new(u64::MAX);
+        let bad = hasher.sha256_hash(Path::new(&entries[0].path)).unwrap();
+
+        let known = HashSet::from([bad.to_ascii_uppercase(), "0".repeat(64)]);
+        let matches = match_hashes(&entries, &known, &hasher);
+        assert_eq!(
+            matches,
+            vec![
+                (entries[0].path.clone(), bad.clone()),
+                (entries[1].path.clone(), bad.clone()),
+            ]
+        );
+
+        // A size that no file has rules everything out unread
+        let sized = HashMap::from([(bad.clone(), 7u64)]);
+        assert_eq!(match_hashes_sized(&entries, &sized, &hasher).len(), 2);
+        let wrong_size = HashMap::from([(bad.clone(), 8u64)]);
+        assert!(match_hashes_sized(&entries, &wrong_size, &hasher).is_empty());
+
+        // Unsized (MFT) entries fall back to the size on disk
+        let unsized_entries: Vec<FileEntry> = entries
+            .iter()
+            .map(|e| FileEntry {
+                size: 0,
+                ..e.clone()
+            })
+            .collect();
+        assert_eq!(
+            match_hashes_sized(&unsized_entries, &sized, &hasher).len(),
+            2
+        );
+    }
+}
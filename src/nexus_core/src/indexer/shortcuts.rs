@@ -0,0 +1,275 @@
+//! Shell link (`.lnk`) inspection
+//!
+//! Reads shortcut targets straight from the MS-SHLLINK binary format, so no
+//! COM initialisation is needed and the indexer can scan shortcuts from any
+//! thread, and no extra dependency is needed.
+
+use crate::FileEntry;
+use rayon::prelude::*;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 0x4C;
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const IS_UNICODE: u32 = 0x80;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x02;
+
+/// Find shortcuts whose target no longer exists
+///
+/// Returns `(shortcut path, target path)` pairs. Shortcuts that can't be
+/// parsed, or that point into the shell namespace rather than the file
+/// system (Control Panel items, etc.), are skipped rather than reported.
+/// Targets on network shares are checked too, which can be slow if the
+/// server is unreachable.
+pub fn find_broken_shortcuts(entries: &[FileEntry]) -> Vec<(String, String)> {
+    entries
+        .par_iter()
+        .filter(|e| {
+            !e.is_dir
+                && e.extension
+                    .as_deref()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+        })
+        .filter_map(|e| {
+            let target = read_shortcut_target(&e.path)?;
+            (!Path::new(&target).exists()).then(|| (e.path.clone(), target))
+        })
+        .collect()
+}
+
+/// Read the file-system target of a shortcut
+///
+/// Relative targets are resolved against the shortcut's own directory.
+pub fn read_shortcut_target<P: AsRef<Path>>(path: P) -> Option<String> {
+    let data = std::fs::read(path.as_ref()).ok()?;
+    let target = parse_target(&data)?;
+
+    if is_absolute(&target) {
+        return Some(target);
+    }
+
+    let base = path.as_ref().parent()?;
+    Some(base.join(target).display().to_string())
+}
+
+fn is_absolute(target: &str) -> bool {
+    let bytes = target.as_bytes();
+    target.starts_with("\\\\")
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        || Path::new(target).is_absolute()
+}
+
+/// Extract the target path from raw shell link bytes
+fn parse_target(data: &[u8]) -> Option<String> {
+    if read_u32(data, 0)? as usize != HEADER_SIZE {
+        return None;
+    }
+    let flags = read_u32(data, 0x14)?;
+    let mut pos = HEADER_SIZE;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        pos += 2 + read_u16(data, pos)? as usize;
+    }
+
+    let mut target = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let size = read_u32(data, pos)? as usize;
+        target = parse_link_info(data.get(pos..pos + size)?);
+        pos += size;
+    }
+
+    if target.is_none() && flags & HAS_RELATIVE_PATH != 0 {
+        let unicode = flags & IS_UNICODE != 0;
+        if flags & HAS_NAME != 0 {
+            pos = skip_string_data(data, pos, unicode)?;
+        }
+        target = read_string_data(data, pos, unicode);
+    }
+
+    target.filter(|t| !t.is_empty())
+}
+
+/// LinkInfo: local base path (+ suffix), or network share name + suffix
+fn parse_link_info(info: &[u8]) -> Option<String> {
+    let header_size = read_u32(info, 4)? as usize;
+    let flags = read_u32(info, 8)?;
+    let suffix = read_ansi(info, read_u32(info, 24)? as usize).unwrap_or_default();
+
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        // Unicode offsets are only present with the extended header
+        if header_size >= 0x24 {
+            let base = read_utf16z(info, read_u32(info, 28)? as usize);
+            let suffix = read_utf16z(info, read_u32(info, 32)? as usize).unwrap_or_default();
+            if let Some(base) = base {
+                return Some(join_suffix(base, &suffix));
+            }
+        }
+        let base = read_ansi(info, read_u32(info, 16)? as usize)?;
+        return Some(join_suffix(base, &suffix));
+    }
+
+    if flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+        let link = read_u32(info, 20)? as usize;
+        let net_name = read_ansi(info, link + read_u32(info, link + 8)? as usize)?;
+        return Some(join_suffix(net_name, &suffix));
+    }
+
+    None
+}
+
+fn join_suffix(mut base: String, suffix: &str) -> String {
+    if !suffix.is_empty() {
+        if !base.ends_with('\\') {
+            base.push('\\');
+        }
+        base.push_str(suffix);
+    }
+    base
+}
+
+fn skip_string_data(data: &[u8], pos: usize, unicode: bool) -> Option<usize> {
+    let chars = read_u16(data, pos)? as usize;
+    Some(pos + 2 + if unicode { chars * 2 } else { chars })
+}
+
+fn read_string_data(data: &[u8], pos: usize, unicode: bool) -> Option<String> {
+    let chars = read_u16(data, pos)? as usize;
+    let start = pos + 2;
+    if unicode {
+        let raw = data.get(start..start + chars * 2)?;
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    } else {
+        let raw = data.get(start..start + chars)?;
+        Some(String::from_utf8_lossy(raw).into_owned())
+    }
+}
+
+/// NUL-terminated code-page string; decoded lossily as UTF-8, which is exact
+/// for ASCII paths
+fn read_ansi(data: &[u8], offset: usize) -> Option<String> {
+    let raw = data.get(offset..)?;
+    let end = raw.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&raw[..end]).into_owned())
+}
+
+fn read_utf16z(data: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = data
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn header(flags: u32) -> Vec<u8> {
+        let mut data = vec![0u8; HEADER_SIZE];
+        data[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        data[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+        data
+    }
+
+    /// Shortcut with a LinkInfo local base path (ANSI only)
+    fn local_link(target: &str) -> Vec<u8> {
+        let mut data = header(HAS_LINK_INFO);
+        let header_size = 0x1Cu32;
+        let base_offset = header_size;
+        let suffix_offset = base_offset + target.len() as u32 + 1;
+        let size = suffix_offset + 1;
+
+        for value in [
+            size,
+            header_size,
+            VOLUME_ID_AND_LOCAL_BASE_PATH,
+            0,
+            base_offset,
+            0,
+            suffix_offset,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(target.as_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    /// Shortcut with only a unicode relative path in StringData
+    fn relative_link(target: &str) -> Vec<u8> {
+        let mut data = header(HAS_RELATIVE_PATH | IS_UNICODE);
+        let units: Vec<u16> = target.encode_utf16().collect();
+        data.extend_from_slice(&(units.len() as u16).to_le_bytes());
+        for unit in units {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data
+    }
+
+    fn lnk_entry(path: &Path) -> FileEntry {
+        FileEntry {
+            path: path.display().to_string(),
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            extension: Some("lnk".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!(
+            parse_target(&local_link("C:\\Tools\\app.exe")).as_deref(),
+            Some("C:\\Tools\\app.exe")
+        );
+        assert_eq!(
+            parse_target(&relative_link("..\\app.exe")).as_deref(),
+            Some("..\\app.exe")
+        );
+        assert_eq!(parse_target(b"not a shortcut"), None);
+        // ID-list only (shell namespace item): no file-system target
+        assert_eq!(parse_target(&header(0)), None);
+    }
+
+    #[test]
+    fn test_find_broken_shortcuts() {
+        let dir = tempdir().unwrap();
+        let live_target = dir.path().join("app.exe");
+        std::fs::write(&live_target, b"x").unwrap();
+
+        let live = dir.path().join("live.lnk");
+        std::fs::write(&live, local_link(&live_target.display().to_string())).unwrap();
+        let dead = dir.path().join("dead.lnk");
+        std::fs::write(&dead, relative_link("missing.exe")).unwrap();
+        let garbage = dir.path().join("garbage.lnk");
+        std::fs::write(&garbage, b"junk").unwrap();
+
+        let broken =
+            find_broken_shortcuts(&[lnk_entry(&live), lnk_entry(&dead), lnk_entry(&garbage)]);
+
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].0, dead.display().to_string());
+        assert!(broken[0].1.ends_with("missing.exe"));
+    }
+}
@@ -0,0 +1,143 @@
+//! Running index statistics
+//!
+//! The batch APIs report [`IndexStats`] once a scan finishes. A
+//! [`StatsAccumulator`] fed from a sink gives the same totals, plus a
+//! per-extension breakdown, while the scan is still running.
+
+use crate::{FileEntry, IndexStats};
+use dashmap::DashMap;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Files of one extension seen so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExtensionTotals {
+    pub count: u64,
+    pub size: u64,
+}
+
+/// Totals over a stream of entries
+///
+/// Takes `&self`, so one accumulator can be shared by the concurrent sink
+/// calls of `FastIndexer::index_all_with_sink` and read from another thread
+/// at any time. Reads taken mid-scan are each consistent on their own but
+/// may be a few entries apart from one another.
+#[derive(Debug, Default)]
+pub struct StatsAccumulator {
+    total_files: AtomicU64,
+    total_dirs: AtomicU64,
+    total_size: AtomicU64,
+    /// Keyed by lowercased `FileEntry::extension`; `""` for files without
+    /// one
+    extensions: DashMap<String, ExtensionTotals>,
+}
+
+impl StatsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one entry; directories count towards `total_dirs` only
+    pub fn add(&self, entry: &FileEntry) {
+        if entry.is_dir {
+            self.total_dirs.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        self.total_files.fetch_add(1, Ordering::Relaxed);
+        self.total_size.fetch_add(entry.size, Ordering::Relaxed);
+
+        let extension = entry.extension.as_deref().unwrap_or_default();
+        let extension: Cow<str> = if extension.bytes().any(|b| b.is_ascii_uppercase()) {
+            extension.to_ascii_lowercase().into()
+        } else {
+            extension.into()
+        };
+        // Avoid allocating the key for extensions already seen
+        if let Some(mut totals) = self.extensions.get_mut(extension.as_ref()) {
+            totals.count += 1;
+            totals.size += entry.size;
+            return;
+        }
+        let mut totals = self.extensions.entry(extension.into_owned()).or_default();
+        totals.count += 1;
+        totals.size += entry.size;
+    }
+
+    pub fn total_files(&self) -> u64 {
+        self.total_files.load(Ordering::Relaxed)
+    }
+
+    pub fn total_dirs(&self) -> u64 {
+        self.total_dirs.load(Ordering::Relaxed)
+    }
+
+    /// Total size of files in bytes
+    pub fn total_size(&self) -> u64 {
+        self.total_size.load(Ordering::Relaxed)
+    }
+
+    /// Totals per extension; `JPG` and `jpg` are counted together as `jpg`
+    pub fn by_extension(&self) -> HashMap<String, ExtensionTotals> {
+        self.extensions
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+
+    /// The totals so far as [`IndexStats`]
+    ///
+    /// Only the file, directory and size totals are filled in; timing,
+    /// drives and truncation are known only to the scan itself.
+    pub fn stats(&self) -> IndexStats {
+        IndexStats {
+            total_files: self.total_files(),
+            total_dirs: self.total_dirs(),
+            total_size: self.total_size(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: u64, is_dir: bool) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            extension: name.rsplit_once('.').map(|(_, ext)| ext.to_string()),
+            size,
+            is_dir,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let stats = StatsAccumulator::new();
+        for e in [
+            entry("a.txt", 10, false),
+            entry("b.TXT", 5, false),
+            entry("c.rs", 7, false),
+            entry("Makefile", 3, false),
+            entry("dir.d", 4096, true),
+        ] {
+            stats.add(&e);
+        }
+
+        assert_eq!(stats.total_files(), 4);
+        assert_eq!(stats.total_dirs(), 1);
+        assert_eq!(stats.total_size(), 25);
+
+        let by_extension = stats.by_extension();
+        assert_eq!(by_extension.len(), 3);
+        assert_eq!(by_extension["txt"], ExtensionTotals { count: 2, size: 15 });
+        assert_eq!(by_extension[""].count, 1);
+
+        let snapshot = stats.stats();
+        assert_eq!(snapshot.total_files, 4);
+        assert_eq!(snapshot.total_size, 25);
+    }
+}
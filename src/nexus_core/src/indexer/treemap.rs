@@ -0,0 +1,148 @@
+//! Size rollups for treemap views
+//!
+//! Rebuilds the directory hierarchy from each entry's `parent` and sums
+//! file sizes up the tree in one pass, giving the nested data a
+//! WinDirStat-style treemap is drawn from.
+
+use crate::FileEntry;
+use std::collections::HashMap;
+
+/// A file or directory with the total size of everything below it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNode {
+    /// Full path; empty for the synthetic root joining several trees
+    pub path: String,
+    /// File size, or for directories the recursive size of their contents
+    pub size: u64,
+    pub is_dir: bool,
+    /// Largest first; empty for files and for directories cut off by
+    /// [`TreeNode::truncate`]
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Drop nodes more than `max_depth` levels below this one
+    ///
+    /// Sizes are kept, so a cut-off directory still reports the total of
+    /// everything under it.
+    pub fn truncate(&mut self, max_depth: usize) {
+        if max_depth == 0 {
+            self.children = Vec::new();
+            return;
+        }
+        for child in &mut self.children {
+            child.truncate(max_depth - 1);
+        }
+    }
+}
+
+/// Build the size tree for a set of indexed entries
+///
+/// Entries whose parent isn't itself an indexed directory become roots.
+/// A single root is returned as is; several (e.g. one per drive) are
+/// gathered under a synthetic node with an empty path.
+pub fn build_treemap(entries: &[FileEntry]) -> TreeNode {
+    let index: HashMap<&str, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.path.as_str(), i))
+        .collect();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); entries.len()];
+    let mut roots = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        match index.get(entry.parent.as_str()) {
+            Some(&parent) if parent != i && entries[parent].is_dir => children[parent].push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    let mut nodes: Vec<TreeNode> = roots
+        .into_iter()
+        .map(|root| build_node(root, entries, &children))
+        .collect();
+
+    if nodes.len() == 1 {
+        return nodes.remove(0);
+    }
+    nodes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    TreeNode {
+        path: String::new(),
+        size: nodes.iter().map(|n| n.size).sum(),
+        is_dir: true,
+        children: nodes,
+    }
+}
+
+fn build_node(i: usize, entries: &[FileEntry], children: &[Vec<usize>]) -> TreeNode {
+    let entry = &entries[i];
+    // A link back to an ancestor would count that ancestor twice
+    if !entry.is_dir || entry.is_cycle {
+        return TreeNode {
+            path: entry.path.clone(),
+            size: if entry.is_cycle { 0 } else { entry.size },
+            is_dir: entry.is_dir,
+            children: Vec::new(),
+        };
+    }
+
+    let mut nodes: Vec<TreeNode> = children[i]
+        .iter()
+        .map(|&child| build_node(child, entries, children))
+        .collect();
+    nodes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+    TreeNode {
+        path: entry.path.clone(),
+        // A directory's own size field is 0 or meaningless; only contents count
+        size: nodes.iter().map(|n| n.size).sum(),
+        is_dir: true,
+        children: nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, parent: &str, size: u64, is_dir: bool) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            parent: parent.to_string(),
+            size,
+            is_dir,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_treemap() {
+        let entries = vec![
+            entry(r"C:\data\small.txt", r"C:\data", 10, false),
+            entry(r"C:\data", "C:\\", 4096, true),
+            entry(r"C:\data\media", r"C:\data", 0, true),
+            entry(r"C:\data\media\a.mp4", r"C:\data\media", 500, false),
+            entry(r"C:\data\media\b.mp4", r"C:\data\media", 300, false),
+        ];
+
+        let mut tree = build_treemap(&entries);
+        assert_eq!(tree.path, r"C:\data");
+        assert_eq!(tree.size, 810);
+        assert_eq!(tree.children[0].path, r"C:\data\media");
+        assert_eq!(tree.children[0].size, 800);
+        assert_eq!(tree.children[0].children[0].size, 500);
+
+        tree.truncate(1);
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(tree.children[0].size, 800);
+
+        // Separate trees are joined under a synthetic root
+        let mut two_drives = entries.clone();
+        two_drives.push(entry(r"D:\big.iso", "D:\\", 2000, false));
+        let tree = build_treemap(&two_drives);
+        assert_eq!(tree.path, "");
+        assert_eq!(tree.size, 2810);
+        assert_eq!(tree.children[0].path, r"D:\big.iso");
+    }
+}
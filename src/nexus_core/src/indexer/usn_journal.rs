@@ -3,33 +3,68 @@
 //! Monitors real-time file system changes on NTFS volumes using the USN Journal.
 //! This provides instant notification of file creates, deletes, renames, and modifications.
 
-use crate::Result;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use super::mft_reader::ROOT_FILE_REF;
+#[cfg(windows)]
+use super::usn_record::parse_usn_records;
+use super::usn_record::{ref_key, UsnRecord};
+use crate::{NexusError, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+#[cfg(windows)]
+use std::sync::mpsc::{channel, Sender};
+#[cfg(windows)]
 use std::thread;
-use tracing::{info, warn};
+#[cfg(windows)]
+use tracing::info;
+use tracing::warn;
 
 /// Types of file system changes
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ChangeType {
     Created,
     Deleted,
     Modified,
-    Renamed { old_name: String },
+    /// `path` is the new path. `old_name` is the old file name and
+    /// `old_path` the full old path; both are empty when the old-name half
+    /// of the rename wasn't seen.
+    Renamed {
+        #[serde(default)]
+        old_name: String,
+        #[serde(default)]
+        old_path: String,
+    },
     SecurityChange,
     Unknown,
 }
 
 /// A file system change event from the USN Journal
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct UsnChange {
+    /// Full path, e.g. `C:\Users\me\notes.txt`; `C:\...\notes.txt` when
+    /// a parent folder is unknown to the monitor
     pub path: String,
     pub change_type: ChangeType,
     pub is_directory: bool,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// A point in a volume's USN journal, recorded when an index is built
+///
+/// Changes after it are the ones the index hasn't seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct JournalPosition {
+    /// Changes when the journal is deleted and recreated, which
+    /// invalidates every position recorded before
+    pub journal_id: u64,
+    pub next_usn: i64,
+}
+
 /// USN Journal monitor for real-time file changes
 pub struct UsnJournal {
+    #[cfg_attr(not(windows), allow(dead_code))]
     drive: char,
     running: std::sync::Arc<std::sync::atomic::AtomicBool>,
 }
@@ -94,18 +129,6 @@ impl UsnJournal {
             info!("USN Journal monitoring started for drive {}", drive);
 
             // Query USN Journal
-            #[repr(C)]
-            #[allow(non_snake_case)]
-            struct UsnJournalData {
-                UsnJournalID: u64,
-                FirstUsn: i64,
-                NextUsn: i64,
-                LowestValidUsn: i64,
-                MaxUsn: i64,
-                MaximumSize: u64,
-                AllocationDelta: u64,
-            }
-
             let mut journal_data = UsnJournalData {
                 UsnJournalID: 0,
                 FirstUsn: 0,
@@ -136,18 +159,12 @@ impl UsnJournal {
                 return;
             }
 
-            // Read USN records
-            #[repr(C)]
-            #[allow(non_snake_case)]
-            struct ReadUsnJournalData {
-                StartUsn: i64,
-                ReasonMask: u32,
-                ReturnOnlyOnClose: u32,
-                Timeout: u64,
-                BytesToWaitFor: u64,
-                UsnJournalID: u64,
-            }
+            // Seed after taking the start position, so changes made while
+            // the MFT is read are replayed on top of it
+            let (_, file_refs) = super::MftReader::read_usn_data(handle);
+            let mut decoder = ChangeDecoder::new(drive, file_refs);
 
+            // Read USN records
             let mut read_data = ReadUsnJournalData {
                 StartUsn: journal_data.NextUsn,
                 ReasonMask: 0xFFFFFFFF, // All reasons
@@ -155,6 +172,8 @@ impl UsnJournal {
                 Timeout: 0,
                 BytesToWaitFor: 0,
                 UsnJournalID: journal_data.UsnJournalID,
+                MinMajorVersion: 2,
+                MaxMajorVersion: 3,
             };
 
             let buffer_size = 64 * 1024;
@@ -175,69 +194,20 @@ impl UsnJournal {
                 };
 
                 if result.is_err() || bytes_returned <= 8 {
+                    // Caught up: an old name still waiting won't be paired
+                    for change in decoder.flush_pending() {
+                        let _ = tx.send(change);
+                    }
                     thread::sleep(std::time::Duration::from_millis(100));
                     continue;
                 }
 
-                // Parse USN records
-                let next_usn = unsafe { *(buffer.as_ptr() as *const i64) };
-                let mut offset = 8usize;
-
-                while offset < bytes_returned as usize {
-                    #[repr(C)]
-                    #[allow(non_snake_case)]
-                    struct UsnRecord {
-                        RecordLength: u32,
-                        MajorVersion: u16,
-                        MinorVersion: u16,
-                        FileReferenceNumber: u64,
-                        ParentFileReferenceNumber: u64,
-                        Usn: i64,
-                        TimeStamp: i64,
-                        Reason: u32,
-                        SourceInfo: u32,
-                        SecurityId: u32,
-                        FileAttributes: u32,
-                        FileNameLength: u16,
-                        FileNameOffset: u16,
-                    }
-
-                    if offset + std::mem::size_of::<UsnRecord>() > bytes_returned as usize {
+                let next_usn = i64::from_ne_bytes(buffer[..8].try_into().unwrap_or_default());
+                let records = parse_usn_records(&buffer[8..bytes_returned as usize]);
+                for change in records.iter().flat_map(|record| decoder.decode(record)) {
+                    if tx.send(change).is_err() {
                         break;
                     }
-
-                    let record = unsafe { &*(buffer.as_ptr().add(offset) as *const UsnRecord) };
-
-                    if record.RecordLength == 0 {
-                        break;
-                    }
-
-                    // Extract filename
-                    let name_offset = offset + record.FileNameOffset as usize;
-                    let name_len = record.FileNameLength as usize / 2;
-
-                    if name_offset + record.FileNameLength as usize <= bytes_returned as usize {
-                        let name_ptr = unsafe { buffer.as_ptr().add(name_offset) as *const u16 };
-                        let name_slice = unsafe { std::slice::from_raw_parts(name_ptr, name_len) };
-                        let name = String::from_utf16_lossy(name_slice);
-
-                        // Determine change type
-                        let change_type = reason_to_change_type(record.Reason);
-                        let is_directory = (record.FileAttributes & 0x10) != 0;
-
-                        let change = UsnChange {
-                            path: format!("{}:\\...\\{}", drive, name), // Simplified path
-                            change_type,
-                            is_directory,
-                            timestamp: chrono::Utc::now(),
-                        };
-
-                        if tx.send(change).is_err() {
-                            break;
-                        }
-                    }
-
-                    offset += record.RecordLength as usize;
                 }
 
                 read_data.StartUsn = next_usn;
@@ -252,7 +222,7 @@ impl UsnJournal {
 
     #[cfg(not(windows))]
     pub fn start_monitoring(&self) -> Result<Receiver<UsnChange>> {
-        Err(NexusError::Windows(
+        Err(crate::NexusError::Windows(
             "USN Journal is only available on Windows".into(),
         ))
     }
@@ -262,17 +232,520 @@ impl UsnJournal {
         self.running
             .store(false, std::sync::atomic::Ordering::SeqCst);
     }
+
+    /// Current end of the journal; record it before a scan starts so no
+    /// change made during the scan is missed
+    #[cfg(windows)]
+    pub fn position(&self) -> Result<JournalPosition> {
+        let handle = open_volume(self.drive)?;
+        let data = query_journal(handle, self.drive);
+        close_volume(handle);
+        let data = data?;
+        Ok(JournalPosition {
+            journal_id: data.UsnJournalID,
+            next_usn: data.NextUsn,
+        })
+    }
+
+    #[cfg(not(windows))]
+    pub fn position(&self) -> Result<JournalPosition> {
+        Err(crate::NexusError::Windows(
+            "USN Journal is only available on Windows".into(),
+        ))
+    }
+
+    /// Count changes recorded since `since`
+    ///
+    /// Counts closed change records, so a file written several times while
+    /// open counts once and a file still open isn't counted yet. Fails if
+    /// the journal was recreated or has wrapped past `since`, in which case
+    /// the number of missed changes is unknown and a full re-index is due.
+    #[cfg(windows)]
+    pub fn count_changes_since(&self, since: &JournalPosition) -> Result<u64> {
+        use windows::Win32::System::Ioctl::FSCTL_READ_USN_JOURNAL;
+
+        let handle = open_volume(self.drive)?;
+        let result = (|| {
+            let data = query_journal(handle, self.drive)?;
+            if data.UsnJournalID != since.journal_id || since.next_usn < data.FirstUsn {
+                return Err(crate::NexusError::Index(format!(
+                    "USN journal on {}: no longer covers the recorded position",
+                    self.drive
+                )));
+            }
+
+            let mut read_data = ReadUsnJournalData {
+                StartUsn: since.next_usn,
+                ReasonMask: 0xFFFFFFFF,
+                ReturnOnlyOnClose: 1,
+                Timeout: 0,
+                BytesToWaitFor: 0,
+                UsnJournalID: data.UsnJournalID,
+                MinMajorVersion: 2,
+                MaxMajorVersion: 3,
+            };
+            let mut buffer = vec![0u8; 64 * 1024];
+            let mut count = 0u64;
+
+            while read_data.StartUsn < data.NextUsn {
+                let mut bytes_returned: u32 = 0;
+                unsafe {
+                    windows::Win32::System::IO::DeviceIoControl(
+                        handle,
+                        FSCTL_READ_USN_JOURNAL,
+                        Some(&read_data as *const _ as *const _),
+                        std::mem::size_of::<ReadUsnJournalData>() as u32,
+                        Some(buffer.as_mut_ptr() as *mut _),
+                        buffer.len() as u32,
+                        Some(&mut bytes_returned),
+                        None,
+                    )
+                }
+                .map_err(|e| {
+                    crate::NexusError::Windows(format!("Failed to read USN journal: {}", e))
+                })?;
+
+                if bytes_returned <= 8 {
+                    break;
+                }
+
+                let mut offset = 8usize;
+                while offset + 4 <= bytes_returned as usize {
+                    let length = u32::from_ne_bytes(
+                        buffer[offset..offset + 4].try_into().unwrap_or_default(),
+                    );
+                    if length == 0 {
+                        break;
+                    }
+                    count += 1;
+                    offset += length as usize;
+                }
+
+                read_data.StartUsn = i64::from_ne_bytes(buffer[..8].try_into().unwrap_or_default());
+            }
+            Ok(count)
+        })();
+        close_volume(handle);
+        result
+    }
+
+    #[cfg(not(windows))]
+    pub fn count_changes_since(&self, _since: &JournalPosition) -> Result<u64> {
+        Err(crate::NexusError::Windows(
+            "USN Journal is only available on Windows".into(),
+        ))
+    }
+
+    /// Start monitoring and append every change to `path`, one JSON object
+    /// per line, for later [`replay`](Self::replay)
+    ///
+    /// Writing runs on a background thread until [`stop`](Self::stop); its
+    /// handle returns how many changes were written. Each line is flushed
+    /// as it is written, so the log survives a crash. With `rotate_bytes`,
+    /// a file that has reached that size is renamed to `path.1` (then
+    /// `path.2`, ...) and a fresh `path` started; numbering continues from
+    /// earlier sessions. Recorded paths are only as complete as the
+    /// monitor's.
+    pub fn record_to(
+        &self,
+        path: impl AsRef<Path>,
+        rotate_bytes: Option<u64>,
+    ) -> Result<std::thread::JoinHandle<Result<u64>>> {
+        let changes = self.start_monitoring()?;
+        let path = path.as_ref().to_path_buf();
+        Ok(std::thread::spawn(move || {
+            write_changes(changes, &path, rotate_bytes)
+        }))
+    }
+
+    /// Read back a log written by [`record_to`](Self::record_to), oldest
+    /// change first
+    ///
+    /// Rotated files (`path.1`, `path.2`, ...) are read before `path`.
+    /// Lines that don't parse, such as one cut short by a crash or one that
+    /// isn't valid UTF-8, are skipped with a warning. A file that fails to
+    /// read midway is abandoned and replay moves on to the next.
+    pub fn replay(path: impl AsRef<Path>) -> Result<impl Iterator<Item = UsnChange>> {
+        let path = path.as_ref();
+        let mut files: Vec<PathBuf> = (1..)
+            .map(|n| rotated_path(path, n))
+            .take_while(|p| p.exists())
+            .collect();
+        if path.exists() {
+            files.push(path.to_path_buf());
+        }
+        if files.is_empty() {
+            return Err(NexusError::InvalidPath(path.display().to_string()));
+        }
+
+        Ok(files.into_iter().flat_map(|file| {
+            let lines = match File::open(&file) {
+                Ok(f) => Some(BufReader::new(f).split(b'\n')),
+                Err(e) => {
+                    warn!("Skipping USN log {}: {}", file.display(), e);
+                    None
+                }
+            };
+            let read_file = file.clone();
+            lines
+                .into_iter()
+                .flatten()
+                .map_while(move |line| {
+                    line.map_err(|e| {
+                        warn!("Stopped reading USN log {}: {}", read_file.display(), e)
+                    })
+                    .ok()
+                })
+                .filter(|line| !line.trim_ascii().is_empty())
+                .filter_map(move |line| match serde_json::from_slice(&line) {
+                    Ok(change) => Some(change),
+                    Err(e) => {
+                        warn!("Skipping bad line in USN log {}: {}", file.display(), e);
+                        None
+                    }
+                })
+        }))
+    }
+}
+
+/// `path.n`, e.g. `audit.jsonl.2`
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Append `changes` to `path` as JSON lines, rotating at `rotate_bytes`
+fn write_changes(
+    changes: impl IntoIterator<Item = UsnChange>,
+    path: &Path,
+    rotate_bytes: Option<u64>,
+) -> Result<u64> {
+    let open = || OpenOptions::new().create(true).append(true).open(path);
+    let mut file = open()?;
+    let mut size = file.metadata()?.len();
+    let mut next_rotation = (1..)
+        .find(|&n| !rotated_path(path, n).exists())
+        .unwrap_or(1);
+    let mut written = 0;
+
+    for change in changes {
+        if rotate_bytes.is_some_and(|max| size >= max) {
+            drop(file);
+            std::fs::rename(path, rotated_path(path, next_rotation))?;
+            next_rotation += 1;
+            file = open()?;
+            size = 0;
+        }
+
+        let mut line = serde_json::to_vec(&change)
+            .map_err(|e| NexusError::Index(format!("Failed to encode USN change: {}", e)))?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.flush()?;
+        size += line.len() as u64;
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[allow(non_snake_case)]
+struct UsnJournalData {
+    UsnJournalID: u64,
+    FirstUsn: i64,
+    NextUsn: i64,
+    LowestValidUsn: i64,
+    MaxUsn: i64,
+    MaximumSize: u64,
+    AllocationDelta: u64,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+#[allow(non_snake_case)]
+struct ReadUsnJournalData {
+    StartUsn: i64,
+    ReasonMask: u32,
+    ReturnOnlyOnClose: u32,
+    Timeout: u64,
+    BytesToWaitFor: u64,
+    UsnJournalID: u64,
+    /// Record versions to return: V2 on NTFS, V3 for 128-bit references
+    MinMajorVersion: u16,
+    MaxMajorVersion: u16,
+}
+
+#[cfg(windows)]
+fn open_volume(drive: char) -> Result<windows::Win32::Foundation::HANDLE> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_SHARE_DELETE, FILE_SHARE_READ,
+        FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+
+    let volume_path: Vec<u16> = format!("\\\\.\\{}:", drive)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        CreateFileW(
+            PCWSTR(volume_path.as_ptr()),
+            0x80000000, // GENERIC_READ
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            None,
+        )
+    }
+    .map_err(|e| crate::NexusError::Windows(format!("Failed to open volume {}: {}", drive, e)))
+}
+
+#[cfg(windows)]
+fn close_volume(handle: windows::Win32::Foundation::HANDLE) {
+    let _ = unsafe { windows::Win32::Foundation::CloseHandle(handle) };
+}
+
+#[cfg(windows)]
+fn query_journal(
+    handle: windows::Win32::Foundation::HANDLE,
+    drive: char,
+) -> Result<UsnJournalData> {
+    use windows::Win32::System::Ioctl::FSCTL_QUERY_USN_JOURNAL;
+
+    let mut data = UsnJournalData {
+        UsnJournalID: 0,
+        FirstUsn: 0,
+        NextUsn: 0,
+        LowestValidUsn: 0,
+        MaxUsn: 0,
+        MaximumSize: 0,
+        AllocationDelta: 0,
+    };
+    let mut bytes_returned: u32 = 0;
+    unsafe {
+        windows::Win32::System::IO::DeviceIoControl(
+            handle,
+            FSCTL_QUERY_USN_JOURNAL,
+            None,
+            0,
+            Some(&mut data as *mut _ as *mut _),
+            std::mem::size_of::<UsnJournalData>() as u32,
+            Some(&mut bytes_returned),
+            None,
+        )
+    }
+    .map_err(|e| {
+        crate::NexusError::Windows(format!("Failed to query USN journal on {}: {}", drive, e))
+    })?;
+    Ok(data)
+}
+
+const USN_REASON_FILE_DELETE: u32 = 0x00000200;
+const USN_REASON_RENAME_OLD_NAME: u32 = 0x00001000;
+const USN_REASON_RENAME_NEW_NAME: u32 = 0x00002000;
+
+/// Records an old-name half waits for its new-name half before it is
+/// taken for a delete (e.g. moved to another volume)
+const RENAME_PAIR_WINDOW: u64 = 64;
+
+/// The old-name half of a rename, waiting for its new-name half
+#[cfg_attr(not(windows), allow(dead_code))]
+struct PendingRename {
+    old_name: String,
+    old_path: String,
+    is_directory: bool,
+    /// Record count when it was seen
+    seen_at: u64,
+}
+
+/// Turns journal records into changes with full paths
+///
+/// NTFS writes a rename as two records sharing a file reference, the old
+/// name then the new name. The first is held back until the second
+/// arrives, and both become one [`ChangeType::Renamed`]. One still unpaired
+/// after [`RENAME_PAIR_WINDOW`] records, or when the journal has been read
+/// to its end, is reported as a delete of the old path.
+#[cfg_attr(not(windows), allow(dead_code))]
+struct ChangeDecoder {
+    drive: char,
+    resolver: PathResolver,
+    pending: HashMap<u128, PendingRename>,
+    records: u64,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl ChangeDecoder {
+    fn new(drive: char, refs: HashMap<u128, (String, u128)>) -> Self {
+        Self {
+            drive,
+            resolver: PathResolver::new(drive, refs),
+            pending: HashMap::new(),
+            records: 0,
+        }
+    }
+
+    /// Changes to report for `record`, oldest first
+    fn decode(&mut self, record: &UsnRecord) -> Vec<UsnChange> {
+        self.records += 1;
+        let mut changes = self.flush_expired();
+
+        let path = self
+            .resolver
+            .resolve(
+                record.file_ref,
+                record.parent_ref,
+                &record.name,
+                record.reason,
+            )
+            .unwrap_or_else(|| format!("{}:\\...\\{}", self.drive, record.name));
+
+        if record.reason & USN_REASON_RENAME_OLD_NAME != 0 {
+            self.pending.insert(
+                record.file_ref,
+                PendingRename {
+                    old_name: record.name.clone(),
+                    old_path: path,
+                    is_directory: record.is_directory(),
+                    seen_at: self.records,
+                },
+            );
+            return changes;
+        }
+
+        let change_type = match self.pending.remove(&record.file_ref) {
+            Some(old) if record.reason & USN_REASON_RENAME_NEW_NAME != 0 => ChangeType::Renamed {
+                old_name: old.old_name,
+                old_path: old.old_path,
+            },
+            // Another change to the file before its new name arrived
+            Some(old) => {
+                self.pending.insert(record.file_ref, old);
+                reason_to_change_type(record.reason)
+            }
+            None => reason_to_change_type(record.reason),
+        };
+        changes.push(UsnChange {
+            path,
+            change_type,
+            is_directory: record.is_directory(),
+            timestamp: chrono::Utc::now(),
+        });
+        changes
+    }
+
+    /// Deletes for the old-name halves that have waited too long
+    fn flush_expired(&mut self) -> Vec<UsnChange> {
+        let records = self.records;
+        self.flush_where(|pending| records - pending.seen_at > RENAME_PAIR_WINDOW)
+    }
+
+    /// Deletes for every old-name half still waiting
+    ///
+    /// Called once the journal has been read to its end: the new-name half
+    /// is written right after the old one, so it would already be here.
+    fn flush_pending(&mut self) -> Vec<UsnChange> {
+        self.flush_where(|_| true)
+    }
+
+    fn flush_where(&mut self, due: impl Fn(&PendingRename) -> bool) -> Vec<UsnChange> {
+        let expired_refs: Vec<u128> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| due(pending))
+            .map(|(&file_ref, _)| file_ref)
+            .collect();
+        let mut expired: Vec<PendingRename> = expired_refs
+            .iter()
+            .filter_map(|file_ref| self.pending.remove(file_ref))
+            .collect();
+        expired.sort_by_key(|pending| pending.seen_at);
+        expired
+            .into_iter()
+            .map(|pending| UsnChange {
+                path: pending.old_path,
+                change_type: ChangeType::Deleted,
+                is_directory: pending.is_directory,
+                timestamp: chrono::Utc::now(),
+            })
+            .collect()
+    }
+}
+
+/// Longest parent chain followed, against loops in a corrupt map
+const MAX_PATH_DEPTH: usize = 1024;
+
+/// Name and parent of every file reference on a volume, kept current from
+/// change records, which carry only the name and parent reference
+#[cfg_attr(not(windows), allow(dead_code))]
+struct PathResolver {
+    refs: HashMap<u128, (String, u128)>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl PathResolver {
+    /// Start from file_ref -> (name, parent_ref) as read from the MFT
+    fn new(drive: char, mut refs: HashMap<u128, (String, u128)>) -> Self {
+        refs.insert(ROOT_FILE_REF, (format!("{}:", drive), 0));
+        Self { refs }
+    }
+
+    /// Full path of a change record, then apply the record to the map
+    ///
+    /// A deleted reference is resolved before it is evicted; the old-name
+    /// half of a rename resolves to the old path and leaves the map alone
+    /// until the new-name half arrives. `None` when a parent is unknown.
+    fn resolve(
+        &mut self,
+        file_ref: u128,
+        parent_ref: u128,
+        name: &str,
+        reason: u32,
+    ) -> Option<String> {
+        let file_ref = ref_key(file_ref);
+        let parent_ref = ref_key(parent_ref);
+        let path = self
+            .path_of(parent_ref)
+            .map(|parent| format!("{}\\{}", parent, name));
+
+        if reason & USN_REASON_FILE_DELETE != 0 {
+            self.refs.remove(&file_ref);
+        } else if reason & USN_REASON_RENAME_OLD_NAME == 0 {
+            self.refs.insert(file_ref, (name.to_string(), parent_ref));
+        }
+        path
+    }
+
+    /// Full path of a known reference
+    fn path_of(&self, file_ref: u128) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = file_ref;
+        while current != ROOT_FILE_REF {
+            if names.len() >= MAX_PATH_DEPTH {
+                return None;
+            }
+            let (name, parent) = self.refs.get(&current)?;
+            names.push(name.as_str());
+            current = *parent;
+        }
+        let (root, _) = self.refs.get(&ROOT_FILE_REF)?;
+        names.push(root);
+        names.reverse();
+        Some(names.join("\\"))
+    }
 }
 
 /// Convert USN reason flags to ChangeType
+#[cfg_attr(not(windows), allow(dead_code))]
 fn reason_to_change_type(reason: u32) -> ChangeType {
     const USN_REASON_FILE_CREATE: u32 = 0x00000100;
-    const USN_REASON_FILE_DELETE: u32 = 0x00000200;
     const USN_REASON_DATA_OVERWRITE: u32 = 0x00000001;
     const USN_REASON_DATA_EXTEND: u32 = 0x00000002;
     const USN_REASON_DATA_TRUNCATION: u32 = 0x00000004;
-    const USN_REASON_RENAME_OLD_NAME: u32 = 0x00001000;
-    const USN_REASON_RENAME_NEW_NAME: u32 = 0x00002000;
     const USN_REASON_SECURITY_CHANGE: u32 = 0x00000800;
 
     if reason & USN_REASON_FILE_CREATE != 0 {
@@ -287,6 +760,7 @@ fn reason_to_change_type(reason: u32) -> ChangeType {
     } else if reason & (USN_REASON_RENAME_OLD_NAME | USN_REASON_RENAME_NEW_NAME) != 0 {
         ChangeType::Renamed {
             old_name: String::new(),
+            old_path: String::new(),
         }
     } else if reason & USN_REASON_SECURITY_CHANGE != 0 {
         ChangeType::SecurityChange
@@ -294,3 +768,207 @@ fn reason_to_change_type(reason: u32) -> ChangeType {
         ChangeType::Unknown
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::usn_record::{parse_usn_records, USN_RECORD_V2_SIZE};
+    use super::*;
+    use tempfile::tempdir;
+
+    fn change(path: &str, change_type: ChangeType) -> UsnChange {
+        UsnChange {
+            path: path.to_string(),
+            change_type,
+            is_directory: false,
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_path_resolver() {
+        const CREATE: u32 = 0x100;
+        const RENAME_NEW: u32 = 0x2000;
+        const RENAME_OLD: u32 = USN_REASON_RENAME_OLD_NAME;
+        const DELETE: u32 = USN_REASON_FILE_DELETE;
+        // A sequence number in the top bits must not matter
+        let seq = |r: u128| r | (7 << 48);
+
+        let seeded = HashMap::from([(100, ("Users".to_string(), ROOT_FILE_REF))]);
+        let mut resolver = PathResolver::new('C', seeded);
+
+        // A nested folder and a file created inside it
+        assert_eq!(
+            resolver
+                .resolve(seq(200), seq(100), "me", CREATE)
+                .as_deref(),
+            Some("C:\\Users\\me")
+        );
+        assert_eq!(
+            resolver
+                .resolve(seq(300), 200, "notes.txt", CREATE)
+                .as_deref(),
+            Some("C:\\Users\\me\\notes.txt")
+        );
+
+        // Renaming the folder moves what's below it
+        assert_eq!(
+            resolver.resolve(200, 100, "me", RENAME_OLD).as_deref(),
+            Some("C:\\Users\\me")
+        );
+        resolver.resolve(200, 100, "you", RENAME_NEW);
+        assert_eq!(
+            resolver.resolve(300, 200, "notes.txt", 0x2).as_deref(),
+            Some("C:\\Users\\you\\notes.txt")
+        );
+
+        // A delete still resolves, then the reference is gone
+        assert_eq!(
+            resolver.resolve(300, 200, "notes.txt", DELETE).as_deref(),
+            Some("C:\\Users\\you\\notes.txt")
+        );
+        assert!(!resolver.refs.contains_key(&300));
+
+        // Unknown parents don't resolve
+        assert_eq!(resolver.resolve(400, 999, "x.txt", CREATE), None);
+    }
+
+    /// A `USN_RECORD_V2` as the journal returns it
+    fn v2_record(file_ref: u64, parent_ref: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let length = (USN_RECORD_V2_SIZE + name.len()).next_multiple_of(8);
+        let mut record = vec![0u8; length];
+        record[0..4].copy_from_slice(&(length as u32).to_le_bytes());
+        record[4..6].copy_from_slice(&2u16.to_le_bytes());
+        record[8..16].copy_from_slice(&file_ref.to_le_bytes());
+        record[16..24].copy_from_slice(&parent_ref.to_le_bytes());
+        record[40..44].copy_from_slice(&reason.to_le_bytes());
+        record[56..58].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        record[58..60].copy_from_slice(&(USN_RECORD_V2_SIZE as u16).to_le_bytes());
+        record[USN_RECORD_V2_SIZE..USN_RECORD_V2_SIZE + name.len()].copy_from_slice(&name);
+        record
+    }
+
+    #[test]
+    fn test_rename_pairing() {
+        let seeded = HashMap::from([
+            (100, ("docs".to_string(), ROOT_FILE_REF)),
+            (200, ("draft.txt".to_string(), 100)),
+            (300, ("moved.txt".to_string(), 100)),
+        ]);
+        let mut decoder = ChangeDecoder::new('C', seeded);
+        let decode = |decoder: &mut ChangeDecoder, buffer: &[u8]| -> Vec<UsnChange> {
+            parse_usn_records(buffer)
+                .iter()
+                .flat_map(|record| decoder.decode(record))
+                .collect()
+        };
+
+        let buffer = [
+            v2_record(200, 100, USN_REASON_RENAME_OLD_NAME, "draft.txt"),
+            v2_record(200, 100, USN_REASON_RENAME_NEW_NAME, "final.txt"),
+        ]
+        .concat();
+        let changes = decode(&mut decoder, &buffer);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "C:\\docs\\final.txt");
+        assert_eq!(
+            changes[0].change_type,
+            ChangeType::Renamed {
+                old_name: "draft.txt".to_string(),
+                old_path: "C:\\docs\\draft.txt".to_string()
+            }
+        );
+
+        // An old name never followed by a new one becomes a delete
+        let mut buffer = v2_record(300, 100, USN_REASON_RENAME_OLD_NAME, "moved.txt");
+        for _ in 0..RENAME_PAIR_WINDOW {
+            buffer.extend(v2_record(200, 100, 0x2, "final.txt"));
+        }
+        let changes = decode(&mut decoder, &buffer);
+        assert_eq!(changes.len(), RENAME_PAIR_WINDOW as usize);
+        assert!(changes
+            .iter()
+            .all(|c| c.change_type == ChangeType::Modified));
+
+        let changes = decode(&mut decoder, &v2_record(200, 100, 0x2, "final.txt"));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "C:\\docs\\moved.txt");
+        assert_eq!(changes[0].change_type, ChangeType::Deleted);
+
+        // ...as does one left at the end of the journal
+        let buffer = v2_record(200, 100, USN_REASON_RENAME_OLD_NAME, "final.txt");
+        assert!(decode(&mut decoder, &buffer).is_empty());
+        let changes = decoder.flush_pending();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "C:\\docs\\final.txt");
+        assert_eq!(changes[0].change_type, ChangeType::Deleted);
+        assert!(decoder.flush_pending().is_empty());
+
+        // A truncated record ends the buffer
+        let mut buffer = v2_record(200, 100, 0x2, "final.txt");
+        buffer.truncate(buffer.len() - 4);
+        assert!(parse_usn_records(&buffer).is_empty());
+    }
+
+    #[test]
+    fn test_record_and_replay_with_rotation() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("usn.jsonl");
+        let changes: Vec<UsnChange> = (0..5)
+            .map(|i| change(&format!("C:\\f{}.txt", i), ChangeType::Created))
+            .chain([change(
+                "C:\\new.txt",
+                ChangeType::Renamed {
+                    old_name: "old.txt".to_string(),
+                    old_path: "C:\\old.txt".to_string(),
+                },
+            )])
+            .collect();
+
+        // Every line is over 100 bytes, so each file holds one change
+        let written = write_changes(changes.clone(), &log, Some(100)).unwrap();
+        assert_eq!(written, 6);
+        assert!(rotated_path(&log, 5).exists());
+
+        // A later session continues the numbering
+        write_changes(
+            [change("C:\\later.txt", ChangeType::Deleted)],
+            &log,
+            Some(100),
+        )
+        .unwrap();
+        assert!(rotated_path(&log, 6).exists());
+
+        let replayed: Vec<UsnChange> = UsnJournal::replay(&log).unwrap().collect();
+        assert_eq!(replayed.len(), 7);
+        assert_eq!(&replayed[..6], &changes[..]);
+        assert_eq!(replayed[6].change_type, ChangeType::Deleted);
+
+        assert!(UsnJournal::replay(dir.path().join("missing.jsonl")).is_err());
+    }
+
+    #[test]
+    fn test_replay_skips_bad_lines() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("usn.jsonl");
+        let changes = [
+            change("C:\\a.txt", ChangeType::Created),
+            change("C:\\b.txt", ChangeType::Deleted),
+        ];
+        write_changes(changes, &log, Some(1)).unwrap();
+
+        // Invalid UTF-8 and a truncated line in the rotated file
+        let mut rotated = OpenOptions::new()
+            .append(true)
+            .open(rotated_path(&log, 1))
+            .unwrap();
+        rotated
+            .write_all(b"\xff\xfe{not json\n{\"path\":\n")
+            .unwrap();
+        drop(rotated);
+
+        let replayed: Vec<UsnChange> = UsnJournal::replay(&log).unwrap().collect();
+        let paths: Vec<&str> = replayed.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["C:\\a.txt", "C:\\b.txt"]);
+    }
+}
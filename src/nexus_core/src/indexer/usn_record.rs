@@ -0,0 +1,184 @@
+//! USN record parsing
+//!
+//! `FSCTL_ENUM_USN_DATA` and `FSCTL_READ_USN_JOURNAL` return packed USN
+//! records after a leading 8-byte USN. NTFS writes `USN_RECORD_V2`, with
+//! 64-bit file references; ReFS, and NTFS when asked for them, write
+//! `USN_RECORD_V3`, whose references are 128-bit `FILE_ID_128`s. Both are
+//! read into [`UsnRecord`], with references widened to `u128`.
+
+/// Record-number part of an NTFS file reference; the top 16 bits are a
+/// sequence number that changes when the record is reused
+const FILE_REF_MASK: u128 = 0x0000_FFFF_FFFF_FFFF;
+
+/// Fixed part of a `USN_RECORD_V2`, up to the file name
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) const USN_RECORD_V2_SIZE: usize = 60;
+
+/// Fixed part of a `USN_RECORD_V3`, up to the file name
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) const USN_RECORD_V3_SIZE: usize = 76;
+
+/// A USN record of either version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct UsnRecord {
+    /// File reference as recorded, sequence number included
+    pub file_ref: u128,
+    pub parent_ref: u128,
+    pub reason: u32,
+    pub attributes: u32,
+    pub name: String,
+}
+
+impl UsnRecord {
+    pub fn is_directory(&self) -> bool {
+        self.attributes & 0x10 != 0 // FILE_ATTRIBUTE_DIRECTORY
+    }
+}
+
+/// Key a file reference for path reconstruction
+///
+/// NTFS references, 64-bit in V2 records and zero-extended in V3, drop
+/// their sequence number so a parent matches whatever sequence its
+/// children recorded. Wider ReFS identifiers are kept whole.
+pub(super) fn ref_key(file_ref: u128) -> u128 {
+    if file_ref >> 64 == 0 {
+        file_ref & FILE_REF_MASK
+    } else {
+        file_ref
+    }
+}
+
+/// Parse the records in a buffer, after its leading USN
+///
+/// Stops at the first record that is truncated or has an impossible
+/// length. Records of other versions, or whose name lies outside the
+/// record, are skipped.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) fn parse_usn_records(buffer: &[u8]) -> Vec<UsnRecord> {
+    let mut records = Vec::new();
+    let mut offset = 0usize;
+    while offset + 4 <= buffer.len() {
+        let length = u32_at(buffer, offset) as usize;
+        if length < 8 || offset + length > buffer.len() {
+            break;
+        }
+        let record = &buffer[offset..offset + length];
+        offset += length;
+        if let Some(record) = parse_record(record) {
+            records.push(record);
+        }
+    }
+    records
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_record(record: &[u8]) -> Option<UsnRecord> {
+    // (fixed size, file reference width, offset of Reason)
+    let (fixed, ref_width, reason_at) = match u16_at(record, 4) {
+        2 => (USN_RECORD_V2_SIZE, 8, 40),
+        3 => (USN_RECORD_V3_SIZE, 16, 56),
+        _ => return None,
+    };
+    if record.len() < fixed {
+        return None;
+    }
+
+    let reference = |at: usize| {
+        let mut bytes = [0u8; 16];
+        bytes[..ref_width].copy_from_slice(&record[at..at + ref_width]);
+        u128::from_le_bytes(bytes)
+    };
+    // Reason, SourceInfo, SecurityId, FileAttributes, FileNameLength,
+    // FileNameOffset follow the USN and timestamp in both versions
+    let name_length = u16_at(record, reason_at + 16) as usize;
+    let name_offset = u16_at(record, reason_at + 18) as usize;
+    if name_offset < fixed {
+        return None;
+    }
+    let name: Vec<u16> = record
+        .get(name_offset..name_offset + name_length)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+
+    Some(UsnRecord {
+        file_ref: reference(8),
+        parent_ref: reference(8 + ref_width),
+        reason: u32_at(record, reason_at),
+        attributes: u32_at(record, reason_at + 12),
+        name: String::from_utf16_lossy(&name),
+    })
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A record as the volume returns it; `version` 2 keeps the low 64
+    /// bits of each reference
+    fn usn_record(
+        version: u16,
+        file_ref: u128,
+        parent_ref: u128,
+        reason: u32,
+        name: &str,
+    ) -> Vec<u8> {
+        let (fixed, ref_width, reason_at) = match version {
+            2 => (USN_RECORD_V2_SIZE, 8, 40),
+            _ => (USN_RECORD_V3_SIZE, 16, 56),
+        };
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let length = (fixed + name.len()).next_multiple_of(8);
+        let mut record = vec![0u8; length];
+        record[0..4].copy_from_slice(&(length as u32).to_le_bytes());
+        record[4..6].copy_from_slice(&version.to_le_bytes());
+        record[8..8 + ref_width].copy_from_slice(&file_ref.to_le_bytes()[..ref_width]);
+        record[8 + ref_width..8 + 2 * ref_width]
+            .copy_from_slice(&parent_ref.to_le_bytes()[..ref_width]);
+        record[reason_at..reason_at + 4].copy_from_slice(&reason.to_le_bytes());
+        record[reason_at + 16..reason_at + 18].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        record[reason_at + 18..reason_at + 20].copy_from_slice(&(fixed as u16).to_le_bytes());
+        record[fixed..fixed + name.len()].copy_from_slice(&name);
+        record
+    }
+
+    #[test]
+    fn test_parse_v2_and_v3_records() {
+        let wide_ref: u128 = 0x0123_4567_89AB_CDEF_0011_2233_4455_6677;
+        let wide_parent: u128 = 0xFEDC_BA98_7654_3210_0000_0000_0000_0005;
+        let narrow_ref: u128 = 0x0007_0000_0000_0064;
+
+        let buffer = [
+            usn_record(3, wide_ref, wide_parent, 0x100, "ReFS файл.txt"),
+            usn_record(2, narrow_ref, 5, 0x2000, "ntfs.txt"),
+            usn_record(4, 1, 5, 0, "skipped"),
+        ]
+        .concat();
+        let records = parse_usn_records(&buffer);
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].file_ref, wide_ref);
+        assert_eq!(records[0].parent_ref, wide_parent);
+        assert_eq!(records[0].reason, 0x100);
+        assert_eq!(records[0].name, "ReFS файл.txt");
+        assert_eq!(ref_key(records[0].file_ref), wide_ref);
+
+        assert_eq!(records[1].file_ref, narrow_ref);
+        assert_eq!(records[1].name, "ntfs.txt");
+        assert_eq!(ref_key(records[1].file_ref), 0x64);
+
+        // A truncated record ends the buffer
+        assert!(parse_usn_records(&buffer[..buffer.len() - 4]).len() == 2);
+        assert!(parse_usn_records(&buffer[..20]).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Volume identification
+//!
+//! Drive letters are unstable: the same volume can be C: today and D:
+//! tomorrow. Volume GUID paths (`\\?\Volume{guid}\`) are not, so a
+//! persistent index keys on those instead. The same goes for DFS namespace
+//! paths, which can be resolved to the share actually serving the file.
+
+/// Resolve the volume GUID path (`\\?\Volume{guid}\`) for a drive letter
+///
+/// Returns `None` if the letter is not mounted or on non-Windows platforms.
+#[cfg(windows)]
+pub fn volume_guid(drive: char) -> Option<String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeNameForVolumeMountPointW;
+
+    let mount_point: Vec<u16> = format!("{}:\\", drive.to_ascii_uppercase())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // A volume GUID path is 49 characters plus the terminator
+    let mut buffer = [0u16; 64];
+    unsafe { GetVolumeNameForVolumeMountPointW(PCWSTR(mount_point.as_ptr()), &mut buffer) }.ok()?;
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+#[cfg(not(windows))]
+pub fn volume_guid(_drive: char) -> Option<String> {
+    None
+}
+
+/// Serial number of the file system on a drive letter
+///
+/// Assigned when the volume is formatted, so a changed serial means the
+/// files seen before are gone even if the letter and GUID are the same.
+/// Returns `None` if the letter is not mounted or on non-Windows platforms.
+#[cfg(windows)]
+pub fn volume_serial(drive: char) -> Option<u32> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let root: Vec<u16> = format!("{}:\\", drive.to_ascii_uppercase())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut serial = 0u32;
+    unsafe {
+        GetVolumeInformationW(
+            PCWSTR(root.as_ptr()),
+            None,
+            Some(&mut serial),
+            None,
+            None,
+            None,
+        )
+    }
+    .ok()?;
+    Some(serial)
+}
+
+#[cfg(not(windows))]
+pub fn volume_serial(_drive: char) -> Option<u32> {
+    None
+}
+
+/// Find the drive letter a volume is currently mounted at
+///
+/// Used to map a stored `volume_id` back to a usable path after drive
+/// letters have been reassigned.
+pub fn drive_for_volume(volume_id: &str) -> Option<char> {
+    ('A'..='Z').find(|&drive| {
+        volume_guid(drive)
+            .map(|guid| guid.eq_ignore_ascii_case(volume_id))
+            .unwrap_or(false)
+    })
+}
+
+/// Root of the volume a path is on, as stored in `FileEntry::volume`
+///
+/// `C:` for drive paths, including `\\?\C:\...` and `\\.\C:`;
+/// `\\server\share` for UNC paths, including `\\?\UNC\server\share\...`;
+/// `\\?\Volume{guid}` for volume GUID paths. `None` for relative paths and
+/// anything else without a recognizable root.
+pub fn volume_root(path: &str) -> Option<String> {
+    let extended = path
+        .get(..4)
+        .filter(|p| *p == r"\\?\" || *p == r"\\.\")
+        .map(|_| &path[4..]);
+
+    if let Some(rest) = extended {
+        if let Some(letter) = leading_drive_letter(rest) {
+            return Some(format!("{}:", letter));
+        }
+        if rest
+            .get(..4)
+            .is_some_and(|p| p.eq_ignore_ascii_case(r"UNC\"))
+        {
+            return unc_root(&rest[4..]);
+        }
+        if rest
+            .get(..7)
+            .is_some_and(|p| p.eq_ignore_ascii_case("Volume{"))
+        {
+            let end = rest.find('}')?;
+            return Some(format!(r"\\?\{}", &rest[..=end]));
+        }
+        return None;
+    }
+
+    if let Some(rest) = path.strip_prefix(r"\\") {
+        return unc_root(rest);
+    }
+    leading_drive_letter(path).map(|letter| format!("{}:", letter))
+}
+
+/// Drive letter of a path, uppercase, seeing through `\\?\` and `\\.\`
+/// prefixes; `None` for UNC and volume GUID paths
+pub fn drive_letter(path: &str) -> Option<char> {
+    let path = path
+        .strip_prefix(r"\\?\")
+        .or_else(|| path.strip_prefix(r"\\.\"))
+        .unwrap_or(path);
+    leading_drive_letter(path)
+}
+
+fn leading_drive_letter(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => {
+            Some(letter.to_ascii_uppercase())
+        }
+        _ => None,
+    }
+}
+
+/// `\\server\share` from `server\share\...`
+fn unc_root(rest: &str) -> Option<String> {
+    let mut parts = rest.split(['\\', '/']).filter(|p| !p.is_empty());
+    let server = parts.next()?;
+    let share = parts.next()?;
+    Some(format!(r"\\{}\{}", server, share))
+}
+
+/// Whether a path is on a network share (`\\server\share` or `\\?\UNC\...`)
+pub(crate) fn is_network_path(path: &str) -> bool {
+    let upper = path.get(..8).map(|p| p.to_ascii_uppercase());
+    upper.as_deref() == Some(r"\\?\UNC\")
+        || (path.starts_with(r"\\") && !path.starts_with(r"\\?\") && !path.starts_with(r"\\.\"))
+}
+
+/// Resolve a network path to the physical path serving it
+///
+/// For paths reached through a DFS namespace this returns the target share
+/// (`\\fileserver2\projects\a.txt` for `\\corp\dfs\projects\a.txt`), so two
+/// namespace paths to the same file can be recognised. Returns `None` for
+/// local paths, when the path is already canonical, on failure, and unless
+/// built with the `dfs` feature on Windows, since it costs a file open and a
+/// network round trip per call.
+#[cfg(all(windows, feature = "dfs"))]
+pub fn resolve_network_path(path: &str) -> Option<String> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFinalPathNameByHandleW, FILE_FLAG_BACKUP_SEMANTICS, FILE_NAME_NORMALIZED,
+        VOLUME_NAME_DOS,
+    };
+
+    if !is_network_path(path) {
+        return None;
+    }
+
+    // No data access; backup semantics so directories can be opened too
+    let file = std::fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+        .open(path)
+        .ok()?;
+
+    let mut buffer = vec![0u16; 1024];
+    loop {
+        let len = unsafe {
+            GetFinalPathNameByHandleW(
+                HANDLE(file.as_raw_handle()),
+                &mut buffer,
+                FILE_NAME_NORMALIZED | VOLUME_NAME_DOS,
+            )
+        } as usize;
+        if len == 0 {
+            return None;
+        }
+        // Too small: `len` is the required size including the terminator
+        if len > buffer.len() {
+            buffer.resize(len, 0);
+            continue;
+        }
+
+        let resolved = String::from_utf16_lossy(&buffer[..len]);
+        let resolved = match resolved.strip_prefix(r"\\?\UNC\") {
+            Some(rest) => format!(r"\\{}", rest),
+            None => resolved,
+        };
+        return (!resolved.eq_ignore_ascii_case(path)).then_some(resolved);
+    }
+}
+
+#[cfg(not(all(windows, feature = "dfs")))]
+pub fn resolve_network_path(_path: &str) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_network_path() {
+        assert!(is_network_path(r"\\server\share\file.txt"));
+        assert!(is_network_path(r"\\?\UNC\server\share\file.txt"));
+        assert!(is_network_path(r"\\?\unc\server\share"));
+        assert!(!is_network_path(r"\\?\C:\file.txt"));
+        assert!(!is_network_path(r"\\.\PhysicalDrive0"));
+        assert!(!is_network_path(r"C:\file.txt"));
+    }
+
+    #[test]
+    fn test_volume_root() {
+        let root = |p: &str| volume_root(p);
+        assert_eq!(root(r"c:\file.txt").as_deref(), Some("C:"));
+        assert_eq!(root(r"\\?\D:\deep\file.txt").as_deref(), Some("D:"));
+        assert_eq!(root(r"\\.\E:").as_deref(), Some("E:"));
+        assert_eq!(
+            root(r"\\server\share\dir\file.txt").as_deref(),
+            Some(r"\\server\share")
+        );
+        assert_eq!(
+            root(r"\\?\UNC\server\share\file.txt").as_deref(),
+            Some(r"\\server\share")
+        );
+        assert_eq!(
+            root(r"\\?\Volume{1b2c3d4e-0000-0000-0000-100000000000}\file.txt").as_deref(),
+            Some(r"\\?\Volume{1b2c3d4e-0000-0000-0000-100000000000}")
+        );
+        assert_eq!(root(r"\\server"), None);
+        assert_eq!(root("relative/file.txt"), None);
+
+        assert_eq!(drive_letter(r"\\?\c:\file.txt"), Some('C'));
+        assert_eq!(drive_letter(r"\\server\share\file.txt"), None);
+    }
+}
@@ -35,13 +35,14 @@ pub enum NexusError {
 pub type Result<T> = std::result::Result<T, NexusError>;
 
 /// File entry with metadata
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct FileEntry {
     /// Full path to the file
     pub path: String,
     /// File name only
     pub name: String,
-    /// File extension (lowercase, without dot)
+    /// File extension (without dot), in its original case; compare with
+    /// [`FileEntry::has_extension`]
     pub extension: Option<String>,
     /// File size in bytes
     pub size: u64,
@@ -57,12 +58,33 @@ pub struct FileEntry {
     pub is_hidden: bool,
     /// Is system file
     pub is_system: bool,
+    /// Is an offline / cloud placeholder (OneDrive, Dropbox) whose content
+    /// is not available locally. Reading it would trigger hydration.
+    #[serde(default)]
+    pub is_offline: bool,
     /// Content hash (optional, for deduplication)
     pub content_hash: Option<String>,
     /// Parent directory
     pub parent: String,
-    /// Drive letter
+    /// Drive letter; `\` for paths without one (UNC, volume GUID)
     pub drive: char,
+    /// Root of the volume: `C:`, `\\server\share` or `\\?\Volume{guid}`;
+    /// empty when the path has no recognizable root
+    #[serde(default)]
+    pub volume: String,
+    /// Volume GUID path (`\\?\Volume{guid}\`), stable across drive-letter
+    /// reassignments. `None` when unresolved or not on Windows.
+    pub volume_id: Option<String>,
+    /// Physical path behind a DFS namespace `path`, when
+    /// `IndexConfig::resolve_network_paths` is on and it differs
+    pub resolved_path: Option<String>,
+    /// A junction or symlink that resolves to one of its own ancestors;
+    /// following it would loop, so nothing below it is counted
+    #[serde(default)]
+    pub is_cycle: bool,
+    /// User-assigned tags; see `SearchEngine::set_tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl FileEntry {
@@ -85,6 +107,42 @@ impl FileEntry {
     }
 }
 
+/// How extension filters (`IndexConfig::extensions`,
+/// `SearchQuery::file_types`) are compared against an entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionMatch {
+    /// `FileEntry::extension` (the last dot segment) equals the filter
+    #[default]
+    Equals,
+    /// The file name ends with `.` + the filter, case-insensitive, so
+    /// compound extensions like `min.js` or `d.ts` can be expressed
+    Suffix,
+}
+
+impl FileEntry {
+    /// Whether this entry matches an extension filter (with or without a
+    /// leading dot), compared case-insensitively
+    pub fn has_extension(&self, filter: &str, mode: ExtensionMatch) -> bool {
+        let filter = filter.strip_prefix('.').unwrap_or(filter);
+        match mode {
+            ExtensionMatch::Equals => self
+                .extension
+                .as_deref()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(filter)),
+            ExtensionMatch::Suffix => has_suffix_ignore_case(&self.name, filter),
+        }
+    }
+}
+
+/// `name` ends with `.` + `suffix`, ASCII case-insensitive
+pub(crate) fn has_suffix_ignore_case(name: &str, suffix: &str) -> bool {
+    let (name, suffix) = (name.as_bytes(), suffix.as_bytes());
+    name.len() > suffix.len()
+        && name[name.len() - suffix.len() - 1] == b'.'
+        && name[name.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+}
+
 /// Index statistics
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct IndexStats {
@@ -93,9 +151,19 @@ pub struct IndexStats {
     pub total_size: u64,
     pub index_time_ms: u64,
     pub drives_indexed: Vec<char>,
+    /// Indexing stopped early because `IndexConfig::max_entries` was reached
+    #[serde(default)]
+    pub truncated: bool,
+    /// Indexing stopped early because it was cancelled; the entries found
+    /// until then are still returned
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Paths of entries flagged `is_cycle`
+    #[serde(default)]
+    pub cycles: Vec<String>,
 }
 
 /// Re-export commonly used types
-pub use indexer::{FastIndexer, IndexConfig};
+pub use indexer::{FastIndexer, IndexConfig, IndexDirectoryOptions, Preset, ScanMode};
 pub use search::{SearchEngine, SearchQuery, SearchResult};
 pub use watcher::{FileWatcher, WatchEvent};
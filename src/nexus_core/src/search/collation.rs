@@ -0,0 +1,158 @@
+//! Name collation for sorted result lists
+//!
+//! With the `collation` feature and a configured locale, names are compared
+//! with an ICU collator so accented letters sort where users of that locale
+//! expect them. Otherwise a natural comparator is used: case-insensitive,
+//! with digit runs compared by value (`file2` before `file10`), which is
+//! what Explorer does.
+
+use std::cmp::Ordering;
+
+/// Compares file names for sorting
+pub struct NameCollator {
+    #[cfg(feature = "collation")]
+    icu: Option<icu_collator::Collator>,
+}
+
+impl NameCollator {
+    /// Create a collator for a BCP-47 locale such as `"de"` or `"sv-SE"`
+    ///
+    /// Falls back to the natural comparator when `locale` is `None`, cannot
+    /// be parsed, has no collation data, or the `collation` feature is off.
+    pub fn new(locale: Option<&str>) -> Self {
+        #[cfg(feature = "collation")]
+        {
+            Self {
+                icu: locale.and_then(icu_collator_for),
+            }
+        }
+        #[cfg(not(feature = "collation"))]
+        {
+            if let Some(locale) = locale {
+                tracing::debug!(
+                    "Locale '{}' ignored: built without the collation feature",
+                    locale
+                );
+            }
+            Self {}
+        }
+    }
+
+    /// Compare two names
+    ///
+    /// Names equal under the locale rules are ordered naturally, so the
+    /// result is a total order and sorting is deterministic.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        #[cfg(feature = "collation")]
+        if let Some(collator) = &self.icu {
+            return collator.compare(a, b).then_with(|| natural_cmp(a, b));
+        }
+        natural_cmp(a, b)
+    }
+}
+
+impl Default for NameCollator {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[cfg(feature = "collation")]
+fn icu_collator_for(locale: &str) -> Option<icu_collator::Collator> {
+    use icu_collator::{Collator, CollatorOptions, Strength};
+
+    let locale: icu_locid::Locale = match locale.parse() {
+        Ok(locale) => locale,
+        Err(e) => {
+            tracing::warn!("Invalid collation locale '{}': {}", locale, e);
+            return None;
+        }
+    };
+
+    // Secondary strength: accents matter, case does not
+    let mut options = CollatorOptions::new();
+    options.strength = Some(Strength::Secondary);
+
+    Collator::try_new(&(&locale).into(), options)
+        .map_err(|e| tracing::warn!("No collation data for '{}': {}", locale, e))
+        .ok()
+}
+
+/// Natural, case-insensitive comparison with a byte-order tie-break
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let l_run = take_digits(&mut left);
+                let r_run = take_digits(&mut right);
+                let l_num = l_run.trim_start_matches('0');
+                let r_num = r_run.trim_start_matches('0');
+                let ordering = l_num.len().cmp(&r_num.len()).then_with(|| l_num.cmp(r_num));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                let ordering = l.to_lowercase().cmp(r.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut run = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        run.push(c);
+    }
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collator: &NameCollator, names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        names.sort_by(|a, b| collator.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn test_natural_order() {
+        let collator = NameCollator::default();
+        assert_eq!(
+            sorted(
+                &collator,
+                &["file10.txt", "File2.txt", "file1.txt", "file02.txt"]
+            ),
+            vec!["file1.txt", "File2.txt", "file02.txt", "file10.txt"]
+        );
+        assert_eq!(natural_cmp("a", "A"), "a".cmp("A"));
+    }
+
+    #[cfg(feature = "collation")]
+    #[test]
+    fn test_locale_collation() {
+        // Swedish sorts ä after z; German sorts it with a
+        let names = ["zebra", "äpple", "apple"];
+        assert_eq!(
+            sorted(&NameCollator::new(Some("sv")), &names),
+            vec!["apple", "zebra", "äpple"]
+        );
+        assert_eq!(
+            sorted(&NameCollator::new(Some("de")), &names),
+            vec!["apple", "äpple", "zebra"]
+        );
+    }
+}
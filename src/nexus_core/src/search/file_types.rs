@@ -0,0 +1,173 @@
+//! File type categories for type-filtered search
+//!
+//! Lets `SearchQuery::file_types` name a category such as `image` instead
+//! of listing every extension it covers.
+
+use std::collections::BTreeMap;
+
+/// Categories every alias map starts with
+const BUILT_IN: &[(&str, &[&str])] = &[
+    (
+        "image",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "heif", "tif", "tiff", "svg",
+            "ico", "raw",
+        ],
+    ),
+    (
+        "video",
+        &[
+            "mp4", "mkv", "mov", "avi", "wmv", "webm", "m4v", "flv", "mpg", "mpeg",
+        ],
+    ),
+    (
+        "audio",
+        &["mp3", "wav", "flac", "aac", "ogg", "m4a", "wma", "opus"],
+    ),
+    (
+        "document",
+        &[
+            "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "rtf", "txt",
+            "md", "csv",
+        ],
+    ),
+    (
+        "archive",
+        &[
+            "zip", "rar", "7z", "tar", "gz", "bz2", "xz", "tgz", "cab", "iso",
+        ],
+    ),
+    (
+        "code",
+        &[
+            "rs", "c", "h", "cpp", "hpp", "cs", "java", "py", "js", "ts", "jsx", "tsx", "go", "rb",
+            "php", "swift", "kt", "sh", "ps1", "html", "css", "json", "toml", "yaml", "yml", "xml",
+            "sql",
+        ],
+    ),
+];
+
+/// Category names that stand for sets of extensions, e.g. `video` for
+/// `mp4`, `mkv`, `mov`, ...
+///
+/// The default map holds the built-in categories (image, video, audio,
+/// document, archive, code). Names are case-insensitive. Serialized as a
+/// plain `{"name": ["ext", ...]}` object, which replaces the built-ins
+/// when deserialized.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct FileTypeAliases {
+    /// Lowercase name to extensions without a leading dot
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for FileTypeAliases {
+    fn default() -> Self {
+        let mut aliases = Self::empty();
+        for (name, extensions) in BUILT_IN {
+            aliases.insert(name, extensions.iter().copied());
+        }
+        aliases
+    }
+}
+
+impl FileTypeAliases {
+    /// A map without any categories
+    pub fn empty() -> Self {
+        Self {
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Add a category, replacing any existing one of the same name
+    pub fn with_alias<I, S>(mut self, name: &str, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.insert(name, extensions);
+        self
+    }
+
+    /// Add a category, replacing any existing one of the same name
+    ///
+    /// Leading dots on the extensions are dropped.
+    pub fn insert<I, S>(&mut self, name: &str, extensions: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let extensions = extensions
+            .into_iter()
+            .map(|e| {
+                let e = e.as_ref();
+                e.strip_prefix('.').unwrap_or(e).to_lowercase()
+            })
+            .collect();
+        self.aliases.insert(name.to_lowercase(), extensions);
+    }
+
+    /// Extensions of a category, if `name` is one
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.aliases.get(&name.to_lowercase()).map(Vec::as_slice)
+    }
+
+    /// Every category and its extensions, by name
+    pub fn categories(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.aliases
+    }
+
+    /// Replace category names in a file type filter with their extensions
+    ///
+    /// Anything else, including a dotted name such as `.code`, is kept as
+    /// a literal extension. Duplicates are dropped; order is kept.
+    pub fn expand(&self, types: &[String]) -> Vec<String> {
+        let mut expanded: Vec<String> = Vec::new();
+        let mut push = |t: &str| {
+            if !expanded.iter().any(|e| e.eq_ignore_ascii_case(t)) {
+                expanded.push(t.to_string());
+            }
+        };
+        for t in types {
+            match self.get(t).filter(|_| !t.starts_with('.')) {
+                Some(extensions) => extensions.iter().for_each(|e| push(e)),
+                None => push(t),
+            }
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_expand_built_in_category() {
+        let aliases = FileTypeAliases::default();
+        let expanded = aliases.expand(&strings(&["Video", "pdf"]));
+
+        assert!(expanded.contains(&"mkv".to_string()));
+        assert!(expanded.contains(&"mov".to_string()));
+        assert_eq!(expanded.last().map(String::as_str), Some("pdf"));
+        assert_eq!(aliases.expand(&strings(&[".code"])), strings(&[".code"]));
+    }
+
+    #[test]
+    fn test_custom_alias() {
+        let aliases = FileTypeAliases::default()
+            .with_alias("Photos", [".JPG", "heic"])
+            .with_alias("image", ["png"]);
+
+        assert_eq!(aliases.get("photos"), Some(&strings(&["jpg", "heic"])[..]));
+        assert_eq!(
+            aliases.expand(&strings(&["photos", "image", "jpg"])),
+            strings(&["jpg", "heic", "png"])
+        );
+        assert_eq!(aliases.categories().len(), 7);
+    }
+}
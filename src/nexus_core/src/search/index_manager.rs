@@ -0,0 +1,278 @@
+//! A set of named search indexes under one base directory
+//!
+//! Each index lives in its own subdirectory (`<base>/<name>`), so one per
+//! drive or per user can be rebuilt without touching the others.
+
+use super::tantivy_engine::sort_results;
+use super::{EngineOptions, SearchEngine, SearchQuery, SearchResult, SortBy};
+use crate::{NexusError, Result};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Opens named sub-indexes on demand and searches one or all of them
+pub struct IndexManager {
+    base_dir: PathBuf,
+    options: EngineOptions,
+    /// Engines opened so far, by name
+    open: parking_lot::Mutex<BTreeMap<String, Arc<SearchEngine>>>,
+}
+
+impl IndexManager {
+    /// Manage the indexes under `base_dir`, creating it if needed
+    pub fn new<P: AsRef<Path>>(base_dir: P) -> Result<Self> {
+        Self::with_options(base_dir, EngineOptions::default())
+    }
+
+    /// Like [`IndexManager::new`], creating new indexes with `options`
+    pub fn with_options<P: AsRef<Path>>(base_dir: P, options: EngineOptions) -> Result<Self> {
+        let base_dir = base_dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&base_dir)?;
+        Ok(Self {
+            base_dir,
+            options,
+            open: parking_lot::Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// The base directory
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Open the index called `name`, creating it if it doesn't exist
+    ///
+    /// `name` becomes a directory name, e.g. `"drive_c"`; it can't be empty,
+    /// `.` or `..`, or contain a path separator or `:`. The engine is opened once
+    /// and shared by later calls.
+    pub fn index(&self, name: &str) -> Result<Arc<SearchEngine>> {
+        validate_name(name)?;
+        let mut open = self.open.lock();
+        if let Some(engine) = open.get(name) {
+            return Ok(engine.clone());
+        }
+
+        let engine = Arc::new(SearchEngine::with_options(
+            self.base_dir.join(name),
+            self.options.clone(),
+        )?);
+        open.insert(name.to_string(), engine.clone());
+        Ok(engine)
+    }
+
+    /// The open engine for `name`, or a fresh reader-only one
+    ///
+    /// Searching takes no writer lock, so an index another process is
+    /// writing still answers. The reader isn't kept in the cache, which
+    /// would stop [`index`](Self::index) from handing out a writer later.
+    fn reader(&self, name: &str) -> Result<Arc<SearchEngine>> {
+        if let Some(engine) = self.open.lock().get(name) {
+            return Ok(engine.clone());
+        }
+        Ok(Arc::new(SearchEngine::open_reader_only_with(
+            self.base_dir.join(name),
+            self.options.clone(),
+        )?))
+    }
+
+    /// Names of the indexes on disk, sorted
+    pub fn names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.base_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() || !entry.path().join("meta.json").exists() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Close the index called `name` and delete it from disk
+    ///
+    /// The next [`IndexManager::index`] call creates it afresh. Engines
+    /// still held elsewhere keep their files open, which on Windows makes
+    /// the delete fail.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        self.open.lock().remove(name);
+        let path = self.base_dir.join(name);
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Search the index called `name`
+    pub fn search(&self, name: &str, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        self.index(name)?.search(query)
+    }
+
+    /// Search every index on disk and merge the results
+    ///
+    /// Results are ordered by score across all indexes before `limit` and
+    /// `sort` apply, as for a single index. Scores are used as they are:
+    /// they are already on one scale, though term statistics are per index,
+    /// so a term that is rare on one drive ranks a little higher there.
+    /// Rescaling each index to its best match would be worse, promoting the
+    /// weak matches of an index with no good ones.
+    ///
+    /// Indexes not already open here are opened reader-only for this
+    /// search, so one being written by another process is still searched.
+    /// An index that can't be opened (e.g. corrupt) is skipped with a
+    /// warning; it's an error only when none of them opens. An invalid
+    /// query fails the whole search.
+    pub fn search_all(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let names = self.names()?;
+        let mut engines = Vec::with_capacity(names.len());
+        let mut last_error = None;
+        for name in &names {
+            match self.reader(name) {
+                Ok(engine) => engines.push(engine),
+                Err(e) => {
+                    warn!("Skipping index '{}': {}", name, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let (true, Some(e)) = (engines.is_empty(), last_error) {
+            return Err(e);
+        }
+
+        // Each index keeps its own top `limit` by relevance; sort at the end
+        let by_relevance = SearchQuery {
+            sort: SortBy::Relevance,
+            ..query.clone()
+        };
+        let per_index = engines
+            .par_iter()
+            .map(|engine| engine.search(&by_relevance))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut results: Vec<SearchResult> = per_index.into_iter().flatten().collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(limit) = query.limit {
+            results.truncate(limit);
+        }
+        sort_results(&mut results, query);
+        Ok(results)
+    }
+}
+
+/// A name must be a single plain path component
+///
+/// `:` is refused too: on Windows it would make `C:x` drive-relative or
+/// `x:stream` an alternate data stream.
+fn validate_name(name: &str) -> Result<()> {
+    let mut components = Path::new(name).components();
+    let single = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(part)), None) if part == name
+    );
+    if !single || name.contains(['/', '\\', ':']) {
+        return Err(NexusError::InvalidPath(format!(
+            "Invalid index name: {:?}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileEntry;
+    use tempfile::tempdir;
+
+    fn entry(drive: char, name: &str) -> FileEntry {
+        FileEntry {
+            path: format!("{}:\\{}", drive, name),
+            name: name.to_string(),
+            parent: format!("{}:", drive),
+            drive,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_all() {
+        let dir = tempdir().unwrap();
+        let manager = IndexManager::new(dir.path()).unwrap();
+
+        for (name, drive, files) in [
+            ("drive_c", 'C', ["report.txt", "notes.txt"]),
+            ("drive_d", 'D', ["report.doc", "photo.jpg"]),
+        ] {
+            let engine = manager.index(name).unwrap();
+            let entries: Vec<FileEntry> = files.iter().map(|f| entry(drive, f)).collect();
+            engine.index_entries(&entries).unwrap();
+            engine.reader.reload().unwrap();
+        }
+        assert_eq!(manager.names().unwrap(), ["drive_c", "drive_d"]);
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            sort: SortBy::Name,
+            ..Default::default()
+        };
+        let names = |results: Vec<SearchResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.entry.name).collect()
+        };
+        assert_eq!(
+            names(manager.search_all(&query).unwrap()),
+            ["report.doc", "report.txt"]
+        );
+        assert_eq!(
+            names(manager.search("drive_d", &query).unwrap()),
+            ["report.doc"]
+        );
+
+        let merged = manager
+            .search_all(&SearchQuery {
+                query: "report".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(merged.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // Another manager searches while this one holds the writers,
+        // without caching its readers
+        let other = IndexManager::new(dir.path()).unwrap();
+        assert_eq!(other.search_all(&query).unwrap().len(), 2);
+        assert!(other.open.lock().is_empty());
+
+        // A broken index is skipped rather than failing the search
+        std::fs::create_dir(dir.path().join("broken")).unwrap();
+        std::fs::write(dir.path().join("broken/meta.json"), b"not json").unwrap();
+        assert_eq!(manager.search_all(&query).unwrap().len(), 2);
+        std::fs::remove_dir_all(dir.path().join("broken")).unwrap();
+
+        manager.remove("drive_d").unwrap();
+        assert_eq!(manager.names().unwrap(), ["drive_c"]);
+        assert!(matches!(
+            manager.index("../escape"),
+            Err(NexusError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_name() {
+        for name in ["drive_c", "my index", "a.b"] {
+            assert!(validate_name(name).is_ok(), "{:?}", name);
+        }
+        for name in [
+            "", ".", "..", "a/b", "a\\b", "/abs", "C:", "C:x", "x:stream", "a/", "./a",
+        ] {
+            assert!(
+                matches!(validate_name(name), Err(NexusError::InvalidPath(_))),
+                "{:?}",
+                name
+            );
+        }
+    }
+}
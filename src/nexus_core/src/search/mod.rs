@@ -2,6 +2,72 @@
 //!
 //! Provides ultra-fast full-text search using the Tantivy search engine.
 
+mod collation;
+mod export;
+mod file_types;
+mod index_manager;
+mod multi_term;
+mod recent;
+mod scope;
+mod tags;
 mod tantivy_engine;
 
-pub use tantivy_engine::{SearchEngine, SearchQuery, SearchResult};
+pub use collation::{natural_cmp, NameCollator};
+pub(crate) use export::export_entries_csv;
+pub use export::{export_csv, export_csv_columns, CsvColumn};
+pub use file_types::FileTypeAliases;
+pub use index_manager::IndexManager;
+pub use multi_term::{parse_query_terms, parse_terms, MultiTermMatcher, QueryTerms};
+pub use recent::{most_recent, RecentBy};
+pub use scope::PathScope;
+pub use tags::TagStore;
+pub use tantivy_engine::{
+    EngineOptions, FieldInfo, SearchEngine, SearchQuery, SearchResult, SearchType, SortBy,
+    TimeBucket,
+};
+
+/// Total size in bytes of the files in a result set
+///
+/// Directories are skipped; their indexed size is 0 and does not reflect
+/// the size of their contents.
+pub fn sum_size(results: &[SearchResult]) -> u64 {
+    results
+        .iter()
+        .filter(|r| !r.entry.is_dir)
+        .map(|r| r.entry.size)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileEntry;
+
+    fn result(name: &str, size: u64, is_dir: bool) -> SearchResult {
+        SearchResult {
+            entry: FileEntry {
+                path: format!("C:\\{}", name),
+                name: name.to_string(),
+                size,
+                is_dir,
+                parent: "C:".to_string(),
+                drive: 'C',
+                ..Default::default()
+            },
+            score: 1.0,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_sum_size() {
+        let results = vec![
+            result("a.tmp", 100, false),
+            result("b.tmp", 250, false),
+            result("dir", 4096, true),
+        ];
+
+        assert_eq!(sum_size(&results), 350);
+        assert_eq!(sum_size(&[]), 0);
+    }
+}
@@ -0,0 +1,289 @@
+//! Multi-term quick search over in-memory entries
+//!
+//! Splits a search-box query into terms and requires every term to be
+//! present, all checked in a single Aho-Corasick pass per field.
+//!
+//! Query syntax:
+//!
+//! - `report 2023` — both terms must appear
+//! - `"q3 final"` — a phrase, matched as one term
+//! - `report -draft` — `draft` must not appear; `-"old copy"` excludes a phrase
+//! - `"-draft"` — a literal leading dash; a lone `-` is also literal
+
+use crate::FileEntry;
+use aho_corasick::AhoCorasick;
+
+/// Terms beyond this are ignored; matches are tracked in a `u64` bitmask
+const MAX_TERMS: usize = 64;
+
+/// Terms of a search-box query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryTerms {
+    /// Terms that must all be present
+    pub include: Vec<String>,
+    /// Terms that must all be absent (written `-term`)
+    pub exclude: Vec<String>,
+}
+
+/// Split a query into lowercase terms
+///
+/// Terms are separated by whitespace; `"exact phrase"` is kept as one term.
+/// An unterminated quote runs to the end of the query. A leading `-` is
+/// part of the term; see [`parse_query_terms`] for exclusions.
+pub fn parse_terms(query: &str) -> Vec<String> {
+    split_terms(query, false).include
+}
+
+/// Split a query into lowercase include and exclude terms
+///
+/// As [`parse_terms`], but a leading `-` outside quotes marks an exclusion.
+pub fn parse_query_terms(query: &str) -> QueryTerms {
+    split_terms(query, true)
+}
+
+fn split_terms(query: &str, exclusions: bool) -> QueryTerms {
+    let mut terms = QueryTerms::default();
+    let mut rest = query.trim_start();
+
+    while !rest.is_empty() {
+        let negated = exclusions
+            && rest.len() > 1
+            && rest.starts_with('-')
+            && !rest[1..].starts_with(char::is_whitespace);
+        if negated {
+            rest = &rest[1..];
+        }
+
+        let (term, remaining) = if let Some(quoted) = rest.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+
+        let term = term.to_lowercase();
+        let list = if negated {
+            &mut terms.exclude
+        } else {
+            &mut terms.include
+        };
+        if !term.is_empty() && !list.contains(&term) {
+            list.push(term);
+        }
+        rest = remaining.trim_start();
+    }
+
+    terms.include.truncate(MAX_TERMS);
+    terms.exclude.truncate(MAX_TERMS);
+    terms
+}
+
+/// Matches entries containing every term and no excluded term of a query
+/// (case-insensitive)
+///
+/// Terms are matched against the file name, except terms containing a path
+/// separator, which are matched against the full path (`src\ main` finds
+/// `main.rs` files under any `src` directory).
+pub struct MultiTermMatcher {
+    name_terms: Option<TermSet>,
+    path_terms: Option<TermSet>,
+    name_excludes: Option<TermSet>,
+    path_excludes: Option<TermSet>,
+}
+
+struct TermSet {
+    automaton: AhoCorasick,
+    /// Bit `i` is set for each pattern `i`
+    all: u64,
+    /// Patterns are ASCII, so ASCII case folding in the automaton is exact
+    /// and the haystack need not be lowercased
+    ascii: bool,
+}
+
+impl TermSet {
+    fn new(terms: &[String]) -> Option<Self> {
+        if terms.is_empty() {
+            return None;
+        }
+        let ascii = terms.iter().all(|t| t.is_ascii());
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(ascii)
+            .build(terms)
+            .ok()?;
+        let all = if terms.len() == MAX_TERMS {
+            u64::MAX
+        } else {
+            (1u64 << terms.len()) - 1
+        };
+        Some(Self {
+            automaton,
+            all,
+            ascii,
+        })
+    }
+
+    fn lowered<'a>(&self, haystack: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.ascii {
+            haystack.into()
+        } else {
+            haystack.to_lowercase().into()
+        }
+    }
+
+    fn all_present(&self, haystack: &str) -> bool {
+        let haystack = self.lowered(haystack);
+        let mut seen = 0u64;
+        for m in self.automaton.find_overlapping_iter(haystack.as_ref()) {
+            seen |= 1 << m.pattern().as_usize();
+            if seen == self.all {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn any_present(&self, haystack: &str) -> bool {
+        self.automaton.is_match(self.lowered(haystack).as_ref())
+    }
+}
+
+/// Split terms into (name terms, path terms)
+fn by_field(terms: Vec<String>) -> (Option<TermSet>, Option<TermSet>) {
+    let (path_terms, name_terms): (Vec<String>, Vec<String>) =
+        terms.into_iter().partition(|t| t.contains(['\\', '/']));
+    (TermSet::new(&name_terms), TermSet::new(&path_terms))
+}
+
+impl MultiTermMatcher {
+    /// Build a matcher for a search-box query; an empty query matches all
+    pub fn new(query: &str) -> Self {
+        let terms = parse_query_terms(query);
+        let (name_terms, path_terms) = by_field(terms.include);
+        let (name_excludes, path_excludes) = by_field(terms.exclude);
+
+        Self {
+            name_terms,
+            path_terms,
+            name_excludes,
+            path_excludes,
+        }
+    }
+
+    /// Whether `entry` contains every term and no excluded term
+    pub fn matches(&self, entry: &FileEntry) -> bool {
+        if let Some(excludes) = &self.name_excludes {
+            if excludes.any_present(&entry.name) {
+                return false;
+            }
+        }
+        if let Some(excludes) = &self.path_excludes {
+            if excludes.any_present(&entry.path) {
+                return false;
+            }
+        }
+        if let Some(terms) = &self.name_terms {
+            if !terms.all_present(&entry.name) {
+                return false;
+            }
+        }
+        if let Some(terms) = &self.path_terms {
+            if !terms.all_present(&entry.path) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            name: path.rsplit('\\').next().unwrap().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(parse_terms("  Annual  REPORT "), vec!["annual", "report"]);
+        assert_eq!(
+            parse_terms(r#"budget "q3 final" xlsx"#),
+            vec!["budget", "q3 final", "xlsx"]
+        );
+        assert_eq!(
+            parse_terms(r#"a "unterminated phrase"#),
+            vec!["a", "unterminated phrase"]
+        );
+        assert_eq!(parse_terms("dup dup"), vec!["dup"]);
+        assert!(parse_terms("   ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_exclusions() {
+        assert_eq!(parse_terms("report -draft"), vec!["report", "-draft"]);
+        assert_eq!(
+            parse_query_terms(r#"report -Draft -"old copy" x-ray"#),
+            QueryTerms {
+                include: vec!["report".to_string(), "x-ray".to_string()],
+                exclude: vec!["draft".to_string(), "old copy".to_string()],
+            }
+        );
+        // Quoted or lone dashes are literal
+        assert_eq!(
+            parse_query_terms(r#""-draft" - a"#).include,
+            vec!["-draft", "-", "a"]
+        );
+        assert_eq!(parse_query_terms("   "), QueryTerms::default());
+    }
+
+    #[test]
+    fn test_excluded_terms() {
+        let matcher = MultiTermMatcher::new("report -draft -old\\");
+        assert!(matcher.matches(&entry("C:\\docs\\report_final.pdf")));
+        assert!(!matcher.matches(&entry("C:\\docs\\report_DRAFT.pdf")));
+        assert!(!matcher.matches(&entry("C:\\old\\report_final.pdf")));
+
+        // Exclusions alone filter everything else through
+        let matcher = MultiTermMatcher::new("-tmp");
+        assert!(matcher.matches(&entry("C:\\a.txt")));
+        assert!(!matcher.matches(&entry("C:\\a.tmp")));
+
+        let matcher = MultiTermMatcher::new(r#""-v2""#);
+        assert!(matcher.matches(&entry("C:\\report-v2.pdf")));
+        assert!(!matcher.matches(&entry("C:\\report_v2.pdf")));
+    }
+
+    #[test]
+    fn test_all_terms_required() {
+        let matcher = MultiTermMatcher::new("report 2023");
+        assert!(matcher.matches(&entry("C:\\docs\\Annual_Report_2023.pdf")));
+        assert!(!matcher.matches(&entry("C:\\docs\\Annual_Report_2022.pdf")));
+
+        // Overlapping terms are each counted
+        let matcher = MultiTermMatcher::new("abc bcd");
+        assert!(matcher.matches(&entry("C:\\abcd.txt")));
+
+        let matcher = MultiTermMatcher::new(r#""q3 final""#);
+        assert!(matcher.matches(&entry("C:\\Budget Q3 Final.xlsx")));
+        assert!(!matcher.matches(&entry("C:\\Budget Q3 draft final.xlsx")));
+
+        assert!(MultiTermMatcher::new("").matches(&entry("C:\\anything")));
+    }
+
+    #[test]
+    fn test_path_terms_and_unicode() {
+        let matcher = MultiTermMatcher::new("src\\ main");
+        assert!(matcher.matches(&entry("C:\\proj\\src\\main.rs")));
+        assert!(!matcher.matches(&entry("C:\\proj\\lib\\main.rs")));
+
+        let matcher = MultiTermMatcher::new("ÄRGER");
+        assert!(matcher.matches(&entry("C:\\ärger.txt")));
+    }
+}
@@ -0,0 +1,128 @@
+//! Most-recent-files selection
+//!
+//! A plain top-N by timestamp over the whole index, independent of any
+//! query, for "what did I just download" style views.
+
+use crate::FileEntry;
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Timestamp that orders entries in [`most_recent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentBy {
+    Created,
+    Modified,
+}
+
+impl RecentBy {
+    fn timestamp(self, entry: &FileEntry) -> Option<DateTime<Utc>> {
+        match self {
+            RecentBy::Created => entry.created,
+            RecentBy::Modified => entry.modified,
+        }
+    }
+}
+
+/// The `count` newest files by `by`, newest first
+///
+/// Keeps a heap of at most `count` entries, so memory stays bounded however
+/// large the index is. Directories and entries without the timestamp are
+/// skipped; ties keep index order.
+pub fn most_recent<'a>(
+    entries: impl IntoIterator<Item = &'a FileEntry>,
+    count: usize,
+    by: RecentBy,
+) -> Vec<&'a FileEntry> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    // Min-heap on (time, newer position first), so the root is the entry
+    // to evict
+    let mut heap = BinaryHeap::with_capacity(count + 1);
+    for (i, entry) in entries.into_iter().enumerate() {
+        if entry.is_dir {
+            continue;
+        }
+        let Some(time) = by.timestamp(entry) else {
+            continue;
+        };
+        heap.push(Reverse((time, Reverse(i), EntryRef(entry))));
+        if heap.len() > count {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((_, _, EntryRef(entry)))| entry)
+        .collect()
+}
+
+/// Carries the entry through the heap without taking part in the ordering
+struct EntryRef<'a>(&'a FileEntry);
+
+impl PartialEq for EntryRef<'_> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for EntryRef<'_> {}
+
+impl PartialOrd for EntryRef<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EntryRef<'_> {
+    fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(name: &str, created: Option<i64>, modified: Option<i64>) -> FileEntry {
+        let at = |secs: i64| Utc.timestamp_opt(secs, 0).unwrap();
+        FileEntry {
+            path: format!("C:\\{}", name),
+            name: name.to_string(),
+            created: created.map(at),
+            modified: modified.map(at),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_most_recent() {
+        let mut dir = entry("dir", Some(900), Some(900));
+        dir.is_dir = true;
+        let entries = vec![
+            entry("old", Some(100), Some(500)),
+            entry("new", Some(300), Some(100)),
+            entry("none", None, Some(300)),
+            entry("mid", Some(200), None),
+            entry("tie", Some(300), None),
+            dir,
+        ];
+        let names = |found: Vec<&FileEntry>| -> Vec<String> {
+            found.into_iter().map(|e| e.name.clone()).collect()
+        };
+
+        assert_eq!(
+            names(most_recent(&entries, 3, RecentBy::Created)),
+            ["new", "tie", "mid"]
+        );
+        assert_eq!(
+            names(most_recent(&entries, 10, RecentBy::Modified)),
+            ["old", "none", "new"]
+        );
+        assert!(most_recent(&entries, 0, RecentBy::Created).is_empty());
+    }
+}
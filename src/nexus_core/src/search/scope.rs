@@ -0,0 +1,100 @@
+//! Folder scoping for the quick search
+//!
+//! Matches paths by prefix rather than by the `parent` field, so a scope
+//! works the same whether or not the host passed a trailing separator.
+
+/// A folder, and optionally everything below it
+///
+/// Case-insensitive on Windows, where both `\` and `/` separate components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathScope {
+    /// Folder path without trailing separators; lowercase on Windows
+    root: String,
+    recursive: bool,
+}
+
+impl PathScope {
+    /// Scope to the entries of `folder`, and of its subfolders if
+    /// `recursive`
+    pub fn new(folder: &str, recursive: bool) -> Self {
+        let root = folder.trim_end_matches(std::path::is_separator);
+        let root = if cfg!(windows) {
+            root.to_lowercase()
+        } else {
+            root.to_string()
+        };
+        Self { root, recursive }
+    }
+
+    /// Whether `path` is inside the scope; the folder itself is not
+    pub fn contains(&self, path: &str) -> bool {
+        let Some(rest) = self.strip_root(path) else {
+            return false;
+        };
+        let Some(rest) = rest.strip_prefix(std::path::is_separator) else {
+            return false;
+        };
+        !rest.is_empty() && (self.recursive || !rest.contains(std::path::is_separator))
+    }
+
+    /// `path` after the folder prefix, compared case-insensitively on Windows
+    fn strip_root<'a>(&self, path: &'a str) -> Option<&'a str> {
+        if !cfg!(windows) {
+            return path.strip_prefix(self.root.as_str());
+        }
+        if self.root.is_ascii() {
+            let head = path.get(..self.root.len())?;
+            return head
+                .eq_ignore_ascii_case(&self.root)
+                .then(|| &path[self.root.len()..]);
+        }
+        // Lowercasing can change byte lengths, so find the split point by
+        // walking the original path
+        let mut lowered = String::with_capacity(path.len());
+        for (i, c) in path.char_indices() {
+            if lowered == self.root {
+                return Some(&path[i..]);
+            }
+            if lowered.len() > self.root.len() {
+                return None;
+            }
+            lowered.extend(c.to_lowercase());
+        }
+        (lowered == self.root).then_some("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_contains() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let path = |parts: &[&str]| parts.join(&sep.to_string());
+        let folder = path(&["", "home", "docs"]);
+
+        let shallow = PathScope::new(&folder, false);
+        let deep = PathScope::new(&format!("{}{}", folder, sep), true);
+
+        let child = path(&["", "home", "docs", "a.txt"]);
+        let nested = path(&["", "home", "docs", "sub", "b.txt"]);
+        let sibling = path(&["", "home", "docs2", "c.txt"]);
+
+        assert!(shallow.contains(&child));
+        assert!(!shallow.contains(&nested));
+        assert!(deep.contains(&child));
+        assert!(deep.contains(&nested));
+
+        // Prefix must end at a component boundary
+        assert!(!deep.contains(&sibling));
+        assert!(!deep.contains(&folder));
+
+        if cfg!(windows) {
+            let scope = PathScope::new("C:\\Users\\Ärger\\", true);
+            assert!(scope.contains("c:\\users\\äRGER\\x.txt"));
+            assert!(scope.contains("C:/Users/Ärger/y/z.txt"));
+            assert!(PathScope::new("C:\\", false).contains("C:\\a.txt"));
+        }
+    }
+}
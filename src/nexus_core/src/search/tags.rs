@@ -0,0 +1,166 @@
+//! User-assigned file tags
+//!
+//! Tags live in the index documents for searching, and in a JSON file
+//! keyed by path so they survive the entry being re-indexed or the index
+//! being cleared and rebuilt.
+
+use crate::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Tags by path, saved to a JSON file on every change
+#[derive(Debug, Default)]
+pub struct TagStore {
+    /// `None` keeps the tags in memory only
+    file: Option<PathBuf>,
+    tags: parking_lot::RwLock<HashMap<String, Vec<String>>>,
+}
+
+impl TagStore {
+    /// Load the tags saved in `file`, starting empty if it doesn't exist
+    ///
+    /// A file that doesn't parse also starts the store empty, with a
+    /// warning; it is renamed to `<file>.bad` first so the next save
+    /// doesn't overwrite it.
+    pub fn open<P: AsRef<Path>>(file: P) -> Result<Self> {
+        let file = file.as_ref().to_path_buf();
+        let tags = match std::fs::read(&file) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                let mut bad = file.as_os_str().to_owned();
+                bad.push(".bad");
+                warn!(
+                    "Ignoring invalid tag store {} (kept as {:?}): {}",
+                    file.display(),
+                    bad,
+                    e
+                );
+                if let Err(e) = std::fs::rename(&file, &bad) {
+                    warn!("Failed to set aside {}: {}", file.display(), e);
+                }
+                HashMap::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            file: Some(file),
+            tags: parking_lot::RwLock::new(tags),
+        })
+    }
+
+    /// Tags of `path`, empty if it has none
+    pub fn get(&self, path: &str) -> Vec<String> {
+        self.tags.read().get(path).cloned().unwrap_or_default()
+    }
+
+    /// Replace the tags of `path` and save; no tags removes the path
+    ///
+    /// Tags are trimmed, and empty tags and case-insensitive repeats are
+    /// dropped. Returns the tags as stored.
+    pub fn set(&self, path: &str, tags: &[String]) -> Result<Vec<String>> {
+        let tags = normalize_tags(tags);
+        let mut all = self.tags.write();
+        if tags.is_empty() {
+            all.remove(path);
+        } else {
+            all.insert(path.to_string(), tags.clone());
+        }
+        self.save(&all)?;
+        Ok(tags)
+    }
+
+    /// Every tag in use with how many paths carry it, by tag
+    ///
+    /// Tags differing only in case are counted together under the spelling
+    /// that sorts first (`Invoice` before `invoice`).
+    pub fn all_tags(&self) -> BTreeMap<String, usize> {
+        let mut by_key: HashMap<String, (&str, usize)> = HashMap::new();
+        let tags = self.tags.read();
+        for tag in tags.values().flatten() {
+            let (spelling, count) = by_key.entry(tag.to_lowercase()).or_insert((tag, 0));
+            *spelling = (*spelling).min(tag.as_str());
+            *count += 1;
+        }
+        by_key
+            .into_values()
+            .map(|(spelling, count)| (spelling.to_string(), count))
+            .collect()
+    }
+
+    /// Write to a temporary file and rename it over the old one, so a crash
+    /// never leaves a truncated store
+    fn save(&self, tags: &HashMap<String, Vec<String>>) -> Result<()> {
+        let Some(ref file) = self.file else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec(tags)
+            .map_err(|e| crate::NexusError::Index(format!("Failed to save tags: {}", e)))?;
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+}
+
+/// Trim, drop empty tags and drop repeats that differ only in case
+pub(crate) fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    tags.iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tag_store() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("tags.json");
+        let tags = |list: &[&str]| -> Vec<String> { list.iter().map(|t| t.to_string()).collect() };
+
+        let store = TagStore::open(&file).unwrap();
+        assert_eq!(
+            store
+                .set(
+                    "C:\\a.pdf",
+                    &tags(&[" invoice ", "Project-X", "", "project-x"])
+                )
+                .unwrap(),
+            tags(&["invoice", "Project-X"])
+        );
+        store.set("C:\\b.pdf", &tags(&["INVOICE"])).unwrap();
+        store.set("C:\\c.pdf", &tags(&["temp"])).unwrap();
+        store.set("C:\\c.pdf", &[]).unwrap();
+
+        // Reloaded from disk
+        let store = TagStore::open(&file).unwrap();
+        assert_eq!(store.get("C:\\a.pdf"), tags(&["invoice", "Project-X"]));
+        assert!(store.get("C:\\c.pdf").is_empty());
+        let all = store.all_tags();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all.get("Project-X"), Some(&1));
+        assert_eq!(all.get("INVOICE"), Some(&2));
+    }
+
+    #[test]
+    fn test_tag_store_invalid_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("tags.json");
+        std::fs::write(&file, b"{not json").unwrap();
+
+        let store = TagStore::open(&file).unwrap();
+        assert!(store.all_tags().is_empty());
+        assert_eq!(
+            std::fs::read(dir.path().join("tags.json.bad")).unwrap(),
+            b"{not json"
+        );
+        store.set("C:\\a.pdf", &["x".to_string()]).unwrap();
+        assert_eq!(TagStore::open(&file).unwrap().get("C:\\a.pdf"), ["x"]);
+    }
+}
@@ -2,55 +2,141 @@
 //!
 //! Provides full-text search with fuzzy matching, filters, and ranking.
 
-use crate::{FileEntry, NexusError, Result};
-use std::path::Path;
+use super::tags::normalize_tags;
+use super::{FileTypeAliases, NameCollator, TagStore};
+use crate::{ExtensionMatch, FileEntry, NexusError, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tantivy::{
-    collector::TopDocs,
+    collector::{Count, TopDocs},
     directory::MmapDirectory,
     doc,
-    query::{FuzzyTermQuery, QueryParser, TermQuery},
-    schema::{Field, Schema, Value, FAST, STORED, TEXT},
-    Index, IndexReader, IndexWriter, ReloadPolicy, Term,
+    query::{
+        AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery, RegexQuery,
+        TermQuery,
+    },
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, FAST, STORED,
+        STRING, TEXT,
+    },
+    tokenizer::{LowerCaser, RawTokenizer, RegexTokenizer, TextAnalyzer, TokenStream},
+    DocAddress, DocId, Index, IndexReader, IndexWriter, ReloadPolicy, Score, Searcher,
+    SegmentReader, SnippetGenerator, Term,
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Search result with score
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SearchResult {
     pub entry: FileEntry,
     pub score: f32,
+    /// Path excerpt with matched terms wrapped in `<b>` tags; `None` when no
+    /// query term appears in the path (e.g. regex and fuzzy queries)
     pub snippet: Option<String>,
 }
 
 /// Search query options
-#[derive(Debug, Clone)]
+///
+/// Serialized as camelCase JSON; omitted fields take their defaults, so
+/// `{"query": "report"}` is a complete query.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct SearchQuery {
     /// Search query string
     pub query: String,
     /// Search type
     pub search_type: SearchType,
-    /// Maximum results
-    pub limit: usize,
-    /// File type filter
+    /// Maximum results; `None` returns every match
+    ///
+    /// Unbounded queries are counted first and collected in one pass sized
+    /// to the match count, but every match is still loaded and materialized,
+    /// so a broad query over a large index is expensive. Prefer a bounded
+    /// limit where possible.
+    pub limit: Option<usize>,
+    /// File type filter: extensions, or category names such as `image`
+    /// that expand through the engine's [`EngineOptions::file_type_aliases`]
     pub file_types: Option<Vec<String>>,
+    /// Compare `file_types` to the extension or as a name suffix
+    pub file_type_match: ExtensionMatch,
     /// Minimum file size
     pub min_size: Option<u64>,
     /// Maximum file size
     pub max_size: Option<u64>,
-    /// Drive filter
+    /// Drive filter; `'\\'` selects entries without a drive letter (UNC
+    /// shares and volume GUID paths), which are stored under that drive
     pub drives: Option<Vec<char>>,
+    /// Volume filter by volume GUID path; unlike `drives`, survives
+    /// drive-letter reassignment
+    pub volume_ids: Option<Vec<String>>,
     /// Only directories
     pub dirs_only: bool,
     /// Only files
     pub files_only: bool,
+    /// Preset modified-time bucket, evaluated in local time
+    pub time_bucket: Option<TimeBucket>,
+    /// Only entries with exactly this `content_hash`, in the format it was
+    /// indexed with: every copy of a file. With an empty `query` all
+    /// entries with the hash match.
+    pub hash_equals: Option<String>,
+    /// Only entries with a directory of exactly this name (case-insensitive)
+    /// somewhere above them: `node_modules` finds everything under any
+    /// `node_modules` folder, but nothing under `my_node_modules`. Needs an
+    /// engine opened with [`EngineOptions::path_components`]. With an empty
+    /// `query` every such entry matches.
+    pub directory_name: Option<String>,
+    /// Only entries carrying every one of these tags (case-insensitive).
+    /// With an empty `query` every such entry matches.
+    pub tags: Option<Vec<String>>,
+    /// Half-life in days for ranking recently modified entries higher;
+    /// `None` (the default) ranks by text relevance alone
+    ///
+    /// Each score becomes `score * 0.5^(age / half_life)`, with `age` the
+    /// days since the entry was modified (future times count as 0), so a
+    /// file modified `half_life` days ago keeps half its score. The decay
+    /// is applied while collecting, so `limit` keeps the best boosted
+    /// matches. Entries without a modified time score 0 and rank last.
+    /// Must be positive.
+    pub recency_boost: Option<f32>,
+    /// Result order. Sorting applies to the results kept after `limit`,
+    /// which are the top matches by relevance.
+    pub sort: SortBy,
+    /// BCP-47 locale for `SortBy::Name` (e.g. `"de"`); `None` uses the
+    /// natural comparator. Needs the `collation` feature to take effect.
+    pub locale: Option<String>,
+    /// Maximum characters per snippet fragment; must be non-zero
+    pub snippet_max_chars: usize,
+    /// Maximum number of snippet fragments, joined with `" … "`; must be
+    /// non-zero
+    pub snippet_fragments: usize,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Order of search results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortBy {
+    /// Best match first
+    #[default]
+    Relevance,
+    /// By name, A to Z
+    Name,
+    /// By name, Z to A
+    NameDescending,
+}
+
+/// Serialized as a lowercase string (`"fuzzy"`, `"glob"`, ...)
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SearchType {
     /// Full-text semantic search
     Semantic,
-    /// Glob pattern matching
+    /// Glob matching (`*`, `?`, `[abc]`, `[!abc]`) against the whole,
+    /// untokenized name, case-insensitive. `report_[0-9]*.pdf` matches
+    /// `Report_2023.pdf`.
     Glob,
     /// Regex pattern matching
     Regex,
@@ -58,6 +144,82 @@ pub enum SearchType {
     Exact,
     /// Fuzzy matching
     Fuzzy,
+    /// Wildcard matching (`*`, `?`) against the whole, untokenized name,
+    /// case-insensitive. `*report*` matches `annual_report_2023.pdf`.
+    Wildcard,
+    /// Names starting with the query, case-insensitive, taken literally:
+    /// `proj` matches `Project-X.docx` and `proj_notes.txt`, whatever the
+    /// tokenizer would make of them
+    Prefix,
+    /// Every word must appear in the name or the path, in any order and
+    /// not necessarily in the same one: `projects report` matches
+    /// `C:\projects\q3\report.docx`. Words are taken literally, without
+    /// query syntax.
+    PathAndName,
+}
+
+/// Preset modified-time ranges for search filtering
+///
+/// Boundaries are local midnights, so "Today" means since 00:00 in the
+/// user's timezone rather than since 00:00 UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeBucket {
+    Today,
+    Yesterday,
+    /// Since Monday of the current week
+    ThisWeek,
+    ThisMonth,
+    ThisYear,
+    /// Before January 1st of the current year
+    Older,
+}
+
+impl TimeBucket {
+    /// Unix-timestamp bounds of this bucket relative to `now`
+    pub fn bounds(&self, now: DateTime<Local>) -> (Bound<i64>, Bound<i64>) {
+        let today = now.date_naive();
+        let start_of = |date: NaiveDate| Bound::Included(local_midnight(date));
+
+        match self {
+            TimeBucket::Today => (start_of(today), Bound::Unbounded),
+            TimeBucket::Yesterday => (
+                start_of(today - Duration::days(1)),
+                Bound::Excluded(local_midnight(today)),
+            ),
+            TimeBucket::ThisWeek => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                (start_of(monday), Bound::Unbounded)
+            }
+            TimeBucket::ThisMonth => (
+                start_of(today.with_day(1).unwrap_or(today)),
+                Bound::Unbounded,
+            ),
+            TimeBucket::ThisYear => (
+                start_of(today.with_ordinal(1).unwrap_or(today)),
+                Bound::Unbounded,
+            ),
+            // Unknown modified times are stored as 0 and are not "older"
+            TimeBucket::Older => (
+                Bound::Excluded(0),
+                Bound::Excluded(local_midnight(today.with_ordinal(1).unwrap_or(today))),
+            ),
+        }
+    }
+}
+
+/// Unix timestamp of local midnight at the start of `date`
+fn local_midnight(date: NaiveDate) -> i64 {
+    let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    match Local.from_local_datetime(&naive).earliest() {
+        Some(dt) => dt.timestamp(),
+        // Midnight skipped by a DST transition; the day starts an hour later
+        None => Local
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+            .map(|dt| dt.timestamp())
+            .unwrap_or_else(|| naive.and_utc().timestamp()),
+    }
 }
 
 impl Default for SearchQuery {
@@ -65,13 +227,24 @@ impl Default for SearchQuery {
         Self {
             query: String::new(),
             search_type: SearchType::Semantic,
-            limit: 100,
+            limit: Some(100),
             file_types: None,
+            file_type_match: ExtensionMatch::Equals,
             min_size: None,
             max_size: None,
             drives: None,
+            volume_ids: None,
             dirs_only: false,
             files_only: false,
+            time_bucket: None,
+            hash_equals: None,
+            directory_name: None,
+            tags: None,
+            recency_boost: None,
+            sort: SortBy::Relevance,
+            locale: None,
+            snippet_max_chars: 150,
+            snippet_fragments: 1,
         }
     }
 }
@@ -79,9 +252,10 @@ impl Default for SearchQuery {
 /// Tantivy search engine
 pub struct SearchEngine {
     index: Index,
-    reader: IndexReader,
-    writer: Arc<parking_lot::Mutex<IndexWriter>>,
-    #[allow(dead_code)]
+    index_path: PathBuf,
+    pub(super) reader: IndexReader,
+    /// `None` for read-only engines
+    writer: Option<Arc<parking_lot::Mutex<IndexWriter>>>,
     schema: Schema,
     // Field references
     field_path: Field,
@@ -92,26 +266,132 @@ pub struct SearchEngine {
     field_drive: Field,
     field_parent: Field,
     field_modified: Field,
+    /// Absent from indexes created before wildcard search
+    field_name_raw: Option<Field>,
+    /// Absent from indexes created before volume ids
+    field_volume_id: Option<Field>,
+    /// Absent from indexes created before content hash lookup
+    field_content_hash: Option<Field>,
+    /// Present when the index was created with `path_components`
+    field_path_components: Option<Field>,
+    /// Exact path and tags; absent from indexes created before tags
+    field_path_exact: Option<Field>,
+    field_tags: Option<Field>,
+    /// Absent from indexes created before offline flags were stored
+    field_is_offline: Option<Field>,
+    /// Absent from indexes created before resolved paths were stored
+    field_resolved_path: Option<Field>,
+    /// Tags by path, reapplied whenever an entry is indexed
+    tags: TagStore,
+    file_type_aliases: FileTypeAliases,
+}
+
+/// Options for creating a search engine
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct EngineOptions {
+    /// Index each directory name of an entry's path as one token, for
+    /// [`SearchQuery::directory_name`]. Fixed when the index is created:
+    /// turning it on for an existing index has no effect until the index
+    /// directory is deleted and rebuilt.
+    pub path_components: bool,
+    /// Categories accepted in [`SearchQuery::file_types`]; the built-in
+    /// ones by default
+    pub file_type_aliases: FileTypeAliases,
+}
+
+/// Description of one search index field, for query-builder UIs
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FieldInfo {
+    pub name: String,
+    /// `"text"`, `"u64"`, `"i64"`, `"f64"`, `"bool"`, `"date"`, `"bytes"`,
+    /// `"json"`, `"facet"` or `"ip"`
+    pub kind: String,
+    /// Can be queried: indexed, or a fast field that range filters read
+    pub searchable: bool,
+    /// Fast field, so results can be sorted on it
+    pub sortable: bool,
+    /// Returned with results
+    pub stored: bool,
+}
+
+/// How the engine acquires the index writer lock
+enum WriterAccess {
+    /// Fail at once if another process holds it
+    Immediate,
+    /// Retry with backoff for up to the given time
+    Retry(std::time::Duration),
+    /// Don't take it: search only
+    ReadOnly,
 }
 
 impl SearchEngine {
     /// Create or open a search engine at the given path
+    ///
+    /// Fails if another process holds the index writer lock; see
+    /// [`SearchEngine::new_with_lock_retry`] and
+    /// [`SearchEngine::open_reader_only`].
     pub fn new<P: AsRef<Path>>(index_path: P) -> Result<Self> {
-        let index_path = index_path.as_ref();
+        Self::with_options(index_path, EngineOptions::default())
+    }
+
+    /// Create or open a search engine with the given options
+    pub fn with_options<P: AsRef<Path>>(index_path: P, options: EngineOptions) -> Result<Self> {
+        Self::open(index_path.as_ref(), WriterAccess::Immediate, &options)
+    }
+
+    /// Create or open a search engine, waiting up to `timeout` for another
+    /// process to release the index writer lock
+    pub fn new_with_lock_retry<P: AsRef<Path>>(
+        index_path: P,
+        timeout: std::time::Duration,
+    ) -> Result<Self> {
+        Self::open(
+            index_path.as_ref(),
+            WriterAccess::Retry(timeout),
+            &EngineOptions::default(),
+        )
+    }
 
-        // Create schema
-        let mut schema_builder = Schema::builder();
+    /// Open an existing index for searching only
+    ///
+    /// Takes no writer lock, so any number of searcher processes can run
+    /// alongside one indexer; commits by the indexer are picked up
+    /// automatically. `index_entries` and `clear` return an error.
+    pub fn open_reader_only<P: AsRef<Path>>(index_path: P) -> Result<Self> {
+        Self::open_reader_only_with(index_path, EngineOptions::default())
+    }
 
-        let field_path = schema_builder.add_text_field("path", TEXT | STORED);
-        let field_name = schema_builder.add_text_field("name", TEXT | STORED);
-        let field_extension = schema_builder.add_text_field("extension", TEXT | STORED);
-        let field_size = schema_builder.add_u64_field("size", FAST | STORED);
-        let field_is_dir = schema_builder.add_u64_field("is_dir", FAST | STORED);
-        let field_drive = schema_builder.add_text_field("drive", TEXT | STORED);
-        let field_parent = schema_builder.add_text_field("parent", TEXT | STORED);
-        let field_modified = schema_builder.add_i64_field("modified", FAST | STORED);
+    /// Open an existing index for searching only, with the given options
+    pub fn open_reader_only_with<P: AsRef<Path>>(
+        index_path: P,
+        options: EngineOptions,
+    ) -> Result<Self> {
+        let index_path = index_path.as_ref();
+        if !index_path.exists() {
+            return Err(NexusError::Index(format!(
+                "No index at {}",
+                index_path.display()
+            )));
+        }
+        Self::open(index_path, WriterAccess::ReadOnly, &options)
+    }
 
-        let schema = schema_builder.build();
+    fn open(index_path: &Path, access: WriterAccess, options: &EngineOptions) -> Result<Self> {
+        let schema = build_schema(options);
+        let field = |name: &str| {
+            schema
+                .get_field(name)
+                .map_err(|e| NexusError::Index(format!("Schema error: {}", e)))
+        };
+        let field_path = field("path")?;
+        let field_name = field("name")?;
+        let field_extension = field("extension")?;
+        let field_size = field("size")?;
+        let field_is_dir = field("is_dir")?;
+        let field_drive = field("drive")?;
+        let field_parent = field("parent")?;
+        let field_modified = field("modified")?;
 
         // Create or open index
         let index = if index_path.exists() {
@@ -125,6 +405,44 @@ impl SearchEngine {
             Index::create(dir, schema.clone(), tantivy::IndexSettings::default())
                 .map_err(|e| NexusError::Index(format!("Failed to create index: {}", e)))?
         };
+        index
+            .tokenizers()
+            .register(PATH_COMPONENTS_TOKENIZER, path_components_analyzer()?);
+        index.tokenizers().register(
+            TAG_TOKENIZER,
+            TextAnalyzer::builder(RawTokenizer::default())
+                .filter(LowerCaser)
+                .build(),
+        );
+
+        // Follow the index on disk, which may predate the requested options
+        let field_name_raw = index.schema().get_field("name_raw").ok();
+        if field_name_raw.is_none() {
+            warn!(
+                "Index at {:?} predates name_raw; rebuild it for wildcard and prefix search",
+                index_path
+            );
+        }
+        let field_volume_id = index.schema().get_field("volume_id").ok();
+        let field_content_hash = index.schema().get_field("content_hash").ok();
+        let field_path_components = index.schema().get_field("path_components").ok();
+        let field_path_exact = index.schema().get_field("path_exact").ok();
+        let field_tags = index.schema().get_field("tags").ok();
+        if field_tags.is_none() {
+            warn!(
+                "Index at {:?} predates tags; rebuild it to tag files",
+                index_path
+            );
+        }
+        let field_is_offline = index.schema().get_field("is_offline").ok();
+        let field_resolved_path = index.schema().get_field("resolved_path").ok();
+        let tags = TagStore::open(tag_store_path(index_path))?;
+        if options.path_components && field_path_components.is_none() {
+            warn!(
+                "Index at {:?} was created without path_components; directory_name queries will fail",
+                index_path
+            );
+        }
 
         // Create reader with auto-reload
         let reader = index
@@ -133,17 +451,19 @@ impl SearchEngine {
             .try_into()
             .map_err(|e| NexusError::Index(format!("Failed to create reader: {}", e)))?;
 
-        // Create writer with 50MB buffer
-        let writer = index
-            .writer(50_000_000)
-            .map_err(|e| NexusError::Index(format!("Failed to create writer: {}", e)))?;
+        let writer = match access {
+            WriterAccess::Immediate => Some(Self::acquire_writer(&index, None)?),
+            WriterAccess::Retry(timeout) => Some(Self::acquire_writer(&index, Some(timeout))?),
+            WriterAccess::ReadOnly => None,
+        };
 
         info!("Search engine initialized at {:?}", index_path);
 
         Ok(Self {
             index,
+            index_path: index_path.to_path_buf(),
             reader,
-            writer: Arc::new(parking_lot::Mutex::new(writer)),
+            writer: writer.map(|w| Arc::new(parking_lot::Mutex::new(w))),
             schema,
             field_path,
             field_name,
@@ -153,43 +473,292 @@ impl SearchEngine {
             field_drive,
             field_parent,
             field_modified,
+            field_name_raw,
+            field_volume_id,
+            field_content_hash,
+            field_path_components,
+            field_path_exact,
+            field_tags,
+            field_is_offline,
+            field_resolved_path,
+            tags,
+            file_type_aliases: options.file_type_aliases.clone(),
         })
     }
 
-    /// Index a batch of file entries
+    /// Categories accepted in [`SearchQuery::file_types`], for a UI to list
+    pub fn file_type_aliases(&self) -> &FileTypeAliases {
+        &self.file_type_aliases
+    }
+
+    /// Describe the fields of the open index
+    ///
+    /// Reflects the index on disk, so an index built by an older version
+    /// reports the schema it was built with.
+    pub fn schema_info(&self) -> Vec<FieldInfo> {
+        describe_schema(&self.index.schema())
+    }
+
+    /// Describe the fields of indexes created by this version
+    pub fn default_schema_info() -> Vec<FieldInfo> {
+        describe_schema(&build_schema(&EngineOptions::default()))
+    }
+
+    /// Create the writer with a 50MB buffer, retrying while the lock is
+    /// held elsewhere if `timeout` is given
+    fn acquire_writer(index: &Index, timeout: Option<std::time::Duration>) -> Result<IndexWriter> {
+        let deadline = timeout.map(|t| std::time::Instant::now() + t);
+        let mut backoff = std::time::Duration::from_millis(10);
+
+        loop {
+            match index.writer(50_000_000) {
+                Ok(writer) => return Ok(writer),
+                Err(tantivy::TantivyError::LockFailure(
+                    tantivy::directory::error::LockError::LockBusy,
+                    _,
+                )) => {
+                    let now = std::time::Instant::now();
+                    match deadline {
+                        Some(deadline) if now < deadline => {
+                            std::thread::sleep(backoff.min(deadline - now));
+                            backoff = (backoff * 2).min(std::time::Duration::from_millis(500));
+                        }
+                        _ => {
+                            return Err(NexusError::Index(
+                                "index locked by another process".to_string(),
+                            ))
+                        }
+                    }
+                }
+                Err(e) => return Err(NexusError::Index(format!("Failed to create writer: {}", e))),
+            }
+        }
+    }
+
+    /// Entries between progress reports of
+    /// [`index_entries_with_progress`](Self::index_entries_with_progress)
+    pub const PROGRESS_INTERVAL: usize = 1000;
+
+    fn writer(&self) -> Result<parking_lot::MutexGuard<'_, IndexWriter>> {
+        self.writer
+            .as_ref()
+            .map(|w| w.lock())
+            .ok_or_else(|| NexusError::Index("Search engine is read-only".to_string()))
+    }
+
+    /// Index a batch of file entries and commit
     pub fn index_entries(&self, entries: &[FileEntry]) -> Result<()> {
-        let mut writer = self.writer.lock();
+        self.index_entries_with_progress(entries, None, None)
+            .map(|_| ())
+    }
 
-        for entry in entries {
-            let modified_ts = entry.modified.map(|dt| dt.timestamp()).unwrap_or(0);
+    /// Index a batch of file entries with progress reporting and
+    /// cancellation, then commit
+    ///
+    /// `progress` is called with (entries added, total) every
+    /// [`PROGRESS_INTERVAL`](Self::PROGRESS_INTERVAL) entries and once at
+    /// the end. `cancel` is checked before each entry; once set, the
+    /// entries added so far are committed and the rest are skipped.
+    /// Returns how many entries were indexed.
+    pub fn index_entries_with_progress(
+        &self,
+        entries: &[FileEntry],
+        progress: Option<&dyn Fn(u64, u64)>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<usize> {
+        let total = entries.len() as u64;
+        let mut added = 0;
+        {
+            let writer = self.writer()?;
+            for entry in entries {
+                if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                    break;
+                }
+                self.add_document(&writer, entry)?;
+                added += 1;
+                if added % Self::PROGRESS_INTERVAL == 0 {
+                    if let Some(progress) = progress {
+                        progress(added as u64, total);
+                    }
+                }
+            }
+        }
+        self.flush()?;
 
-            writer
-                .add_document(doc!(
-                    self.field_path => entry.path.clone(),
-                    self.field_name => entry.name.clone(),
-                    self.field_extension => entry.extension.clone().unwrap_or_default(),
-                    self.field_size => entry.size,
-                    self.field_is_dir => if entry.is_dir { 1u64 } else { 0u64 },
-                    self.field_drive => entry.drive.to_string(),
-                    self.field_parent => entry.parent.clone(),
-                    self.field_modified => modified_ts,
-                ))
-                .map_err(|e| NexusError::Index(format!("Failed to add document: {}", e)))?;
+        if let Some(progress) = progress {
+            progress(added as u64, total);
         }
+        if added < entries.len() {
+            info!("Indexing cancelled after {} of {} entries", added, total);
+        } else {
+            info!("Indexed {} entries", added);
+        }
+        Ok(added)
+    }
+
+    /// Add one entry without committing
+    ///
+    /// The entry becomes searchable after the next [`flush`](Self::flush),
+    /// so a burst of watcher events can be added one by one and committed
+    /// once.
+    pub fn add_entry(&self, entry: &FileEntry) -> Result<()> {
+        let writer = self.writer()?;
+        self.add_document(&writer, entry)
+    }
+
+    /// Commit everything added since the last commit
+    pub fn flush(&self) -> Result<()> {
+        self.writer()?
+            .commit()
+            .map_err(|e| NexusError::Index(format!("Failed to commit: {}", e)))?;
+        Ok(())
+    }
+
+    /// Replace the tags of the entry at `path` (exact, as indexed)
+    ///
+    /// The tags are saved to the index's tag store first, so they come back
+    /// whenever `path` is indexed again, also after [`clear`](Self::clear);
+    /// a path that isn't indexed yet gets them once it is. An indexed entry
+    /// is deleted and re-added with the new tags, then committed. An empty
+    /// list removes all tags. Returns the tags as stored: trimmed, without
+    /// empty tags or repeats that differ only in case.
+    pub fn set_tags(&self, path: &str, tags: &[String]) -> Result<Vec<String>> {
+        let Some(field_path_exact) = self.field_path_exact.filter(|_| self.field_tags.is_some())
+        else {
+            return Err(NexusError::Index(
+                "Index predates tags; rebuild it to tag files".to_string(),
+            ));
+        };
+        let mut writer = self.writer()?;
+        let tags = self.tags.set(path, tags)?;
 
+        let term = Term::from_field_text(field_path_exact, path);
+        let searcher = self.reader.searcher();
+        let found = searcher
+            .search(
+                &TermQuery::new(term.clone(), IndexRecordOption::Basic),
+                &TopDocs::with_limit(1),
+            )
+            .map_err(|e| NexusError::Search(format!("Search failed: {}", e)))?;
+        let Some(&(_, address)) = found.first() else {
+            return Ok(tags);
+        };
+        let doc: tantivy::TantivyDocument = searcher
+            .doc(address)
+            .map_err(|e| NexusError::Search(format!("Failed to retrieve doc: {}", e)))?;
+
+        // The store now holds the new tags; add_document applies them
+        let entry = FileEntry {
+            tags: Vec::new(),
+            ..self.entry_from_doc(&doc)
+        };
+        writer.delete_term(term);
+        self.add_document(&writer, &entry)?;
         writer
             .commit()
             .map_err(|e| NexusError::Index(format!("Failed to commit: {}", e)))?;
+        Ok(tags)
+    }
+
+    /// Tags of `path` from the tag store, whether or not it's indexed
+    pub fn tags(&self, path: &str) -> Vec<String> {
+        self.tags.get(path)
+    }
 
-        info!("Indexed {} entries", entries.len());
+    /// Every tag in use with how many paths carry it
+    pub fn all_tags(&self) -> std::collections::BTreeMap<String, usize> {
+        self.tags.all_tags()
+    }
+
+    fn add_document(&self, writer: &IndexWriter, entry: &FileEntry) -> Result<()> {
+        let modified_ts = entry.modified.map(|dt| dt.timestamp()).unwrap_or(0);
+
+        let mut document = doc!(
+            self.field_path => entry.path.clone(),
+            self.field_name => entry.name.clone(),
+            self.field_extension => entry.extension.clone().unwrap_or_default(),
+            self.field_size => entry.size,
+            self.field_is_dir => if entry.is_dir { 1u64 } else { 0u64 },
+            self.field_drive => entry.drive.to_string(),
+            self.field_parent => entry.parent.clone(),
+            self.field_modified => modified_ts,
+        );
+        if let Some(field) = self.field_name_raw {
+            document.add_text(field, entry.name.to_lowercase());
+        }
+        if let Some(field) = self.field_volume_id {
+            document.add_text(field, entry.volume_id.as_deref().unwrap_or_default());
+        }
+        if let Some(field) = self.field_content_hash {
+            document.add_text(field, entry.content_hash.as_deref().unwrap_or_default());
+        }
+        if let Some(field) = self.field_path_components {
+            document.add_text(field, &entry.parent);
+        }
+        if let Some(field) = self.field_path_exact {
+            document.add_text(field, &entry.path);
+        }
+        if let Some(field) = self.field_is_offline {
+            document.add_u64(field, entry.is_offline as u64);
+        }
+        if let (Some(field), Some(resolved)) = (self.field_resolved_path, &entry.resolved_path) {
+            document.add_text(field, resolved);
+        }
+        if let Some(field) = self.field_tags {
+            let mut tags = entry.tags.clone();
+            tags.extend(self.tags.get(&entry.path));
+            for tag in normalize_tags(&tags) {
+                document.add_text(field, &tag);
+            }
+        }
+
+        writer
+            .add_document(document)
+            .map_err(|e| NexusError::Index(format!("Failed to add document: {}", e)))?;
         Ok(())
     }
 
-    /// Search for files
-    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let searcher = self.reader.searcher();
+    /// The `name_raw` field, or an error for indexes created without it
+    fn name_raw_field(&self) -> Result<Field> {
+        self.field_name_raw.ok_or_else(|| {
+            NexusError::Search(
+                "Index format changed: rebuild the index for wildcard and prefix search"
+                    .to_string(),
+            )
+        })
+    }
 
+    /// Require each token of `text` in the name or the path
+    fn path_and_name_query(&self, text: &str) -> Result<Box<dyn Query>> {
+        let mut analyzer = self
+            .index
+            .tokenizer_for_field(self.field_name)
+            .map_err(|e| NexusError::Search(format!("Tokenizer error: {}", e)))?;
+        let mut tokens = analyzer.token_stream(text);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        while let Some(token) = tokens.next() {
+            let either: Vec<(Occur, Box<dyn Query>)> = [self.field_name, self.field_path]
+                .into_iter()
+                .map(|field| {
+                    let term = TermQuery::new(
+                        Term::from_field_text(field, &token.text),
+                        tantivy::schema::IndexRecordOption::WithFreqs,
+                    );
+                    (Occur::Should, Box::new(term) as Box<dyn Query>)
+                })
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(either))));
+        }
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// Translate a `SearchQuery` into a Tantivy query
+    fn build_query(&self, query: &SearchQuery) -> Result<Box<dyn tantivy::query::Query>> {
+        let filter_only =
+            (query.hash_equals.is_some() || query.directory_name.is_some() || query.tags.is_some())
+                && query.query.trim().is_empty();
         let tantivy_query: Box<dyn tantivy::query::Query> = match query.search_type {
+            _ if filter_only => Box::new(AllQuery),
             SearchType::Exact => Box::new(TermQuery::new(
                 Term::from_field_text(self.field_name, &query.query),
                 tantivy::schema::IndexRecordOption::Basic,
@@ -201,20 +770,35 @@ impl SearchEngine {
                     true,
                 ))
             }
-            SearchType::Glob | SearchType::Regex => {
-                // Use regex query for glob patterns
-                let pattern = if query.search_type == SearchType::Glob {
-                    glob_to_regex(&query.query)
-                } else {
-                    query.query.clone()
-                };
-
+            SearchType::Glob => {
+                let pattern = glob_to_regex(&query.query.to_lowercase());
+                Box::new(
+                    RegexQuery::from_pattern(&pattern, self.name_raw_field()?)
+                        .map_err(|e| NexusError::Search(format!("Invalid glob: {}", e)))?,
+                )
+            }
+            SearchType::Regex => {
                 let query_parser =
                     QueryParser::for_index(&self.index, vec![self.field_name, self.field_path]);
                 query_parser
-                    .parse_query(&pattern)
+                    .parse_query(&query.query)
                     .map_err(|e| NexusError::Search(format!("Invalid query: {}", e)))?
             }
+            SearchType::Wildcard => {
+                let pattern = wildcard_to_regex(&query.query.to_lowercase());
+                Box::new(
+                    RegexQuery::from_pattern(&pattern, self.name_raw_field()?)
+                        .map_err(|e| NexusError::Search(format!("Invalid pattern: {}", e)))?,
+                )
+            }
+            SearchType::Prefix => {
+                let pattern = format!("{}.*", escape_regex(&query.query.to_lowercase()));
+                Box::new(
+                    RegexQuery::from_pattern(&pattern, self.name_raw_field()?)
+                        .map_err(|e| NexusError::Search(format!("Invalid prefix: {}", e)))?,
+                )
+            }
+            SearchType::PathAndName => self.path_and_name_query(&query.query)?,
             SearchType::Semantic => {
                 // Full-text search across name and path
                 let query_parser =
@@ -225,10 +809,468 @@ impl SearchEngine {
             }
         };
 
-        let top_docs = searcher
-            .search(&tantivy_query, &TopDocs::with_limit(query.limit))
-            .map_err(|e| NexusError::Search(format!("Search failed: {}", e)))?;
+        // Filters pushed into the query so `limit` counts only matching docs
+        let mut filters: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+        if let Some(ref hash) = query.hash_equals {
+            let field = self.field_content_hash.ok_or_else(|| {
+                NexusError::Search(
+                    "Index format changed: rebuild the index to search by hash".to_string(),
+                )
+            })?;
+            // Unhashed entries are indexed with an empty hash; never match them
+            let by_hash: Box<dyn Query> = if hash.is_empty() {
+                Box::new(BooleanQuery::new(Vec::new()))
+            } else {
+                Box::new(TermQuery::new(
+                    Term::from_field_text(field, hash),
+                    tantivy::schema::IndexRecordOption::Basic,
+                ))
+            };
+            filters.push((Occur::Must, by_hash));
+        }
+
+        if let Some(ref name) = query.directory_name {
+            let field = self.field_path_components.ok_or_else(|| {
+                NexusError::Search(
+                    "directory_name needs an index created with path_components".to_string(),
+                )
+            })?;
+            let name = name.trim_matches(['\\', '/']).to_lowercase();
+            filters.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(field, &name),
+                    tantivy::schema::IndexRecordOption::Basic,
+                )),
+            ));
+        }
+
+        if let Some(ref tags) = query.tags {
+            let field = self.field_tags.ok_or_else(|| {
+                NexusError::Search("tags need an index created with tag support".to_string())
+            })?;
+            for tag in normalize_tags(tags) {
+                filters.push((
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(field, &tag.to_lowercase()),
+                        tantivy::schema::IndexRecordOption::Basic,
+                    )),
+                ));
+            }
+        }
+
+        if let Some(bucket) = query.time_bucket {
+            let (lower, upper) = bucket.bounds(Local::now());
+            let range = RangeQuery::new_i64_bounds(
+                self.schema.get_field_name(self.field_modified).to_string(),
+                lower,
+                upper,
+            );
+            filters.push((Occur::Must, Box::new(range)));
+        }
+
+        let u64_range = |field: Field, lower: Bound<u64>, upper: Bound<u64>| -> Box<dyn Query> {
+            Box::new(RangeQuery::new_u64_bounds(
+                self.schema.get_field_name(field).to_string(),
+                lower,
+                upper,
+            ))
+        };
+        if query.files_only {
+            let files = u64_range(self.field_is_dir, Bound::Included(0), Bound::Included(0));
+            filters.push((Occur::Must, files));
+        }
+        if query.dirs_only {
+            let dirs = u64_range(self.field_is_dir, Bound::Included(1), Bound::Included(1));
+            filters.push((Occur::Must, dirs));
+        }
+        if query.min_size.is_some() || query.max_size.is_some() {
+            let size = u64_range(
+                self.field_size,
+                query.min_size.map_or(Bound::Unbounded, Bound::Included),
+                query.max_size.map_or(Bound::Unbounded, Bound::Included),
+            );
+            filters.push((Occur::Must, size));
+        }
+
+        if let Some(ref volumes) = query.volume_ids {
+            // Ids compare ignoring case, as `search` checks them
+            let any_volume: Vec<(Occur, Box<dyn Query>)> = match self.field_volume_id {
+                Some(field) => volumes
+                    .iter()
+                    .map(|v| {
+                        let pattern = format!("(?i){}", escape_regex(v));
+                        let query: Box<dyn Query> =
+                            Box::new(RegexQuery::from_pattern(&pattern, field).map_err(|e| {
+                                NexusError::Search(format!("Invalid volume id: {}", e))
+                            })?);
+                        Ok((Occur::Should, query))
+                    })
+                    .collect::<Result<_>>()?,
+                None => Vec::new(),
+            };
+            filters.push((Occur::Must, Box::new(BooleanQuery::new(any_volume))));
+        }
+
+        if let (Some(types), Some(field)) = (&query.file_types, self.field_name_raw) {
+            // Broader than `has_extension` only for non-ASCII case folding;
+            // `search` still checks each hit exactly
+            let any_type = self
+                .file_type_aliases
+                .expand(types)
+                .iter()
+                .map(|t| {
+                    let t = t.strip_prefix('.').unwrap_or(t).to_lowercase();
+                    let pattern = match query.file_type_match {
+                        // The extension is the last dot segment of a name
+                        // that doesn't start with it
+                        ExtensionMatch::Equals if t.contains('.') => return Ok(None),
+                        ExtensionMatch::Equals => format!(".+\\.{}", escape_regex(&t)),
+                        ExtensionMatch::Suffix => format!(".*\\.{}", escape_regex(&t)),
+                    };
+                    let query: Box<dyn Query> =
+                        Box::new(RegexQuery::from_pattern(&pattern, field).map_err(|e| {
+                            NexusError::Search(format!("Invalid file type: {}", e))
+                        })?);
+                    Ok(Some((Occur::Should, query)))
+                })
+                .filter_map(Result::transpose)
+                .collect::<Result<Vec<_>>>()?;
+            filters.push((Occur::Must, Box::new(BooleanQuery::new(any_type))));
+        }
+
+        if let Some(ref drives) = query.drives {
+            // The drive field is tokenized, so terms are lowercase
+            let drive_term = |d: char| -> Box<dyn Query> {
+                let term = Term::from_field_text(self.field_drive, &d.to_lowercase().to_string());
+                Box::new(TermQuery::new(
+                    term,
+                    tantivy::schema::IndexRecordOption::Basic,
+                ))
+            };
+            let any_drive: Vec<(Occur, Box<dyn Query>)> = drives
+                .iter()
+                .map(|&d| {
+                    if d != '\\' {
+                        return (Occur::Should, drive_term(d));
+                    }
+                    // `\` leaves no token, so match entries with no letter
+                    let mut no_letter: Vec<(Occur, Box<dyn Query>)> =
+                        vec![(Occur::Must, Box::new(AllQuery))];
+                    no_letter.extend(('a'..='z').map(|l| (Occur::MustNot, drive_term(l))));
+                    let query: Box<dyn Query> = Box::new(BooleanQuery::new(no_letter));
+                    (Occur::Should, query)
+                })
+                .collect();
+            // An empty Should-only BooleanQuery matches nothing, as intended
+            filters.push((Occur::Must, Box::new(BooleanQuery::new(any_drive))));
+        }
+
+        if filters.is_empty() {
+            return Ok(tantivy_query);
+        }
+        filters.insert(0, (Occur::Must, tantivy_query));
+        Ok(Box::new(BooleanQuery::new(filters)))
+    }
+
+    /// Check that `query` would run, without executing it
+    ///
+    /// Parses the query string, compiles wildcard and glob patterns and
+    /// checks the snippet options, returning the same error `search` would.
+    /// Cheap enough to call on every keystroke.
+    pub fn validate_query(&self, query: &SearchQuery) -> Result<()> {
+        self.compile(query).map(|_| ())
+    }
+
+    /// Validate options and build the Tantivy query
+    fn compile(&self, query: &SearchQuery) -> Result<Box<dyn Query>> {
+        if query.snippet_max_chars == 0 || query.snippet_fragments == 0 {
+            return Err(NexusError::Search(
+                "Snippet length and fragment count must be non-zero".to_string(),
+            ));
+        }
+        if let Some(half_life) = query.recency_boost {
+            if !(half_life.is_finite() && half_life > 0.0) {
+                return Err(NexusError::Search(format!(
+                    "recency_boost must be a positive number of days, got {}",
+                    half_life
+                )));
+            }
+        }
+        self.build_query(query)
+    }
+
+    /// Explain how a document scores for a query
+    ///
+    /// Returns Tantivy's scoring breakdown (BM25 terms, boosts, filters) as
+    /// pretty-printed JSON for the document at `path`. The document must be
+    /// among the query's top `limit` results.
+    pub fn explain(&self, query: &SearchQuery, path: &str) -> Result<String> {
+        let searcher = self.reader.searcher();
+        let tantivy_query = self.build_query(query)?;
+
+        let top_docs = Self::top_docs(
+            &searcher,
+            tantivy_query.as_ref(),
+            query.limit,
+            query.recency_boost,
+        )?;
+
+        for (_score, doc_address) in top_docs {
+            let doc: tantivy::TantivyDocument = searcher
+                .doc(doc_address)
+                .map_err(|e| NexusError::Search(format!("Failed to retrieve doc: {}", e)))?;
+
+            if doc.get_first(self.field_path).and_then(|v| v.as_str()) == Some(path) {
+                let explanation = tantivy_query
+                    .explain(&searcher, doc_address)
+                    .map_err(|e| NexusError::Search(format!("Explain failed: {}", e)))?;
+                return Ok(explanation.to_pretty_json());
+            }
+        }
+
+        let scope = match query.limit {
+            Some(limit) => format!("the top {} results", limit),
+            None => "the results".to_string(),
+        };
+        Err(NexusError::Search(format!(
+            "'{}' is not in {} for query '{}'",
+            path, scope, query.query
+        )))
+    }
+
+    /// Collect the top `limit` matches, or every match when `limit` is `None`
+    ///
+    /// The unbounded case counts matches first and then collects them in a
+    /// single pass, so the collector is never sized beyond the actual match
+    /// count.
+    fn top_docs(
+        searcher: &Searcher,
+        query: &dyn Query,
+        limit: Option<usize>,
+        recency_half_life: Option<f32>,
+    ) -> Result<Vec<(Score, DocAddress)>> {
+        let search_err =
+            |e: tantivy::TantivyError| NexusError::Search(format!("Search failed: {}", e));
+
+        let now = chrono::Utc::now().timestamp();
+        let collect = |limit: usize| {
+            let top = TopDocs::with_limit(limit);
+            let Some(half_life) = recency_half_life else {
+                return searcher.search(query, &top).map_err(search_err);
+            };
+            let half_life_secs = f64::from(half_life) * 86_400.0;
+
+            let decayed = top.tweak_score(move |segment: &SegmentReader| {
+                let modified = segment.fast_fields().i64("modified").ok();
+                move |doc: DocId, score: Score| {
+                    // 0 is stored for entries without a modified time
+                    match modified.as_ref().and_then(|m| m.first(doc)) {
+                        Some(ts) if ts != 0 => {
+                            let age = (now - ts).max(0) as f64;
+                            score * 0.5f64.powf(age / half_life_secs) as Score
+                        }
+                        _ => 0.0,
+                    }
+                }
+            });
+            searcher.search(query, &decayed).map_err(search_err)
+        };
+
+        let limit = match limit {
+            Some(limit) => limit,
+            None => searcher.search(query, &Count).map_err(search_err)?,
+        };
+        // TopDocs panics on a zero limit
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        collect(limit)
+    }
+
+    /// Rebuild an entry from its stored fields; times other than modified
+    /// and the attribute flags aren't stored
+    fn entry_from_doc(&self, doc: &tantivy::TantivyDocument) -> FileEntry {
+        let path = doc
+            .get_first(self.field_path)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let name = doc
+            .get_first(self.field_name)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let extension = doc
+            .get_first(self.field_extension)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty());
+
+        let size = doc
+            .get_first(self.field_size)
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+
+        let is_dir = doc
+            .get_first(self.field_is_dir)
+            .and_then(|v| v.as_u64())
+            .map(|v| v == 1)
+            .unwrap_or(false);
+
+        let drive = doc
+            .get_first(self.field_drive)
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.chars().next())
+            .unwrap_or('C');
+
+        let parent = doc
+            .get_first(self.field_parent)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let volume_id = self
+            .field_volume_id
+            .and_then(|field| doc.get_first(field))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        let content_hash = self
+            .field_content_hash
+            .and_then(|field| doc.get_first(field))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        let modified = doc
+            .get_first(self.field_modified)
+            .and_then(|v| v.as_i64())
+            .filter(|&ts| ts != 0)
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0));
+
+        let is_offline = self
+            .field_is_offline
+            .and_then(|field| doc.get_first(field))
+            .and_then(|v| v.as_u64())
+            == Some(1);
+
+        let resolved_path = self
+            .field_resolved_path
+            .and_then(|field| doc.get_first(field))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        let tags = self
+            .field_tags
+            .map(|field| {
+                doc.get_all(field)
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let volume = crate::indexer::volume_root(&path).unwrap_or_default();
+        FileEntry {
+            path,
+            name,
+            extension,
+            size,
+            created: None,
+            modified,
+            accessed: None,
+            is_dir,
+            is_hidden: false,
+            is_system: false,
+            is_offline,
+            content_hash,
+            parent,
+            drive,
+            volume,
+            volume_id,
+            resolved_path,
+            is_cycle: false,
+            tags,
+        }
+    }
+
+    /// Count the matches for `query` on each drive, without retrieving them
+    ///
+    /// Meant for drive filter chips, so the query's own `drives` filter is
+    /// ignored and every drive in the index gets a count, zero included.
+    /// The other filters apply as in [`search`](Self::search), except
+    /// `file_types` on an index that predates `name_raw`.
+    /// Keys are uppercase letters. Entries without a drive letter (UNC and
+    /// volume GUID paths) aren't counted.
+    pub fn counts_by_drive(&self, query: &SearchQuery) -> Result<HashMap<char, u64>> {
+        let all_drives = SearchQuery {
+            drives: None,
+            ..query.clone()
+        };
+        let tantivy_query = self.compile(&all_drives)?;
+        let searcher = self.reader.searcher();
+        let search_err =
+            |e: tantivy::TantivyError| NexusError::Search(format!("Search failed: {}", e));
+
+        // The drive terms present in any segment
+        let mut letters = BTreeSet::new();
+        for segment in searcher.segment_readers() {
+            let inverted = segment
+                .inverted_index(self.field_drive)
+                .map_err(search_err)?;
+            let mut terms = inverted.terms().stream()?;
+            while terms.advance() {
+                let mut chars = std::str::from_utf8(terms.key()).unwrap_or_default().chars();
+                if let (Some(letter), None) = (chars.next(), chars.next()) {
+                    letters.insert(letter);
+                }
+            }
+        }
+
+        let mut counts = HashMap::new();
+        for letter in letters {
+            // The drive field is tokenized, so terms are lowercase
+            let term = Term::from_field_text(self.field_drive, &letter.to_string());
+            let on_drive = BooleanQuery::new(vec![
+                (Occur::Must, tantivy_query.box_clone()),
+                (
+                    Occur::Must,
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+                ),
+            ]);
+            let count = searcher.search(&on_drive, &Count).map_err(search_err)?;
+            counts.insert(letter.to_ascii_uppercase(), count as u64);
+        }
+        Ok(counts)
+    }
+
+    /// Search for files
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let tantivy_query = self.compile(query)?;
+        let searcher = self.reader.searcher();
+
+        let top_docs = Self::top_docs(
+            &searcher,
+            tantivy_query.as_ref(),
+            query.limit,
+            query.recency_boost,
+        )?;
 
+        let mut snippets =
+            SnippetGenerator::create(&searcher, tantivy_query.as_ref(), self.field_path)
+                .map_err(|e| NexusError::Search(format!("Failed to create snippets: {}", e)))?;
+        snippets.set_max_num_chars(query.snippet_max_chars);
+
+        let file_types = query
+            .file_types
+            .as_deref()
+            .map(|types| self.file_type_aliases.expand(types));
         let mut results = Vec::new();
 
         for (score, doc_address) in top_docs {
@@ -236,102 +1278,52 @@ impl SearchEngine {
                 .doc(doc_address)
                 .map_err(|e| NexusError::Search(format!("Failed to retrieve doc: {}", e)))?;
 
-            let path = doc
-                .get_first(self.field_path)
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string();
-
-            let name = doc
-                .get_first(self.field_name)
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string();
-
-            let extension = doc
-                .get_first(self.field_extension)
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .filter(|s| !s.is_empty());
-
-            let size = doc
-                .get_first(self.field_size)
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-
-            let is_dir = doc
-                .get_first(self.field_is_dir)
-                .and_then(|v| v.as_u64())
-                .map(|v| v == 1)
-                .unwrap_or(false);
-
-            let drive = doc
-                .get_first(self.field_drive)
-                .and_then(|v| v.as_str())
-                .and_then(|s| s.chars().next())
-                .unwrap_or('C');
-
-            let parent = doc
-                .get_first(self.field_parent)
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string();
+            let entry = self.entry_from_doc(&doc);
 
             // Apply filters
-            if query.files_only && is_dir {
+            if query.files_only && entry.is_dir {
                 continue;
             }
-            if query.dirs_only && !is_dir {
+            if query.dirs_only && !entry.is_dir {
                 continue;
             }
             if let Some(min) = query.min_size {
-                if size < min {
+                if entry.size < min {
                     continue;
                 }
             }
             if let Some(max) = query.max_size {
-                if size > max {
+                if entry.size > max {
                     continue;
                 }
             }
-            if let Some(ref types) = query.file_types {
-                if let Some(ext) = &extension {
-                    if !types.iter().any(|t| t.eq_ignore_ascii_case(ext)) {
-                        continue;
-                    }
-                } else {
-                    continue;
+            if let Some(ref volumes) = query.volume_ids {
+                match &entry.volume_id {
+                    Some(id) if volumes.iter().any(|v| v.eq_ignore_ascii_case(id)) => {}
+                    _ => continue,
                 }
             }
-            if let Some(ref drives) = query.drives {
-                if !drives.contains(&drive) {
+
+            // Indexes without name_raw are only filtered here
+            if let Some(ref types) = file_types {
+                if !types
+                    .iter()
+                    .any(|t| entry.has_extension(t, query.file_type_match))
+                {
                     continue;
                 }
             }
 
-            let entry = FileEntry {
-                path,
-                name,
-                extension,
-                size,
-                created: None,
-                modified: None,
-                accessed: None,
-                is_dir,
-                is_hidden: false,
-                is_system: false,
-                content_hash: None,
-                parent,
-                drive,
-            };
-
+            let snippet = snippet_fragments(&snippets, &entry.path, query.snippet_fragments);
             results.push(SearchResult {
                 entry,
                 score,
-                snippet: None,
+                snippet,
             });
         }
 
+        sort_results(&mut results, query);
+
         debug!(
             "Search '{}' returned {} results",
             query.query,
@@ -342,7 +1334,7 @@ impl SearchEngine {
 
     /// Clear the entire index
     pub fn clear(&self) -> Result<()> {
-        let mut writer = self.writer.lock();
+        let mut writer = self.writer()?;
         writer
             .delete_all_documents()
             .map_err(|e| NexusError::Index(format!("Failed to clear index: {}", e)))?;
@@ -352,6 +1344,36 @@ impl SearchEngine {
         Ok(())
     }
 
+    /// Merge every segment into one and delete files no longer in use
+    ///
+    /// Reclaims the space of deleted documents and speeds up searches after
+    /// many incremental updates. The whole index is rewritten, which takes
+    /// time and temporary disk space proportional to its size and blocks
+    /// other writes to this engine meanwhile, so run it when idle. Entries
+    /// added since the last commit are not merged.
+    pub fn optimize(&self) -> Result<()> {
+        let mut writer = self.writer()?;
+        let segments = self
+            .index
+            .searchable_segment_metas()
+            .map_err(|e| NexusError::Index(format!("Failed to list segments: {}", e)))?;
+
+        if segments.len() > 1 || segments.iter().any(|s| s.has_deletes()) {
+            let ids: Vec<_> = segments.iter().map(|s| s.id()).collect();
+            writer
+                .merge(&ids)
+                .wait()
+                .map_err(|e| NexusError::Index(format!("Failed to merge segments: {}", e)))?;
+            info!("Merged {} segments", ids.len());
+        }
+
+        writer
+            .garbage_collect_files()
+            .wait()
+            .map_err(|e| NexusError::Index(format!("Failed to collect garbage: {}", e)))?;
+        Ok(())
+    }
+
     /// Get index statistics
     pub fn stats(&self) -> (u64, u64) {
         let searcher = self.reader.searcher();
@@ -359,37 +1381,1397 @@ impl SearchEngine {
         let num_segments = searcher.segment_readers().len() as u64;
         (num_docs, num_segments)
     }
+
+    /// Total size in bytes of the files in the index directory
+    ///
+    /// Includes segments no longer in use until they are garbage collected,
+    /// so it can drop after [`optimize`](Self::optimize).
+    pub fn index_size_bytes(&self) -> Result<u64> {
+        let mut total = 0;
+        for entry in walkdir::WalkDir::new(&self.index_path) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.file_type().is_file() {
+                total += entry.metadata().map_err(std::io::Error::from)?.len();
+            }
+        }
+        Ok(total)
+    }
 }
 
-/// Convert glob pattern to regex
-fn glob_to_regex(glob: &str) -> String {
-    let mut regex = String::new();
-    regex.push('^');
+/// Tokenizer of the `path_components` field
+const PATH_COMPONENTS_TOKENIZER: &str = "path_components";
 
-    for c in glob.chars() {
-        match c {
-            '*' => regex.push_str(".*"),
-            '?' => regex.push('.'),
-            '.' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' | '|' | '\\' => {
-                regex.push('\\');
-                regex.push(c);
-            }
-            _ => regex.push(c),
+/// Each tag as one lowercase token
+const TAG_TOKENIZER: &str = "tag";
+
+/// Tag store beside the index directory, `<index>.tags.json`
+///
+/// Kept outside it so deleting the index directory to rebuild it, as
+/// schema changes require, doesn't lose the tags.
+fn tag_store_path(index_path: &Path) -> PathBuf {
+    match (index_path.parent(), index_path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let mut file = name.to_owned();
+            file.push(".tags.json");
+            parent.join(file)
         }
+        // A root or `..` has no name to put the store beside
+        _ => index_path.join("tags.json"),
     }
+}
 
-    regex.push('$');
-    regex
+/// One lowercase token per path component, kept whole: `node_modules`
+/// stays one token where the default tokenizer would split it
+fn path_components_analyzer() -> Result<TextAnalyzer> {
+    let tokenizer = RegexTokenizer::new(r"[^\\/]+")
+        .map_err(|e| NexusError::Index(format!("Invalid tokenizer pattern: {}", e)))?;
+    Ok(TextAnalyzer::builder(tokenizer).filter(LowerCaser).build())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Schema of new indexes
+fn build_schema(options: &EngineOptions) -> Schema {
+    let mut schema_builder = Schema::builder();
 
-    #[test]
-    fn test_glob_to_regex() {
-        assert_eq!(glob_to_regex("*.txt"), "^.*\\.txt$");
-        assert_eq!(glob_to_regex("file?.txt"), "^file.\\.txt$");
-        assert_eq!(glob_to_regex("test"), "^test$");
+    schema_builder.add_text_field("path", TEXT | STORED);
+    schema_builder.add_text_field("name", TEXT | STORED);
+    schema_builder.add_text_field("extension", TEXT | STORED);
+    schema_builder.add_u64_field("size", FAST | STORED);
+    schema_builder.add_u64_field("is_dir", FAST | STORED);
+    schema_builder.add_text_field("drive", TEXT | STORED);
+    schema_builder.add_text_field("parent", TEXT | STORED);
+    schema_builder.add_i64_field("modified", FAST | STORED);
+    // Lowercased, untokenized name for wildcard/substring matching
+    schema_builder.add_text_field("name_raw", STRING);
+    schema_builder.add_text_field("volume_id", STRING | STORED);
+    // Exact-match only; empty when the entry wasn't hashed
+    schema_builder.add_text_field("content_hash", STRING | STORED);
+    if options.path_components {
+        // Directory names of the parent path; searchable only
+        let indexing = TextFieldIndexing::default()
+            .set_tokenizer(PATH_COMPONENTS_TOKENIZER)
+            .set_index_option(IndexRecordOption::Basic);
+        schema_builder.add_text_field(
+            "path_components",
+            TextOptions::default().set_indexing_options(indexing),
+        );
+    }
+    // Untokenized path, to find an entry's document again
+    schema_builder.add_text_field("path_exact", STRING);
+    let tag_indexing = TextFieldIndexing::default()
+        .set_tokenizer(TAG_TOKENIZER)
+        .set_index_option(IndexRecordOption::Basic);
+    schema_builder.add_text_field(
+        "tags",
+        TextOptions::default()
+            .set_indexing_options(tag_indexing)
+            .set_stored(),
+    );
+    // Cloud placeholder flag; returned with results only
+    schema_builder.add_u64_field("is_offline", STORED);
+    // Physical path behind a DFS namespace path, when resolved
+    schema_builder.add_text_field("resolved_path", STORED);
+
+    schema_builder.build()
+}
+
+fn describe_schema(schema: &Schema) -> Vec<FieldInfo> {
+    use tantivy::schema::FieldType;
+
+    schema
+        .fields()
+        .map(|(_, entry)| {
+            let kind = match entry.field_type() {
+                FieldType::Str(_) => "text",
+                FieldType::U64(_) => "u64",
+                FieldType::I64(_) => "i64",
+                FieldType::F64(_) => "f64",
+                FieldType::Bool(_) => "bool",
+                FieldType::Date(_) => "date",
+                FieldType::Facet(_) => "facet",
+                FieldType::Bytes(_) => "bytes",
+                FieldType::JsonObject(_) => "json",
+                FieldType::IpAddr(_) => "ip",
+            };
+            FieldInfo {
+                name: entry.name().to_string(),
+                kind: kind.to_string(),
+                searchable: entry.is_indexed() || entry.is_fast(),
+                sortable: entry.is_fast(),
+                stored: entry.is_stored(),
+            }
+        })
+        .collect()
+}
+
+/// Apply `query.sort` to results in relevance order
+pub(super) fn sort_results(results: &mut [SearchResult], query: &SearchQuery) {
+    if query.sort != SortBy::Relevance {
+        let collator = NameCollator::new(query.locale.as_deref());
+        results.sort_by(|a, b| collator.compare(&a.entry.name, &b.entry.name));
+        if query.sort == SortBy::NameDescending {
+            results.reverse();
+        }
+    }
+}
+
+/// Build up to `max_fragments` highlighted fragments of `text`, in text order
+///
+/// `SnippetGenerator` only yields the single best fragment, so the text on
+/// either side of each fragment found is searched again for more.
+fn snippet_fragments(
+    generator: &SnippetGenerator,
+    text: &str,
+    max_fragments: usize,
+) -> Option<String> {
+    let mut fragments: Vec<(usize, String)> = Vec::new();
+    let mut pending = vec![(0, text.len())];
+
+    while fragments.len() < max_fragments {
+        let Some((from, to)) = pending.pop() else {
+            break;
+        };
+        let segment = &text[from..to];
+        let snippet = generator.snippet(segment);
+        if snippet.is_empty() {
+            continue;
+        }
+        let Some(offset) = segment.find(snippet.fragment()) else {
+            continue;
+        };
+        let start = from + offset;
+        let end = start + snippet.fragment().len();
+        fragments.push((start, snippet.to_html()));
+        pending.push((from, start));
+        pending.push((end, to));
+    }
+
+    if fragments.is_empty() {
+        return None;
+    }
+    fragments.sort_by_key(|(start, _)| *start);
+    let fragments: Vec<String> = fragments.into_iter().map(|(_, html)| html).collect();
+    Some(fragments.join(" … "))
+}
+
+/// Convert a glob pattern to a Tantivy term regex
+///
+/// Supports `*`, `?` and `[...]` classes, negated with `[!...]`; a `]`
+/// first in a class is literal, as is a `[` that is never closed.
+/// Tantivy regexes always match the whole term, so no anchors are added.
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let negated = chars.get(i + 1) == Some(&'!');
+                let start = if negated { i + 2 } else { i + 1 };
+                // Search from after the first member so `[]]` works
+                let end = chars
+                    .get(start + 1..)
+                    .and_then(|rest| rest.iter().position(|&c| c == ']'))
+                    .map(|p| start + 1 + p);
+                let Some(end) = end else {
+                    push_escaped(&mut regex, '[');
+                    i += 1;
+                    continue;
+                };
+                regex.push('[');
+                if negated {
+                    regex.push('^');
+                }
+                for &c in &chars[start..end] {
+                    // Ranges keep their `-`; set operators are taken literally
+                    if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+                i = end;
+            }
+            c => push_escaped(&mut regex, c),
+        }
+        i += 1;
+    }
+
+    regex
+}
+
+/// Convert a wildcard pattern to a Tantivy term regex
+///
+/// Tantivy regexes always match the whole term, so no anchors are added.
+fn wildcard_to_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => push_escaped(&mut regex, c),
+        }
+    }
+
+    regex
+}
+
+/// Escape every regex metacharacter so `text` matches literally
+fn escape_regex(text: &str) -> String {
+    let mut regex = String::new();
+    for c in text.chars() {
+        push_escaped(&mut regex, c);
+    }
+    regex
+}
+
+fn push_escaped(regex: &mut String, c: char) {
+    if matches!(
+        c,
+        '.' | '*' | '?' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' | '|' | '\\'
+    ) {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(name: &str, modified: Option<chrono::DateTime<chrono::Utc>>) -> FileEntry {
+        FileEntry {
+            path: format!("C:\\data\\{}", name),
+            name: name.to_string(),
+            extension: name.rsplit('.').next().map(|s| s.to_string()),
+            size: 10,
+            modified,
+            parent: "C:\\data".to_string(),
+            drive: 'C',
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_time_bucket_bounds() {
+        // Wednesday afternoon
+        let now = Local.with_ymd_and_hms(2024, 6, 12, 15, 30, 0).unwrap();
+        let midnight = |y, m, d| {
+            Local
+                .with_ymd_and_hms(y, m, d, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+
+        assert_eq!(
+            TimeBucket::Today.bounds(now),
+            (Bound::Included(midnight(2024, 6, 12)), Bound::Unbounded)
+        );
+        assert_eq!(
+            TimeBucket::Yesterday.bounds(now),
+            (
+                Bound::Included(midnight(2024, 6, 11)),
+                Bound::Excluded(midnight(2024, 6, 12))
+            )
+        );
+        assert_eq!(
+            TimeBucket::ThisWeek.bounds(now).0,
+            Bound::Included(midnight(2024, 6, 10))
+        );
+        assert_eq!(
+            TimeBucket::ThisMonth.bounds(now).0,
+            Bound::Included(midnight(2024, 6, 1))
+        );
+        assert_eq!(
+            TimeBucket::ThisYear.bounds(now).0,
+            Bound::Included(midnight(2024, 1, 1))
+        );
+        assert_eq!(
+            TimeBucket::Older.bounds(now),
+            (Bound::Excluded(0), Bound::Excluded(midnight(2024, 1, 1)))
+        );
+    }
+
+    #[test]
+    fn test_wildcard_to_regex() {
+        assert_eq!(wildcard_to_regex("*report*"), ".*report.*");
+        assert_eq!(wildcard_to_regex("file?.txt"), "file.\\.txt");
+    }
+
+    #[test]
+    fn test_schema_info() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let info = engine.schema_info();
+        assert_eq!(info, SearchEngine::default_schema_info());
+
+        let field = |name: &str| info.iter().find(|f| f.name == name).unwrap();
+        assert_eq!(
+            field("size"),
+            &FieldInfo {
+                name: "size".to_string(),
+                kind: "u64".to_string(),
+                searchable: true,
+                sortable: true,
+                stored: true,
+            }
+        );
+        assert_eq!(field("name").kind, "text");
+        assert!(!field("name").sortable);
+        assert!(!field("name_raw").stored);
+    }
+
+    #[test]
+    fn test_recency_boost() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let now = chrono::Utc::now();
+        engine
+            .index_entries(&[
+                entry("report_old.txt", Some(now - Duration::days(60))),
+                entry("report_new.txt", Some(now - Duration::days(1))),
+                entry("report_undated.txt", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            search_type: SearchType::Prefix,
+            recency_boost: Some(7.0),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        let names: Vec<&str> = results.iter().map(|r| r.entry.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["report_new.txt", "report_old.txt", "report_undated.txt"]
+        );
+        // One day into a 7-day half-life
+        let unboosted = SearchQuery {
+            recency_boost: None,
+            ..query.clone()
+        };
+        let ratio = results[0].score / engine.search(&unboosted).unwrap()[0].score;
+        assert!((ratio - 0.5f32.powf(1.0 / 7.0)).abs() < 0.01);
+
+        assert!(engine
+            .search(&SearchQuery {
+                recency_boost: Some(0.0),
+                ..query
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_index_entries_progress_and_cancel() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let entries: Vec<FileEntry> = (0..2500)
+            .map(|i| entry(&format!("file{}.txt", i), None))
+            .collect();
+
+        let reports = parking_lot::Mutex::new(Vec::new());
+        let report = |done: u64, total: u64| reports.lock().push((done, total));
+        let added = engine
+            .index_entries_with_progress(&entries, Some(&report), None)
+            .unwrap();
+        assert_eq!(added, 2500);
+        assert_eq!(
+            reports.into_inner(),
+            vec![(1000, 2500), (2000, 2500), (2500, 2500)]
+        );
+
+        // Cancelled up front: nothing added, but still committed cleanly
+        let cancel = AtomicBool::new(true);
+        let added = engine
+            .index_entries_with_progress(&entries, None, Some(&cancel))
+            .unwrap();
+        assert_eq!(added, 0);
+        engine.reader.reload().unwrap();
+        assert_eq!(engine.stats().0, 2500);
+    }
+
+    #[test]
+    fn test_tags() {
+        let dir = tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        let engine = SearchEngine::new(&index_path).unwrap();
+        engine
+            .index_entries(&[entry("a.pdf", None), entry("b.pdf", None)])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let tags = |list: &[&str]| -> Vec<String> { list.iter().map(|t| t.to_string()).collect() };
+        engine
+            .set_tags("C:\\data\\a.pdf", &tags(&["Invoice", "project-x"]))
+            .unwrap();
+        engine
+            .set_tags("C:\\data\\b.pdf", &tags(&["invoice"]))
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let find = |query: &str, with: &[&str]| {
+            let query = SearchQuery {
+                query: query.to_string(),
+                tags: Some(tags(with)),
+                ..Default::default()
+            };
+            let mut results: Vec<FileEntry> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry)
+                .collect();
+            results.sort_by(|a, b| a.name.cmp(&b.name));
+            results
+        };
+
+        let invoices = find("", &["INVOICE"]);
+        assert_eq!(invoices.len(), 2);
+        assert_eq!(invoices[0].tags, tags(&["Invoice", "project-x"]));
+        assert_eq!(find("", &["invoice", "project-x"]).len(), 1);
+        assert_eq!(find("b", &["invoice"]).len(), 1);
+
+        // Tags survive clearing and re-indexing
+        engine.clear().unwrap();
+        engine.index_entries(&[entry("a.pdf", None)]).unwrap();
+        engine.reader.reload().unwrap();
+        assert_eq!(find("", &["project-x"]).len(), 1);
+        assert_eq!(engine.all_tags().get("Invoice"), Some(&2));
+
+        // Removing tags rewrites the document
+        engine.set_tags("C:\\data\\a.pdf", &[]).unwrap();
+        engine.reader.reload().unwrap();
+        assert!(find("", &["project-x"]).is_empty());
+        assert_eq!(engine.stats().0, 1);
+
+        // The store sits beside the index, so deleting the index keeps it
+        drop(engine);
+        std::fs::remove_dir_all(&index_path).unwrap();
+        let engine = SearchEngine::new(&index_path).unwrap();
+        assert_eq!(engine.all_tags().get("invoice"), Some(&1));
+    }
+
+    #[test]
+    fn test_directory_name() {
+        let dir = tempdir().unwrap();
+        let options = EngineOptions {
+            path_components: true,
+            ..Default::default()
+        };
+        let engine = SearchEngine::with_options(dir.path().join("index"), options).unwrap();
+        let at = |parent: &str, name: &str| FileEntry {
+            path: format!("{}\\{}", parent, name),
+            parent: parent.to_string(),
+            ..entry(name, None)
+        };
+        engine
+            .index_entries(&[
+                at("C:\\proj\\node_modules\\lodash", "index.js"),
+                at("C:\\proj\\Node_Modules", "package.json"),
+                at("C:\\proj\\my_node_modules", "index.js"),
+                at("C:\\proj\\src", "node_modules.txt"),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let find = |query: &str, name: &str| {
+            let query = SearchQuery {
+                query: query.to_string(),
+                directory_name: Some(name.to_string()),
+                ..Default::default()
+            };
+            let mut paths: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            find("", "node_modules"),
+            vec![
+                "C:\\proj\\Node_Modules\\package.json",
+                "C:\\proj\\node_modules\\lodash\\index.js",
+            ]
+        );
+        assert_eq!(
+            find("package", "node_modules\\"),
+            vec!["C:\\proj\\Node_Modules\\package.json"]
+        );
+
+        // Opt-in: a default engine rejects the filter
+        let plain = SearchEngine::new(dir.path().join("plain")).unwrap();
+        assert!(plain
+            .search(&SearchQuery {
+                directory_name: Some("src".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        for batch in 0..3 {
+            let entries: Vec<FileEntry> = (0..10)
+                .map(|i| entry(&format!("file{}_{}.txt", batch, i), None))
+                .collect();
+            engine.index_entries(&entries).unwrap();
+        }
+        engine.reader.reload().unwrap();
+        assert_eq!(engine.stats(), (30, 3));
+        assert!(engine.index_size_bytes().unwrap() > 0);
+
+        engine.optimize().unwrap();
+        engine.reader.reload().unwrap();
+        assert_eq!(engine.stats(), (30, 1));
+    }
+
+    #[test]
+    fn test_hash_equals() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let hashed = |name: &str, hash: Option<&str>| FileEntry {
+            content_hash: hash.map(|h| h.to_string()),
+            ..entry(name, None)
+        };
+        engine
+            .index_entries(&[
+                hashed("report.pdf", Some("aa11")),
+                hashed("report_copy.pdf", Some("aa11")),
+                hashed("other_report.pdf", Some("bb22")),
+                hashed("unhashed_report.pdf", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let find = |text: &str, hash: &str| {
+            let query = SearchQuery {
+                query: text.to_string(),
+                hash_equals: Some(hash.to_string()),
+                ..Default::default()
+            };
+            let mut names: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(find("", "aa11"), vec!["report.pdf", "report_copy.pdf"]);
+        assert_eq!(find("copy", "aa11"), vec!["report_copy.pdf"]);
+        assert!(find("", "AA11").is_empty());
+        assert!(find("", "").is_empty());
+    }
+
+    #[test]
+    fn test_offline_flag_stored() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let placeholder = FileEntry {
+            is_offline: true,
+            ..entry("cloud_report.txt", None)
+        };
+        engine
+            .index_entries(&[placeholder, entry("local_report.txt", None)])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        let offline = |name: &str| {
+            results
+                .iter()
+                .find(|r| r.entry.name == name)
+                .unwrap()
+                .entry
+                .is_offline
+        };
+        assert!(offline("cloud_report.txt"));
+        assert!(!offline("local_report.txt"));
+    }
+
+    #[test]
+    fn test_resolved_path_stored() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let resolved = "\\\\fs01\\projects\\report.txt".to_string();
+        let namespaced = FileEntry {
+            path: "\\\\corp\\dfs\\projects\\report.txt".to_string(),
+            resolved_path: Some(resolved.clone()),
+            ..entry("report.txt", None)
+        };
+        engine.index_entries(&[namespaced]).unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results[0].entry.resolved_path, Some(resolved));
+    }
+
+    #[test]
+    fn test_index_into_older_schema() {
+        let dir = tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        std::fs::create_dir_all(&index_path).unwrap();
+
+        // The schema indexes had before name_raw, volume_id and content_hash
+        let mut builder = Schema::builder();
+        builder.add_text_field("path", TEXT | STORED);
+        builder.add_text_field("name", TEXT | STORED);
+        builder.add_text_field("extension", TEXT | STORED);
+        builder.add_u64_field("size", FAST | STORED);
+        builder.add_u64_field("is_dir", FAST | STORED);
+        builder.add_text_field("drive", TEXT | STORED);
+        builder.add_text_field("parent", TEXT | STORED);
+        builder.add_i64_field("modified", FAST | STORED);
+        Index::create_in_dir(&index_path, builder.build()).unwrap();
+
+        let engine = SearchEngine::new(&index_path).unwrap();
+        let indexed = FileEntry {
+            volume_id: Some("\\\\?\\Volume{1234}\\".to_string()),
+            content_hash: Some("abc".to_string()),
+            ..entry("report.txt", None)
+        };
+        engine.index_entries(&[indexed]).unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.volume_id, None);
+        assert_eq!(results[0].entry.content_hash, None);
+
+        let wildcard = SearchQuery {
+            query: "rep*".to_string(),
+            search_type: SearchType::Wildcard,
+            ..Default::default()
+        };
+        assert!(engine.search(&wildcard).is_err());
+        let by_hash = SearchQuery {
+            hash_equals: Some("abc".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.search(&by_hash).is_err());
+    }
+
+    #[test]
+    fn test_add_entry_then_flush() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let query = SearchQuery {
+            query: "notes".to_string(),
+            ..Default::default()
+        };
+
+        engine.add_entry(&entry("notes.txt", None)).unwrap();
+        engine.add_entry(&entry("more_notes.txt", None)).unwrap();
+        engine.reader.reload().unwrap();
+        assert!(engine.search(&query).unwrap().is_empty());
+
+        engine.flush().unwrap();
+        engine.reader.reload().unwrap();
+        assert_eq!(engine.search(&query).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_prefix_search() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        engine
+            .index_entries(&[
+                entry("Project-X.docx", None),
+                entry("proj_notes.txt", None),
+                entry("my_project.txt", None),
+                entry("a+b (1).txt", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |prefix: &str| {
+            let query = SearchQuery {
+                query: prefix.to_string(),
+                search_type: SearchType::Prefix,
+                ..Default::default()
+            };
+            let mut names: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Start of the name only, across token boundaries, any case
+        assert_eq!(search("proj"), vec!["Project-X.docx", "proj_notes.txt"]);
+        assert_eq!(search("PROJECT-x"), vec!["Project-X.docx"]);
+        // Metacharacters are literal
+        assert_eq!(search("a+b ("), vec!["a+b (1).txt"]);
+        assert!(search("proj*").is_empty());
+    }
+
+    #[test]
+    fn test_path_and_name_search() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let at = |dir: &str, name: &str| FileEntry {
+            path: format!("{}\\{}", dir, name),
+            parent: dir.to_string(),
+            ..entry(name, None)
+        };
+        engine
+            .index_entries(&[
+                at("C:\\projects\\q3", "report.docx"),
+                at("C:\\Report", "projects_plan.txt"),
+                at("C:\\projects", "notes.txt"),
+                at("C:\\other", "report.docx"),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |text: &str, search_type: SearchType| {
+            let query = SearchQuery {
+                query: text.to_string(),
+                search_type,
+                ..Default::default()
+            };
+            let mut paths: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        // Every word, from the path or the name, in either order
+        let both = vec![
+            "C:\\Report\\projects_plan.txt".to_string(),
+            "C:\\projects\\q3\\report.docx".to_string(),
+        ];
+        assert_eq!(search("projects report", SearchType::PathAndName), both);
+        assert_eq!(search("REPORT Projects", SearchType::PathAndName), both);
+        assert_eq!(
+            search("projects report q3", SearchType::PathAndName),
+            vec!["C:\\projects\\q3\\report.docx"]
+        );
+        assert!(search("projects missing", SearchType::PathAndName).is_empty());
+
+        // Semantic search matches any of the words
+        assert_eq!(search("projects report", SearchType::Semantic).len(), 4);
+    }
+
+    #[test]
+    fn test_wildcard_search() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        engine
+            .index_entries(&[
+                entry("annual_report_2023.pdf", None),
+                entry("Report.docx", None),
+                entry("summary.txt", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |pattern: &str| {
+            let query = SearchQuery {
+                query: pattern.to_string(),
+                search_type: SearchType::Wildcard,
+                ..Default::default()
+            };
+            let mut names: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Middle of a single token
+        assert_eq!(
+            search("*report*"),
+            vec!["Report.docx", "annual_report_2023.pdf"]
+        );
+        // Leading wildcard
+        assert_eq!(search("*2023.pdf"), vec!["annual_report_2023.pdf"]);
+        // Trailing wildcard, case-insensitive
+        assert_eq!(search("REPORT*"), vec!["Report.docx"]);
+        // Wildcard between literals
+        assert_eq!(search("annual*2023*"), vec!["annual_report_2023.pdf"]);
+        assert_eq!(search("sum?ary.txt"), vec!["summary.txt"]);
+        assert!(search("*missing*").is_empty());
+    }
+
+    #[test]
+    fn test_explain() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        engine
+            .index_entries(&[entry("report.txt", None), entry("notes.txt", None)])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "report".to_string(),
+            ..Default::default()
+        };
+
+        let explanation = engine.explain(&query, "C:\\data\\report.txt").unwrap();
+        assert!(explanation.contains("value"));
+
+        let err = engine.explain(&query, "C:\\data\\notes.txt").unwrap_err();
+        assert!(err.to_string().contains("not in the top"));
+    }
+
+    #[test]
+    fn test_search_time_bucket() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+
+        let now = chrono::Utc::now();
+        engine
+            .index_entries(&[
+                entry("report_new.txt", Some(now)),
+                entry("report_old.txt", Some(now - Duration::days(800))),
+                entry("report_unknown.txt", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |bucket| {
+            let query = SearchQuery {
+                query: "txt".to_string(),
+                time_bucket: bucket,
+                ..Default::default()
+            };
+            let mut names: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(search(Some(TimeBucket::Today)), vec!["report_new.txt"]);
+        assert_eq!(search(Some(TimeBucket::Older)), vec!["report_old.txt"]);
+        assert_eq!(search(None).len(), 3);
+    }
+
+    #[test]
+    fn test_search_volume_filter() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+
+        let volume = "\\\\?\\Volume{1234}\\".to_string();
+        let mut tagged = entry("tagged.txt", None);
+        tagged.volume_id = Some(volume.clone());
+        engine
+            .index_entries(&[tagged, entry("untagged.txt", None)])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "txt".to_string(),
+            volume_ids: Some(vec![volume.to_uppercase()]),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.name, "tagged.txt");
+        assert_eq!(results[0].entry.volume_id.as_deref(), Some(volume.as_str()));
+    }
+
+    #[test]
+    fn test_unbounded_limit() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let entries: Vec<FileEntry> = (0..150)
+            .map(|i| entry(&format!("log_{}.txt", i), None))
+            .collect();
+        engine.index_entries(&entries).unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |limit| {
+            let query = SearchQuery {
+                query: "txt".to_string(),
+                limit,
+                ..Default::default()
+            };
+            engine.search(&query).unwrap().len()
+        };
+
+        assert_eq!(search(Some(100)), 100);
+        assert_eq!(search(None), 150);
+    }
+
+    #[test]
+    fn test_drive_filter_meets_limit() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let mut entries: Vec<FileEntry> = (0..50)
+            .map(|i| entry(&format!("c_{}.txt", i), None))
+            .collect();
+        entries.extend((0..5).map(|i| FileEntry {
+            drive: 'D',
+            ..entry(&format!("d_{}.txt", i), None)
+        }));
+        engine.index_entries(&entries).unwrap();
+        engine.reader.reload().unwrap();
+
+        let query = SearchQuery {
+            query: "txt".to_string(),
+            limit: Some(5),
+            drives: Some(vec!['d']),
+            ..Default::default()
+        };
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.entry.drive == 'D'));
+    }
+
+    #[test]
+    fn test_drive_filter_unc() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let share = FileEntry {
+            path: "\\\\server\\share\\report.txt".to_string(),
+            parent: "\\\\server\\share".to_string(),
+            drive: '\\',
+            ..entry("report.txt", None)
+        };
+        engine
+            .index_entries(&[entry("report.txt", None), share])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |drives: Vec<char>| {
+            let query = SearchQuery {
+                query: "report".to_string(),
+                drives: Some(drives),
+                ..Default::default()
+            };
+            let mut paths: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(search(vec!['\\']), vec!["\\\\server\\share\\report.txt"]);
+        assert_eq!(search(vec!['c']), vec!["C:\\data\\report.txt"]);
+        assert_eq!(search(vec!['C', '\\']).len(), 2);
+    }
+
+    #[test]
+    fn test_counts_by_drive() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let mut entries: Vec<FileEntry> = (0..4)
+            .map(|i| entry(&format!("report_{}.txt", i), None))
+            .collect();
+        entries.push(FileEntry {
+            drive: 'D',
+            ..entry("report_d.txt", None)
+        });
+        entries.push(FileEntry {
+            drive: 'E',
+            ..entry("notes.md", None)
+        });
+        entries.push(FileEntry {
+            drive: 'F',
+            ..entry("report_f.md", None)
+        });
+        engine.index_entries(&entries).unwrap();
+        engine.reader.reload().unwrap();
+
+        // The query's own drive filter doesn't hide the other drives
+        let query = SearchQuery {
+            query: "report".to_string(),
+            drives: Some(vec!['d']),
+            ..Default::default()
+        };
+        let counts = engine.counts_by_drive(&query).unwrap();
+        assert_eq!(
+            counts,
+            HashMap::from([('C', 4), ('D', 1), ('E', 0), ('F', 1)])
+        );
+
+        // The other filters narrow the counts as they do the results
+        let filtered = |query: SearchQuery| {
+            let counts = engine.counts_by_drive(&query).unwrap();
+            let found = engine.search(&query).unwrap().len() as u64;
+            assert_eq!(counts.values().sum::<u64>(), found);
+            counts
+        };
+        let md = filtered(SearchQuery {
+            query: "report".to_string(),
+            file_types: Some(vec!["md".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(md, HashMap::from([('C', 0), ('D', 0), ('E', 0), ('F', 1)]));
+        let dirs = filtered(SearchQuery {
+            query: "report".to_string(),
+            dirs_only: true,
+            ..Default::default()
+        });
+        assert_eq!(dirs.values().sum::<u64>(), 0);
+        let files = filtered(SearchQuery {
+            query: "report".to_string(),
+            files_only: true,
+            min_size: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(files[&'C'], 4);
+        let too_big = filtered(SearchQuery {
+            query: "report".to_string(),
+            min_size: Some(u64::MAX),
+            ..Default::default()
+        });
+        assert_eq!(too_big.values().sum::<u64>(), 0);
+    }
+
+    #[test]
+    fn test_file_type_filter_meets_limit() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let mut entries: Vec<FileEntry> = (0..50)
+            .map(|i| entry(&format!("report_{}.txt", i), None))
+            .collect();
+        entries.extend((0..5).map(|i| entry(&format!("report_{}.min.js", i), None)));
+        engine.index_entries(&entries).unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |types: &[&str], file_type_match| {
+            let query = SearchQuery {
+                query: "report".to_string(),
+                limit: Some(5),
+                file_types: Some(types.iter().map(|t| t.to_string()).collect()),
+                file_type_match,
+                ..Default::default()
+            };
+            engine.search(&query).unwrap()
+        };
+
+        let results = search(&["JS"], ExtensionMatch::Equals);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.entry.name.ends_with(".min.js")));
+        assert_eq!(search(&[".min.js"], ExtensionMatch::Suffix).len(), 5);
+        assert!(search(&["min.js"], ExtensionMatch::Equals).is_empty());
+    }
+
+    #[test]
+    fn test_file_type_suffix_match() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let with_ext = |name: &str| FileEntry {
+            extension: name.rsplit('.').next().map(str::to_string),
+            ..entry(name, None)
+        };
+        engine
+            .index_entries(&[with_ext("app.min.js"), with_ext("app.js")])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |file_type_match| {
+            let query = SearchQuery {
+                query: "app".to_string(),
+                file_types: Some(vec![".min.js".to_string()]),
+                file_type_match,
+                ..Default::default()
+            };
+            engine.search(&query).unwrap().len()
+        };
+
+        assert_eq!(search(ExtensionMatch::Equals), 0);
+        assert_eq!(search(ExtensionMatch::Suffix), 1);
+    }
+
+    #[test]
+    fn test_file_type_category() {
+        let dir = tempdir().unwrap();
+        let options = EngineOptions {
+            file_type_aliases: FileTypeAliases::default().with_alias("photos", ["jpg", "heic"]),
+            ..Default::default()
+        };
+        let engine = SearchEngine::with_options(dir.path().join("index"), options).unwrap();
+        let with_ext = |name: &str| FileEntry {
+            extension: name.rsplit('.').next().map(str::to_string),
+            ..entry(name, None)
+        };
+        engine
+            .index_entries(&[
+                with_ext("trip.jpg"),
+                with_ext("trip.heic"),
+                with_ext("trip.mkv"),
+                with_ext("trip.txt"),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |types: &[&str]| {
+            let query = SearchQuery {
+                query: "trip".to_string(),
+                file_types: Some(types.iter().map(|t| t.to_string()).collect()),
+                ..Default::default()
+            };
+            engine.search(&query).unwrap().len()
+        };
+
+        assert_eq!(search(&["photos"]), 2);
+        assert_eq!(search(&["Video", "txt"]), 2);
+        assert_eq!(search(&["image", "mkv"]), 3);
+    }
+
+    #[test]
+    fn test_sort_by_name() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        engine
+            .index_entries(&[
+                entry("notes10.txt", None),
+                entry("Notes2.txt", None),
+                entry("notes1.txt", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let names = |sort| {
+            let query = SearchQuery {
+                query: "txt".to_string(),
+                sort,
+                ..Default::default()
+            };
+            engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(SortBy::Name),
+            vec!["notes1.txt", "Notes2.txt", "notes10.txt"]
+        );
+        assert_eq!(
+            names(SortBy::NameDescending),
+            vec!["notes10.txt", "Notes2.txt", "notes1.txt"]
+        );
+    }
+
+    #[test]
+    fn test_search_query_json() {
+        let query: SearchQuery = serde_json::from_str(
+            r#"{"query": "report", "searchType": "fuzzy", "timeBucket": "thisWeek", "dirsOnly": true}"#,
+        )
+        .unwrap();
+        assert_eq!(query.query, "report");
+        assert_eq!(query.search_type, SearchType::Fuzzy);
+        assert_eq!(query.time_bucket, Some(TimeBucket::ThisWeek));
+        assert!(query.dirs_only);
+        assert_eq!(query.limit, SearchQuery::default().limit);
+
+        let json = serde_json::to_value(SearchQuery {
+            search_type: SearchType::Glob,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(json["searchType"], "glob");
+        assert!(json.get("fileTypes").is_some());
+    }
+
+    #[test]
+    fn test_snippet_options() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let mut deep = entry("budget.xlsx", None);
+        deep.path = format!("C:\\budget\\{}\\budget.xlsx", ["archive"; 12].join("\\"));
+        engine.index_entries(&[deep]).unwrap();
+        engine.reader.reload().unwrap();
+
+        let snippet = |snippet_max_chars, snippet_fragments| {
+            let query = SearchQuery {
+                query: "budget".to_string(),
+                snippet_max_chars,
+                snippet_fragments,
+                ..Default::default()
+            };
+            engine.search(&query).map(|r| r[0].snippet.clone().unwrap())
+        };
+
+        let single = snippet(20, 1).unwrap();
+        assert_eq!(single.matches("<b>budget</b>").count(), 1);
+        assert!(!single.contains(" … "));
+
+        let multi = snippet(20, 3).unwrap();
+        assert_eq!(multi.matches("<b>budget</b>").count(), 2);
+        assert!(multi.contains(" … "));
+
+        // The whole path fits in one default-sized fragment
+        assert_eq!(snippet(150, 1).unwrap().matches("<b>budget</b>").count(), 2);
+
+        assert!(snippet(0, 1).is_err());
+        assert!(snippet(150, 0).is_err());
+    }
+
+    #[test]
+    fn test_validate_query() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        let validate = |search_type: SearchType, text: &str| {
+            let query = SearchQuery {
+                query: text.to_string(),
+                search_type,
+                ..Default::default()
+            };
+            engine.validate_query(&query).map_err(|e| e.to_string())
+        };
+
+        assert!(validate(SearchType::Semantic, "annual report").is_ok());
+        let err = validate(SearchType::Semantic, "nosuchfield:x").unwrap_err();
+        assert!(err.contains("nosuchfield"));
+        assert!(validate(SearchType::Semantic, "(report").is_err());
+
+        assert!(validate(SearchType::Regex, "name:/rep.*/").is_ok());
+        assert!(validate(SearchType::Regex, "name:/[a-/").is_err());
+
+        // Patterns are escaped, so any text compiles
+        assert!(validate(SearchType::Wildcard, "*[report(*").is_ok());
+        assert!(validate(SearchType::Exact, "(report").is_ok());
+        assert!(validate(SearchType::Fuzzy, "(report").is_ok());
+
+        assert!(validate(SearchType::Glob, "report_[0-9]*.pdf").is_ok());
+        assert!(validate(SearchType::Glob, "[z-a]*").is_err());
+
+        // Validation reports exactly what search would
+        for text in ["*.txt", "report", "[z-a]"] {
+            let query = SearchQuery {
+                query: text.to_string(),
+                search_type: SearchType::Glob,
+                ..Default::default()
+            };
+            assert_eq!(
+                engine.validate_query(&query).is_ok(),
+                engine.search(&query).is_ok()
+            );
+        }
+
+        let query = SearchQuery {
+            snippet_fragments: 0,
+            ..Default::default()
+        };
+        assert!(engine.validate_query(&query).is_err());
+    }
+
+    #[test]
+    fn test_writer_lock() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index");
+        let writer = SearchEngine::new(&path).unwrap();
+        writer.index_entries(&[entry("report.txt", None)]).unwrap();
+
+        let err = SearchEngine::new_with_lock_retry(&path, std::time::Duration::from_millis(50))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("index locked by another process"));
+
+        // Readers need no lock and can't write
+        let reader = SearchEngine::open_reader_only(&path).unwrap();
+        reader.reader.reload().unwrap();
+        let query = SearchQuery {
+            query: "report".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(reader.search(&query).unwrap().len(), 1);
+        assert!(reader.index_entries(&[entry("x.txt", None)]).is_err());
+        assert!(SearchEngine::open_reader_only(dir.path().join("missing")).is_err());
+
+        // Released while waiting
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            drop(writer);
+        });
+        assert!(
+            SearchEngine::new_with_lock_retry(&path, std::time::Duration::from_secs(10)).is_ok()
+        );
+        release.join().unwrap();
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        assert_eq!(glob_to_regex("*.txt"), ".*\\.txt");
+        assert_eq!(glob_to_regex("file?.txt"), "file.\\.txt");
+        assert_eq!(glob_to_regex("test"), "test");
+        assert_eq!(glob_to_regex("v[0-9].[!a]"), "v[0-9]\\.[^a]");
+        assert_eq!(glob_to_regex("[]&]"), "[\\]\\&]");
+        assert_eq!(glob_to_regex("a[b"), "a\\[b");
+    }
+
+    #[test]
+    fn test_glob_search() {
+        let dir = tempdir().unwrap();
+        let engine = SearchEngine::new(dir.path().join("index")).unwrap();
+        engine
+            .index_entries(&[
+                entry("Report_2023.pdf", None),
+                entry("report_draft.pdf", None),
+                entry("report_2023.docx", None),
+            ])
+            .unwrap();
+        engine.reader.reload().unwrap();
+
+        let search = |pattern: &str| {
+            let query = SearchQuery {
+                query: pattern.to_string(),
+                search_type: SearchType::Glob,
+                ..Default::default()
+            };
+            let mut names: Vec<String> = engine
+                .search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.entry.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(search("report_[0-9]*.pdf"), vec!["Report_2023.pdf"]);
+        assert_eq!(search("report_[!0-9]*"), vec!["report_draft.pdf"]);
+        assert_eq!(search("*.pdf"), vec!["Report_2023.pdf", "report_draft.pdf"]);
+        assert_eq!(search("report_2023.doc?"), vec!["report_2023.docx"]);
     }
 }
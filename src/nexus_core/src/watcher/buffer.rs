@@ -0,0 +1,202 @@
+//! Bounded event buffer with backpressure policies
+//!
+//! `std::sync::mpsc` senders can't evict queued items, so the bounded mode
+//! uses its own queue: the watcher callback pushes, the consumer pops, and
+//! overflow is resolved on the producer side according to [`OverflowPolicy`].
+
+use super::WatchEvent;
+use parking_lot::{Condvar, Mutex};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// What to do when the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued event to make room
+    DropOldest,
+    /// Fold the new event into a queued event for the same paths and kind;
+    /// evict the oldest if nothing matches
+    Coalesce,
+}
+
+/// Bounded buffer configuration for [`super::FileWatcher::with_buffer`]
+#[derive(Debug, Clone, Copy)]
+pub struct WatchBuffer {
+    /// Maximum number of queued events
+    pub capacity: usize,
+    /// Overflow handling
+    pub policy: OverflowPolicy,
+}
+
+impl Default for WatchBuffer {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            policy: OverflowPolicy::Coalesce,
+        }
+    }
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<WatchEvent>>,
+    ready: Condvar,
+    dropped: AtomicU64,
+    closed: AtomicBool,
+}
+
+/// Producer half, owned by the watcher callback
+pub(super) struct BufferSender {
+    shared: Arc<Shared>,
+    config: WatchBuffer,
+}
+
+impl BufferSender {
+    pub(super) fn send(&self, event: WatchEvent) {
+        let mut queue = self.shared.queue.lock();
+
+        if queue.len() >= self.config.capacity.max(1) {
+            let merged = self.config.policy == OverflowPolicy::Coalesce
+                && queue
+                    .iter()
+                    .any(|e| e.kind == event.kind && e.paths == event.paths);
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+            if merged {
+                return;
+            }
+            queue.pop_front();
+        }
+
+        queue.push_back(event);
+        drop(queue);
+        self.shared.ready.notify_one();
+    }
+}
+
+impl Drop for BufferSender {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        self.shared.ready.notify_all();
+    }
+}
+
+/// Consumer half of a bounded watcher buffer
+pub struct WatchReceiver {
+    shared: Arc<Shared>,
+}
+
+impl WatchReceiver {
+    /// Block until an event arrives; `None` once the watcher is dropped and
+    /// the buffer is drained
+    pub fn recv(&self) -> Option<WatchEvent> {
+        let mut queue = self.shared.queue.lock();
+        loop {
+            if let Some(event) = queue.pop_front() {
+                return Some(event);
+            }
+            if self.shared.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            self.shared.ready.wait(&mut queue);
+        }
+    }
+
+    /// Wait up to `timeout` for an event
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<WatchEvent> {
+        let mut queue = self.shared.queue.lock();
+        if queue.is_empty() && !self.shared.closed.load(Ordering::Acquire) {
+            self.shared.ready.wait_for(&mut queue, timeout);
+        }
+        queue.pop_front()
+    }
+
+    /// Take an event if one is queued
+    pub fn try_recv(&self) -> Option<WatchEvent> {
+        self.shared.queue.lock().pop_front()
+    }
+
+    /// Number of events dropped or coalesced because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of events currently queued
+    pub fn len(&self) -> usize {
+        self.shared.queue.lock().len()
+    }
+
+    /// Whether the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.lock().is_empty()
+    }
+}
+
+/// Create a connected sender/receiver pair
+pub(super) fn bounded(config: WatchBuffer) -> (BufferSender, WatchReceiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::with_capacity(config.capacity.min(1024))),
+        ready: Condvar::new(),
+        dropped: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+    });
+    (
+        BufferSender {
+            shared: shared.clone(),
+            config,
+        },
+        WatchReceiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watcher::WatchEventKind;
+
+    fn event(path: &str, kind: WatchEventKind) -> WatchEvent {
+        WatchEvent {
+            paths: vec![path.to_string()],
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let (tx, rx) = bounded(WatchBuffer {
+            capacity: 2,
+            policy: OverflowPolicy::DropOldest,
+        });
+        tx.send(event("a", WatchEventKind::Create));
+        tx.send(event("b", WatchEventKind::Create));
+        tx.send(event("c", WatchEventKind::Create));
+
+        assert_eq!(rx.dropped(), 1);
+        assert_eq!(rx.try_recv().unwrap().paths, vec!["b"]);
+        assert_eq!(rx.try_recv().unwrap().paths, vec!["c"]);
+
+        drop(tx);
+        assert!(rx.recv().is_none());
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let (tx, rx) = bounded(WatchBuffer {
+            capacity: 2,
+            policy: OverflowPolicy::Coalesce,
+        });
+        tx.send(event("a", WatchEventKind::Modify));
+        tx.send(event("b", WatchEventKind::Modify));
+        // Matches a queued event: folded in, nothing evicted
+        tx.send(event("a", WatchEventKind::Modify));
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.try_recv().unwrap().paths, vec!["a"]);
+
+        tx.send(event("c", WatchEventKind::Modify));
+        // No match: falls back to evicting the oldest
+        tx.send(event("d", WatchEventKind::Remove));
+        assert_eq!(rx.dropped(), 2);
+        assert_eq!(rx.try_recv().unwrap().paths, vec!["c"]);
+        assert_eq!(rx.try_recv().unwrap().paths, vec!["d"]);
+    }
+}
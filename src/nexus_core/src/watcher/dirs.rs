@@ -0,0 +1,175 @@
+//! Directory-structure event filter
+//!
+//! Keeps only creates, removes and renames of directories. A removed path
+//! can no longer be stat'ed, so the filter tracks the set of known
+//! directories and decides removes (and the old side of renames) from it.
+
+use super::{WatchEvent, WatchEventKind};
+use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+use notify::{Event, EventKind};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Known directories under a watched root
+pub(super) struct DirTracker {
+    known: HashSet<PathBuf>,
+}
+
+impl DirTracker {
+    /// Seed with every directory under `root`
+    pub(super) fn new(root: &Path) -> Self {
+        let mut tracker = Self {
+            known: HashSet::new(),
+        };
+        tracker.add_tree(root);
+        tracker
+    }
+
+    /// Translate a raw event into a directory event, updating the known set
+    pub(super) fn filter(&mut self, event: &Event) -> Option<WatchEvent> {
+        let kind = match event.kind {
+            EventKind::Create(kind) => {
+                let dirs: Vec<&PathBuf> = event
+                    .paths
+                    .iter()
+                    .filter(|p| kind == CreateKind::Folder || p.is_dir())
+                    .collect();
+                for dir in &dirs {
+                    self.add_tree(dir);
+                }
+                return to_event(&dirs, WatchEventKind::Create);
+            }
+            EventKind::Remove(kind) => {
+                let dirs: Vec<&PathBuf> = event
+                    .paths
+                    .iter()
+                    .filter(|p| self.remove_tree(p) || kind == RemoveKind::Folder)
+                    .collect();
+                return to_event(&dirs, WatchEventKind::Remove);
+            }
+            EventKind::Modify(ModifyKind::Name(mode)) => mode,
+            _ => return None,
+        };
+
+        match (kind, event.paths.as_slice()) {
+            (RenameMode::Both, [from, to]) => {
+                if !self.known.contains(from) && !to.is_dir() {
+                    return None;
+                }
+                self.remove_tree(from);
+                self.add_tree(to);
+                to_event(&[from, to], WatchEventKind::Rename)
+            }
+            (RenameMode::From, [from]) => {
+                if !self.remove_tree(from) {
+                    return None;
+                }
+                to_event(&[from], WatchEventKind::Rename)
+            }
+            (_, [path]) => {
+                // Old name of a directory, or a new name that is one
+                if self.remove_tree(path) || path.is_dir() {
+                    self.add_tree(path);
+                    to_event(&[path], WatchEventKind::Rename)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn add_tree(&mut self, root: &Path) {
+        if !root.is_dir() {
+            return;
+        }
+        self.known.extend(
+            WalkDir::new(root)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_dir())
+                .map(|e| e.into_path()),
+        );
+    }
+
+    /// Forget `root` and everything below it; returns whether it was known
+    fn remove_tree(&mut self, root: &Path) -> bool {
+        let known = self.known.remove(root);
+        if known {
+            self.known.retain(|p| !p.starts_with(root));
+        }
+        known
+    }
+}
+
+fn to_event(paths: &[&PathBuf], kind: WatchEventKind) -> Option<WatchEvent> {
+    if paths.is_empty() {
+        return None;
+    }
+    Some(WatchEvent {
+        paths: paths.iter().map(|p| p.display().to_string()).collect(),
+        kind,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths
+            .iter()
+            .fold(Event::new(kind), |e, p| e.add_path(p.to_path_buf()))
+    }
+
+    #[test]
+    fn test_only_directory_changes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("a/nested")).unwrap();
+        std::fs::write(root.join("file.txt"), b"x").unwrap();
+        let mut tracker = DirTracker::new(root);
+
+        let any_create = EventKind::Create(CreateKind::Any);
+        let any_remove = EventKind::Remove(RemoveKind::Any);
+
+        // File writes and file creates are dropped
+        let write = EventKind::Modify(ModifyKind::Any);
+        assert!(tracker
+            .filter(&event(write, &[&root.join("file.txt")]))
+            .is_none());
+        assert!(tracker
+            .filter(&event(any_create, &[&root.join("file.txt")]))
+            .is_none());
+
+        std::fs::create_dir(root.join("b")).unwrap();
+        let created = tracker
+            .filter(&event(any_create, &[&root.join("b")]))
+            .unwrap();
+        assert_eq!(created.kind, WatchEventKind::Create);
+
+        // Removed paths can't be stat'ed: dir-ness comes from the known set
+        std::fs::remove_dir_all(root.join("a")).unwrap();
+        std::fs::remove_file(root.join("file.txt")).unwrap();
+        let removed = tracker
+            .filter(&event(any_remove, &[&root.join("a")]))
+            .unwrap();
+        assert_eq!(removed.kind, WatchEventKind::Remove);
+        assert!(!tracker.known.contains(&root.join("a/nested")));
+        assert!(tracker
+            .filter(&event(any_remove, &[&root.join("file.txt")]))
+            .is_none());
+
+        std::fs::rename(root.join("b"), root.join("c")).unwrap();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let renamed = tracker
+            .filter(&event(rename, &[&root.join("b"), &root.join("c")]))
+            .unwrap();
+        assert_eq!(renamed.kind, WatchEventKind::Rename);
+        assert_eq!(renamed.paths.len(), 2);
+        assert!(tracker.known.contains(&root.join("c")));
+        assert!(!tracker.known.contains(&root.join("b")));
+    }
+}
@@ -2,7 +2,14 @@
 //!
 //! Provides real-time file system change notifications using notify crate.
 
+mod buffer;
+mod dirs;
+
+pub use buffer::{OverflowPolicy, WatchBuffer, WatchReceiver};
+
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use std::sync::mpsc::{channel, Receiver};
 use tracing::info;
@@ -15,7 +22,7 @@ pub struct WatchEvent {
 }
 
 /// Types of watch events
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WatchEventKind {
     Create,
     Modify,
@@ -35,6 +42,19 @@ impl From<&notify::EventKind> for WatchEventKind {
     }
 }
 
+impl From<&Event> for WatchEvent {
+    fn from(event: &Event) -> Self {
+        WatchEvent {
+            paths: event
+                .paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect(),
+            kind: WatchEventKind::from(&event.kind),
+        }
+    }
+}
+
 /// File system watcher
 pub struct FileWatcher {
     _watcher: RecommendedWatcher,
@@ -42,21 +62,114 @@ pub struct FileWatcher {
 
 impl FileWatcher {
     /// Create a new file watcher for the given path
+    ///
+    /// The channel is unbounded: a consumer that falls behind during a burst
+    /// (e.g. extracting a large archive) lets it grow without limit. Use
+    /// [`FileWatcher::with_buffer`] to cap memory.
     pub fn new<P: AsRef<Path>>(path: P) -> crate::Result<(Self, Receiver<WatchEvent>)> {
         let (tx, rx) = channel();
+        let watcher = Self::watch_recursive(path.as_ref(), move |event| {
+            let _ = tx.send(WatchEvent::from(&event));
+        })?;
+        Ok((watcher, rx))
+    }
+
+    /// Create a watcher that only reports directory structure changes
+    ///
+    /// Emits creates, removes and renames (`WatchEventKind::Rename`, with
+    /// old and new paths when the platform pairs them) of directories under
+    /// `path`; file changes are dropped. Suited to folder-tree views.
+    /// Directories are enumerated once up front so removed ones can still
+    /// be recognised.
+    pub fn dirs_only<P: AsRef<Path>>(path: P) -> crate::Result<(Self, Receiver<WatchEvent>)> {
+        let (tx, rx) = channel();
+        let mut tracker = dirs::DirTracker::new(path.as_ref());
+        let watcher = Self::watch_recursive(path.as_ref(), move |event| {
+            if let Some(event) = tracker.filter(&event) {
+                let _ = tx.send(event);
+            }
+        })?;
+        Ok((watcher, rx))
+    }
 
+    /// Create a file watcher with a bounded event buffer
+    ///
+    /// When the buffer is full, events are dropped or coalesced according
+    /// to `buffer.policy`; [`WatchReceiver::dropped`] reports how many.
+    pub fn with_buffer<P: AsRef<Path>>(
+        path: P,
+        buffer: WatchBuffer,
+    ) -> crate::Result<(Self, WatchReceiver)> {
+        let (tx, rx) = buffer::bounded(buffer);
+        let watcher = Self::watch_recursive(path.as_ref(), move |event| {
+            tx.send(WatchEvent::from(&event))
+        })?;
+        Ok((watcher, rx))
+    }
+
+    fn watch_recursive<F>(path: &Path, mut send: F) -> crate::Result<Self>
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
         let mut watcher = RecommendedWatcher::new(
             move |res: Result<Event, notify::Error>| {
                 if let Ok(event) = res {
-                    let watch_event = WatchEvent {
-                        paths: event
-                            .paths
-                            .iter()
-                            .map(|p| p.display().to_string())
-                            .collect(),
-                        kind: WatchEventKind::from(&event.kind),
-                    };
-                    let _ = tx.send(watch_event);
+                    send(event);
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| crate::NexusError::Io(std::io::Error::other(e)))?;
+
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| crate::NexusError::Io(std::io::Error::other(e)))?;
+
+        info!("File watcher started for: {}", path.display());
+
+        Ok(Self { _watcher: watcher })
+    }
+
+    /// Follow a single growing file, `tail -f` style
+    ///
+    /// Starts at the current end of the file and emits each chunk of newly
+    /// appended bytes. If the file shrinks (truncation) or is replaced
+    /// (log rotation), reading restarts from the beginning of the new file.
+    pub fn tail<P: AsRef<Path>>(path: P) -> crate::Result<(Self, Receiver<Vec<u8>>)> {
+        let path = path.as_ref().to_path_buf();
+        let (tx, rx) = channel();
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| crate::NexusError::InvalidPath(path.display().to_string()))?
+            .to_owned();
+        // Watch the parent so rotation (remove + create) is still observed
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => std::path::PathBuf::from("."),
+        };
+
+        let mut offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let target = path.clone();
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                let Ok(event) = res else { return };
+                if !event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(file_name.as_os_str()))
+                {
+                    return;
+                }
+                match event.kind {
+                    notify::EventKind::Remove(_) => offset = 0,
+                    notify::EventKind::Create(_) | notify::EventKind::Modify(_) => {
+                        if let Some(bytes) = read_appended(&target, &mut offset) {
+                            let _ = tx.send(bytes);
+                        }
+                    }
+                    _ => {}
                 }
             },
             Config::default(),
@@ -64,11 +177,65 @@ impl FileWatcher {
         .map_err(|e| crate::NexusError::Io(std::io::Error::other(e)))?;
 
         watcher
-            .watch(path.as_ref(), RecursiveMode::Recursive)
+            .watch(&parent, RecursiveMode::NonRecursive)
             .map_err(|e| crate::NexusError::Io(std::io::Error::other(e)))?;
 
-        info!("File watcher started for: {}", path.as_ref().display());
+        info!("Tailing file: {}", path.display());
 
         Ok((Self { _watcher: watcher }, rx))
     }
 }
+
+/// Read everything past `offset` and advance it to EOF
+///
+/// Resets to the start of the file when `offset` is past EOF, i.e. the file
+/// was truncated or replaced by a shorter one. Returns `None` when there is
+/// nothing new or the file cannot be read.
+fn read_appended(path: &Path, offset: &mut u64) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+
+    if *offset > len {
+        *offset = 0;
+    }
+    if *offset == len {
+        return None;
+    }
+
+    file.seek(SeekFrom::Start(*offset)).ok()?;
+    let mut buffer = Vec::with_capacity((len - *offset) as usize);
+    file.read_to_end(&mut buffer).ok()?;
+    *offset += buffer.len() as u64;
+
+    Some(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_appended() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, b"first\n").unwrap();
+
+        let mut offset = 0;
+        assert_eq!(read_appended(&path, &mut offset).unwrap(), b"first\n");
+        assert_eq!(read_appended(&path, &mut offset), None);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"second\n").unwrap();
+        assert_eq!(read_appended(&path, &mut offset).unwrap(), b"second\n");
+
+        // Truncation resets to the start of the file
+        std::fs::write(&path, b"new\n").unwrap();
+        assert_eq!(read_appended(&path, &mut offset).unwrap(), b"new\n");
+        assert_eq!(offset, 4);
+    }
+}
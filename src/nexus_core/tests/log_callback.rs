@@ -0,0 +1,57 @@
+//! Forwarding of log output to the FFI log callback
+
+use nexus_core::ffi::{nexus_clear_log_callback, nexus_set_log_callback, nexus_set_log_level};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+static RECEIVED: Mutex<Vec<(i32, String)>> = Mutex::new(Vec::new());
+
+extern "C" fn on_log(level: i32, message: *const c_char) {
+    let message = unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned();
+    RECEIVED.lock().unwrap().push((level, message));
+}
+
+fn take_received() -> Vec<(i32, String)> {
+    std::mem::take(&mut *RECEIVED.lock().unwrap())
+}
+
+struct PanicsOnDebug;
+
+impl std::fmt::Debug for PanicsOnDebug {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        panic!("field formatting failed")
+    }
+}
+
+#[test]
+fn test_log_callback() {
+    assert!(nexus_set_log_callback(on_log));
+    nexus_set_log_level(2);
+
+    tracing::warn!(drive = "D", "drive needs admin");
+    tracing::info!("below the level");
+    let received = take_received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, 2);
+    assert!(received[0].1.ends_with("drive needs admin drive=\"D\""));
+
+    // NUL can't cross as a C string
+    tracing::error!("bad\0name");
+    assert!(take_received()[0].1.ends_with("bad name"));
+
+    // A panicking field is swallowed, and logging keeps working
+    tracing::error!(value = ?PanicsOnDebug, "unformattable");
+    tracing::error!("after the panic");
+    let received = take_received();
+    assert!(received.last().unwrap().1.ends_with("after the panic"));
+
+    nexus_set_log_level(0);
+    tracing::error!("logging off");
+    nexus_set_log_level(3);
+    nexus_clear_log_callback();
+    tracing::error!("no callback");
+    assert!(take_received().is_empty());
+}
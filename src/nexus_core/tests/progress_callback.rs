@@ -0,0 +1,46 @@
+//! Progress reporting through the FFI during indexing
+
+use nexus_core::ffi::{
+    nexus_clear_progress_callback, nexus_get_progress_current, nexus_index_directory,
+    nexus_is_indexing, nexus_set_progress_callback, nexus_set_progress_interval,
+};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static INDEXING_CALLS: AtomicU64 = AtomicU64::new(0);
+static MAX_CURRENT: AtomicU64 = AtomicU64::new(0);
+static ACTIVE_DURING_SCAN: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_progress(current: u64, _total: u64, phase: *const c_char) {
+    let phase = unsafe { CStr::from_ptr(phase) }.to_str().unwrap();
+    if phase == "indexing" {
+        INDEXING_CALLS.fetch_add(1, Ordering::SeqCst);
+        MAX_CURRENT.fetch_max(current, Ordering::SeqCst);
+        if nexus_is_indexing() {
+            ACTIVE_DURING_SCAN.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[test]
+fn test_progress_callback_fires_during_indexing() {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..5 {
+        std::fs::write(dir.path().join(format!("file{}.txt", i)), b"x").unwrap();
+    }
+    let path = CString::new(dir.path().to_str().unwrap()).unwrap();
+
+    nexus_set_progress_interval(1, 0);
+    nexus_set_progress_callback(on_progress);
+    let count = nexus_index_directory(path.as_ptr());
+    nexus_clear_progress_callback();
+
+    // The five files plus the directory itself
+    assert_eq!(count, 6);
+    assert!(INDEXING_CALLS.load(Ordering::SeqCst) >= 1);
+    assert_eq!(MAX_CURRENT.load(Ordering::SeqCst), 6);
+    assert!(ACTIVE_DURING_SCAN.load(Ordering::SeqCst));
+    assert!(!nexus_is_indexing());
+    assert_eq!(nexus_get_progress_current(), 6);
+}